@@ -0,0 +1,440 @@
+//! Selection of the file to install from the files of a project, following
+//! the rules pip applies: the highest matching version wins; yanked files are
+//! only considered when pinned with `==` (PEP592), and then only if no file
+//! that isn't yanked matches; files whose
+//! `Requires-Python` excludes the target interpreter are skipped; and among
+//! the files of the chosen version, wheels are preferred over sdists by the
+//! priority of their most preferred supported tag, then by build tag.
+//!
+//! For reproducible resolutions, files uploaded after a cutoff can be ignored,
+//! so that the selection is the one that would have been made at that time.
+
+use crate::error::Error;
+use crate::filename::{BuildTag, DistFilename};
+use crate::requires_python::RequiresPython;
+use crate::simple::File;
+use crate::specifier::{Operator, SpecifierSet};
+use crate::timestamp::Timestamp;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::cmp::Reverse;
+use std::fmt;
+
+/// A `(python, abi, platform)` tag triple, as returned by
+/// [`WheelFilename::tags`](crate::filename::WheelFilename::tags).
+pub type Tag = (String, String, String);
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+/// When pre-releases may be selected. Pre-releases are always allowed if a
+/// specifier explicitly mentions one, e.g. `>=2.0b1`.
+pub enum PrereleasePolicy {
+    /// Never select a pre-release.
+    Disallow,
+    /// Select pre-releases like any other version, like pip's `--pre`.
+    Allow,
+    /// Only select a pre-release if no final release matches.
+    #[default]
+    IfNecessary,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Why a file was not selected.
+pub enum Rejection {
+    /// The filename is neither a wheel nor an sdist.
+    InvalidFilename,
+    /// The version doesn't match the specifiers.
+    VersionMismatch,
+    /// The file was uploaded at the given time, after the cutoff.
+    UploadedAfter(Timestamp),
+    /// A cutoff was given, but the upload time of the file is absent or not
+    /// a valid timestamp.
+    UnknownUploadTime,
+    /// The file is yanked and the version wasn't pinned with `==`.
+    Yanked(Option<String>),
+    /// The file's `Requires-Python` excludes the target interpreter.
+    RequiresPython(RequiresPython),
+    /// The file is a wheel, none of whose tags are supported.
+    IncompatibleTags,
+    /// The version is a pre-release and pre-releases are not allowed.
+    Prerelease,
+    /// The file is acceptable, but another file was preferred.
+    Outranked,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::InvalidFilename => write!(f, "not a wheel or sdist"),
+            Rejection::VersionMismatch => write!(f, "version does not match the specifiers"),
+            Rejection::UploadedAfter(upload_time) => write!(f, "uploaded after the cutoff, at {}", upload_time),
+            Rejection::UnknownUploadTime => write!(f, "unknown upload time"),
+            Rejection::Yanked(None) => write!(f, "yanked"),
+            Rejection::Yanked(Some(reason)) => write!(f, "yanked ({})", reason),
+            Rejection::RequiresPython(requires_python) =>
+                write!(f, "requires Python {}", requires_python),
+            Rejection::IncompatibleTags => write!(f, "no supported wheel tag"),
+            Rejection::Prerelease => write!(f, "pre-release"),
+            Rejection::Outranked => write!(f, "a better candidate was selected"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// The criteria a file has to meet to be selected.
+pub struct CandidateSelector {
+    pub specifiers: SpecifierSet,
+    pub prereleases: PrereleasePolicy,
+    /// The tags supported by the target environment, most preferred first.
+    /// `None` accepts every wheel with equal priority.
+    pub tags: Option<Vec<Tag>>,
+    /// The version of the target interpreter, checked against
+    /// `Requires-Python`. `None` skips the check.
+    pub python_version: Option<Version>,
+    /// Ignore files uploaded after this time, as well as files without an
+    /// upload time, which only the JSON API provides.
+    pub exclude_newer: Option<Timestamp>,
+}
+
+#[derive(Clone, Debug)]
+/// The result of [`CandidateSelector::select`]. `F` is the type of the given
+/// files, e.g. [`File`] or an [`IndexFile`](crate::index::IndexFile).
+pub struct Selection<'a, F = File> {
+    /// The selected file, if any file was acceptable.
+    pub best: Option<&'a F>,
+    /// Every other file, with the reason it was not selected, in the order
+    /// the files were given.
+    pub rejected: Vec<(&'a F, Rejection)>,
+}
+
+/// The sort key of an acceptable file: whether it isn't yanked, then the
+/// version, then the preference for wheels by tag priority (`None` for
+/// sdists), then the build tag.
+type Rank = (bool, Version, Option<Reverse<usize>>, Option<BuildTag>);
+
+impl CandidateSelector {
+    /// Picks the best of `files`.
+    ///
+    /// ```
+    /// # use pep440::candidate::CandidateSelector;
+    /// # use pep440::simple::ProjectPage;
+    /// # use pep440::specifier::SpecifierSet;
+    /// let html = r#"
+    /// <a href="foo-1.0.tar.gz">foo-1.0.tar.gz</a>
+    /// <a href="foo-1.0-py3-none-any.whl">foo-1.0-py3-none-any.whl</a>
+    /// <a href="foo-1.1.tar.gz" data-yanked="">foo-1.1.tar.gz</a>
+    /// <a href="foo-2.0b1.tar.gz">foo-2.0b1.tar.gz</a>"#;
+    /// let page = ProjectPage::parse(html, "https://index.example/simple/foo/").unwrap();
+    /// let selector = CandidateSelector {
+    ///     specifiers: SpecifierSet::parse(">=1.0").unwrap(),
+    ///     ..Default::default()
+    /// };
+    /// let selection = selector.select(&page.files);
+    /// assert_eq!(selection.best.unwrap().filename, "foo-1.0-py3-none-any.whl");
+    /// for (file, rejection) in &selection.rejected {
+    ///     println!("{}: {}", file.filename, rejection);
+    /// }
+    /// ```
+    pub fn select<'a, F: AsRef<File>>(&self, files: &'a [F]) -> Selection<'a, F> {
+        let pinned = self.specifiers.specifiers.iter().any(|specifier| {
+            specifier.operator == Operator::ArbitraryEqual
+                || (specifier.operator == Operator::Equal && !specifier.wildcard)
+        });
+
+        let mut rejected = vec![];
+        let mut acceptable = vec![];
+        for (index, file) in files.iter().enumerate() {
+            match self.check(file.as_ref(), pinned) {
+                Ok(rank) => acceptable.push((index, rank)),
+                Err(rejection) => rejected.push((index, rejection)),
+            }
+        }
+
+        let allow_prereleases = match self.prereleases {
+            PrereleasePolicy::Allow => true,
+            _ if self.specifiers.prereleases() => true,
+            PrereleasePolicy::Disallow => false,
+            PrereleasePolicy::IfNecessary => acceptable.iter().all(|(_, rank)| rank.1.is_prerelease()),
+        };
+        if !allow_prereleases {
+            acceptable.retain(|(index, rank)| {
+                let prerelease = rank.1.is_prerelease();
+                if prerelease {
+                    rejected.push((*index, Rejection::Prerelease));
+                }
+                !prerelease
+            });
+        }
+
+        // Ties go to the earlier file, e.g. the one from the earlier index.
+        let best = acceptable
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by(|(_, (_, a)), (_, (_, b))| a.cmp(b))
+            .map(|(position, _)| position);
+        let best = best.map(|position| acceptable.remove(position).0);
+        rejected.extend(acceptable.into_iter().map(|(index, _)| (index, Rejection::Outranked)));
+
+        // Restore the order of the input, which the checks above shuffled.
+        rejected.sort_by_key(|(index, _)| *index);
+
+        Selection {
+            best: best.map(|index| &files[index]),
+            rejected: rejected.into_iter().map(|(index, rejection)| (&files[index], rejection)).collect(),
+        }
+    }
+
+    /// Like [`select`](CandidateSelector::select), but returns an error that
+    /// summarizes the rejections if no file is acceptable.
+    ///
+    /// ```
+    /// # use pep440::candidate::CandidateSelector;
+    /// # use pep440::simple::ProjectPage;
+    /// # use pep440::timestamp::Timestamp;
+    /// let html = r#"<a href="foo-1.0.tar.gz">foo-1.0.tar.gz</a>"#;
+    /// let mut files = ProjectPage::parse(html, "https://index.example/simple/foo/").unwrap().files;
+    /// files[0].upload_time = Some("2023-05-01T12:34:56Z".to_string());
+    ///
+    /// let selector = CandidateSelector {
+    ///     exclude_newer: Some(Timestamp::parse("2023-01-01").unwrap()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     selector.select_best(&files).unwrap_err().to_string(),
+    ///     "No matching distribution found: none of the 1 files of any version is acceptable \
+    ///      (1 uploaded after 2023-01-01T00:00:00Z)");
+    /// ```
+    pub fn select_best<'a, F: AsRef<File>>(&self, files: &'a [F]) -> Result<&'a F, Error> {
+        let selection = self.select(files);
+        if let Some(best) = selection.best {
+            return Ok(best);
+        }
+
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (_, rejection) in &selection.rejected {
+            let summary = match (rejection, &self.exclude_newer) {
+                (Rejection::UploadedAfter(_), Some(cutoff)) => format!("uploaded after {}", cutoff),
+                (Rejection::UploadedAfter(_), None) => "uploaded after the cutoff".to_string(),
+                (Rejection::UnknownUploadTime, _) => "without an upload time".to_string(),
+                (Rejection::Yanked(_), _) => "yanked".to_string(),
+                (Rejection::RequiresPython(_), _) => "excluded by Requires-Python".to_string(),
+                (rejection, _) => rejection.to_string(),
+            };
+            *counts.entry(summary).or_default() += 1;
+        }
+        let specifiers = if self.specifiers.is_empty() {
+            "any version".to_string()
+        } else {
+            format!("`{}`", self.specifiers)
+        };
+        let mut reason = format!("none of the {} files of {} is acceptable", files.len(), specifiers);
+        if !counts.is_empty() {
+            let counts: Vec<String> = counts.iter().map(|(summary, count)| format!("{} {}", count, summary)).collect();
+            reason.push_str(&format!(" ({})", counts.join(", ")));
+        }
+        Err(Error::no_matching_distribution(reason))
+    }
+
+    fn check(&self, file: &File, pinned: bool) -> Result<Rank, Rejection> {
+        let dist = file.dist.as_ref().ok_or(Rejection::InvalidFilename)?;
+        if !self.specifiers.contains_with(dist.version(), true) {
+            return Err(Rejection::VersionMismatch);
+        }
+        if let Some(cutoff) = &self.exclude_newer {
+            let upload_time = file
+                .upload_time
+                .as_deref()
+                .and_then(Timestamp::parse)
+                .ok_or(Rejection::UnknownUploadTime)?;
+            if upload_time > *cutoff {
+                return Err(Rejection::UploadedAfter(upload_time));
+            }
+        }
+        if file.yanked && !pinned {
+            return Err(Rejection::Yanked(file.yanked_reason.clone()));
+        }
+        if let (Some(requires_python), Some(python)) = (&file.requires_python, &self.python_version) {
+            if !requires_python.contains(python) {
+                return Err(Rejection::RequiresPython(requires_python.clone()));
+            }
+        }
+        match dist {
+            DistFilename::Sdist(sdist) => Ok((!file.yanked, sdist.version.clone(), None, None)),
+            DistFilename::Wheel(wheel) => {
+                let priority = match &self.tags {
+                    None => 0,
+                    Some(supported) => wheel
+                        .tags()
+                        .iter()
+                        .filter_map(|tag| supported.iter().position(|supported| supported == tag))
+                        .min()
+                        .ok_or(Rejection::IncompatibleTags)?,
+                };
+                Ok((!file.yanked, wheel.version.clone(), Some(Reverse(priority)), wheel.build_tag.clone()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::candidate::*;
+    use crate::simple::ProjectPage;
+
+    fn files(filenames: &[&str]) -> Vec<File> {
+        let html: String = filenames
+            .iter()
+            .map(|filename| format!("<a href=\"{}\">{}</a>\n", filename, filename))
+            .collect();
+        ProjectPage::parse(&html, "https://index.example/simple/foo/").unwrap().files
+    }
+
+    fn tag(tag: &str) -> Tag {
+        let parts: Vec<&str> = tag.split('-').collect();
+        (parts[0].to_string(), parts[1].to_string(), parts[2].to_string())
+    }
+
+    fn selector(specifiers: &str) -> CandidateSelector {
+        CandidateSelector {
+            specifiers: SpecifierSet::parse(specifiers).unwrap(),
+            tags: Some(vec![
+                tag("cp312-cp312-manylinux_2_17_x86_64"),
+                tag("cp312-abi3-manylinux_2_17_x86_64"),
+                tag("py3-none-manylinux_2_17_x86_64"),
+                tag("py3-none-any"),
+            ]),
+            python_version: Some(Version::parse("3.12.1").unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn best(selector: &CandidateSelector, files: &[File]) -> Option<String> {
+        selector.select(files).best.map(|file| file.filename.clone())
+    }
+
+    #[test]
+    fn test_tag_priority() {
+        let files = files(&[
+            "foo-1.0.tar.gz",
+            "foo-1.0-py3-none-any.whl",
+            "foo-1.0-cp312-abi3-manylinux_2_17_x86_64.whl",
+            "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+            "foo-0.9-cp312-cp312-manylinux_2_17_x86_64.whl",
+        ]);
+        let selection = selector("").select(&files);
+        assert_eq!(selection.best.unwrap().filename, "foo-1.0-cp312-abi3-manylinux_2_17_x86_64.whl");
+        let reasons: Vec<&Rejection> = selection.rejected.iter().map(|(_, rejection)| rejection).collect();
+        assert_eq!(reasons, vec![
+            &Rejection::Outranked,
+            &Rejection::Outranked,
+            &Rejection::IncompatibleTags,
+            &Rejection::Outranked,
+        ]);
+
+        // Without tags, every wheel is equally good but still beats the sdist.
+        let selector = CandidateSelector { tags: None, ..selector("==1.0") };
+        assert!(best(&selector, &files).unwrap().ends_with(".whl"));
+    }
+
+    #[test]
+    fn test_build_tag() {
+        let files = files(&[
+            "foo-1.0-1-py3-none-any.whl",
+            "foo-1.0-2-py3-none-any.whl",
+            "foo-1.0-py3-none-any.whl",
+        ]);
+        assert_eq!(best(&selector(""), &files).unwrap(), "foo-1.0-2-py3-none-any.whl");
+    }
+
+    #[test]
+    fn test_prereleases() {
+        let files = files(&["foo-1.0.tar.gz", "foo-2.0b1.tar.gz"]);
+        assert_eq!(best(&selector(""), &files).unwrap(), "foo-1.0.tar.gz");
+        assert_eq!(best(&selector(">=2.0b1"), &files).unwrap(), "foo-2.0b1.tar.gz");
+        assert_eq!(best(&selector(">1.0"), &files).unwrap(), "foo-2.0b1.tar.gz");
+
+        let allow = CandidateSelector { prereleases: PrereleasePolicy::Allow, ..selector("") };
+        assert_eq!(best(&allow, &files).unwrap(), "foo-2.0b1.tar.gz");
+
+        let disallow = CandidateSelector { prereleases: PrereleasePolicy::Disallow, ..selector(">1.0") };
+        let selection = disallow.select(&files);
+        assert!(selection.best.is_none());
+        assert_eq!(selection.rejected[1].1, Rejection::Prerelease);
+    }
+
+    #[test]
+    fn test_yanked_and_requires_python() {
+        let page = ProjectPage::parse(r#"
+<a href="foo-1.0.tar.gz">foo-1.0.tar.gz</a>
+<a href="foo-1.1.tar.gz" data-yanked="oops">foo-1.1.tar.gz</a>
+<a href="foo-1.2.tar.gz" data-requires-python="&gt;=3.13">foo-1.2.tar.gz</a>
+<a href="foo-1.0.win32.exe">foo-1.0.win32.exe</a>"#,
+            "https://index.example/simple/foo/").unwrap();
+
+        let selection = selector("").select(&page.files);
+        assert_eq!(selection.best.unwrap().filename, "foo-1.0.tar.gz");
+        let reasons: Vec<String> = selection.rejected.iter().map(|(_, rejection)| rejection.to_string()).collect();
+        assert_eq!(reasons, vec!["yanked (oops)", "requires Python >=3.13", "not a wheel or sdist"]);
+
+        assert_eq!(best(&selector("==1.1"), &page.files).unwrap(), "foo-1.1.tar.gz");
+        assert_eq!(best(&selector("==1.*"), &page.files).unwrap(), "foo-1.0.tar.gz");
+
+        // A yanked file is only chosen if no other file matches the pin.
+        let yanked = ProjectPage::parse(r#"
+<a href="foo-1.1-py3-none-any.whl" data-yanked="">foo-1.1-py3-none-any.whl</a>
+<a href="foo-1.1.tar.gz">foo-1.1.tar.gz</a>
+<a href="foo-1.1.0-1-py3-none-any.whl" data-yanked="">foo-1.1.0-1-py3-none-any.whl</a>"#,
+            "https://index.example/simple/foo/").unwrap();
+        assert_eq!(best(&selector("==1.1"), &yanked.files).unwrap(), "foo-1.1.tar.gz");
+        assert_eq!(best(&selector("===1.1"), &yanked.files).unwrap(), "foo-1.1.tar.gz");
+
+        let any_python = CandidateSelector { python_version: None, ..selector("") };
+        assert_eq!(best(&any_python, &page.files).unwrap(), "foo-1.2.tar.gz");
+    }
+
+    #[test]
+    fn test_exclude_newer() {
+        let mut files = files(&[
+            "foo-1.0.tar.gz",
+            "foo-1.1-py3-none-any.whl",
+            "foo-1.1.tar.gz",
+            "foo-1.2.tar.gz",
+            "foo-1.3.tar.gz",
+        ]);
+        let upload_times = [
+            Some("2022-06-01T10:00:00Z"),
+            Some("2023-01-01T00:00:00.000001Z"),
+            Some("2022-12-31T23:59:59.999Z"),
+            None,
+            Some("yesterday"),
+        ];
+        for (file, upload_time) in files.iter_mut().zip(upload_times.iter()) {
+            file.upload_time = upload_time.map(str::to_string);
+        }
+
+        let cutoff = Timestamp::parse("2023-01-01T00:00:00Z").unwrap();
+        let dated = CandidateSelector { exclude_newer: Some(cutoff), ..selector("") };
+        let selection = dated.select(&files);
+        assert_eq!(selection.best.unwrap().filename, "foo-1.1.tar.gz");
+        let reasons: Vec<&Rejection> = selection.rejected.iter().map(|(_, rejection)| rejection).collect();
+        assert_eq!(reasons, vec![
+            &Rejection::Outranked,
+            &Rejection::UploadedAfter(Timestamp::parse("2023-01-01T00:00:00.000001Z").unwrap()),
+            &Rejection::UnknownUploadTime,
+            &Rejection::UnknownUploadTime,
+        ]);
+
+        let dated = CandidateSelector { exclude_newer: Some(cutoff), ..selector(">=1.1") };
+        assert_eq!(best(&dated, &files).unwrap(), "foo-1.1.tar.gz");
+
+        let dated = CandidateSelector {
+            exclude_newer: Some(Timestamp::parse("2022-01-01").unwrap()),
+            ..selector(">=1.0")
+        };
+        let err = dated.select_best(&files).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No matching distribution found: none of the 5 files of `>=1.0` is acceptable \
+             (3 uploaded after 2022-01-01T00:00:00Z, 2 without an upload time)");
+    }
+}
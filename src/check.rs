@@ -0,0 +1,271 @@
+//! Consistency checking of an installed environment, like `pip check`: every
+//! `Requires-Dist` of every installed distribution which applies to the
+//! environment must be installed at an allowed version.
+//!
+//! Like pip, the dependencies of extras are not checked, since nothing
+//! records which extras were requested, and a direct URL requirement is
+//! satisfied by any installed version.
+
+use crate::installed::{InstalledDistribution, SitePackages};
+use crate::marker::MarkerEnvironment;
+use crate::name::PackageName;
+use crate::requirement::{Requirement, VersionOrUrl};
+use crate::Version;
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// What is wrong with a dependency.
+pub enum ProblemKind {
+    /// The dependency is not installed at all.
+    Missing,
+    /// The installed version is not allowed by the requirement.
+    Conflict { installed: Version },
+    /// The installed version is a pre-release which the requirement only
+    /// allows because pre-releases are accepted once installed. An installer
+    /// would not pick it, so this is a warning rather than an error.
+    PrereleaseOnly { installed: Version },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A dependency of an installed distribution which is not satisfied.
+pub struct Problem {
+    /// The distribution with the dependency.
+    pub name: PackageName,
+    pub version: Version,
+    pub requirement: Requirement,
+    pub kind: ProblemKind,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The problems found in an environment, in order of distribution name.
+pub struct CheckReport {
+    pub problems: Vec<Problem>,
+}
+
+impl CheckReport {
+    /// Checks the dependencies of every distribution in `site_packages`,
+    /// evaluating markers in `env`. Where a project is installed more than
+    /// once, only the newest install is considered.
+    ///
+    /// ```no_run
+    /// # use pep440::check::CheckReport;
+    /// # use pep440::installed::SitePackages;
+    /// # use pep440::marker::MarkerEnvironment;
+    /// # fn env() -> MarkerEnvironment { unimplemented!() }
+    /// let site_packages = SitePackages::scan("venv/lib/python3.12/site-packages").unwrap();
+    /// let report = CheckReport::check(&site_packages, &env());
+    /// for problem in &report.problems {
+    ///     println!("{}", problem);
+    /// }
+    /// ```
+    pub fn check(site_packages: &SitePackages, env: &MarkerEnvironment) -> CheckReport {
+        let installed = |name: &PackageName| site_packages.get(name).first();
+        let mut problems = vec![];
+        for distribution in site_packages.packages.values().filter_map(|distributions| distributions.first()) {
+            for requirement in &distribution.metadata.requires_dist {
+                if !requirement.evaluate_markers(env, &[]) {
+                    continue;
+                }
+                let kind = match installed(&requirement.name) {
+                    None => Some(ProblemKind::Missing),
+                    Some(dependency) => check_version(requirement, dependency),
+                };
+                if let Some(kind) = kind {
+                    problems.push(Problem {
+                        name: distribution.name.clone(),
+                        version: distribution.version.clone(),
+                        requirement: requirement.clone(),
+                        kind,
+                    });
+                }
+            }
+        }
+        CheckReport { problems }
+    }
+
+    /// Returns `true` if there are no problems other than pre-release
+    /// warnings, i.e. if `pip check` would succeed.
+    pub fn is_ok(&self) -> bool {
+        self.problems.iter().all(|problem| matches!(problem.kind, ProblemKind::PrereleaseOnly { .. }))
+    }
+}
+
+fn check_version(requirement: &Requirement, dependency: &InstalledDistribution) -> Option<ProblemKind> {
+    let specifiers = match &requirement.version_or_url {
+        Some(VersionOrUrl::Specifiers(specifiers)) => specifiers,
+        _ => return None,
+    };
+    let installed = &dependency.version;
+    if !specifiers.contains_with(installed, true) {
+        Some(ProblemKind::Conflict { installed: installed.clone() })
+    } else if !specifiers.contains(installed) {
+        Some(ProblemKind::PrereleaseOnly { installed: installed.clone() })
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for Problem {
+    /// Writes the problem the way `pip check` does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dependency = &self.requirement.name;
+        let specifiers = match &self.requirement.version_or_url {
+            Some(VersionOrUrl::Specifiers(specifiers)) => specifiers.to_string(),
+            _ => String::new(),
+        };
+        write!(f, "{} {} ", self.name, self.version)?;
+        match &self.kind {
+            ProblemKind::Missing => write!(f, "requires {}, which is not installed.", dependency),
+            ProblemKind::Conflict { installed } => write!(
+                f,
+                "has requirement {}{}, but you have {} {}.",
+                dependency, specifiers, dependency, installed),
+            ProblemKind::PrereleaseOnly { installed } => write!(
+                f,
+                "has requirement {}{}, which is only satisfied by the pre-release {} {}.",
+                dependency, specifiers, dependency, installed),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use crate::check::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct ReportJson {
+        problems: Vec<ProblemJson>,
+    }
+
+    #[derive(Serialize)]
+    struct ProblemJson {
+        name: String,
+        version: String,
+        requirement: String,
+        /// `missing`, `conflict` or `prerelease-only`.
+        kind: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        installed: Option<String>,
+    }
+
+    impl CheckReport {
+        /// Writes the report as JSON, with versions and requirements as
+        /// strings.
+        ///
+        /// ```
+        /// # use pep440::check::{CheckReport, Problem, ProblemKind};
+        /// # use pep440::name::PackageName;
+        /// # use pep440::requirement::Requirement;
+        /// # use pep440::Version;
+        /// let report = CheckReport {
+        ///     problems: vec![Problem {
+        ///         name: PackageName::parse("requests").unwrap(),
+        ///         version: Version::parse("2.31.0").unwrap(),
+        ///         requirement: Requirement::parse("idna<4").unwrap(),
+        ///         kind: ProblemKind::Conflict { installed: Version::parse("4.0").unwrap() },
+        ///     }],
+        /// };
+        /// assert_eq!(
+        ///     report.to_json(),
+        ///     r#"{"problems":[{"name":"requests","version":"2.31.0","requirement":"idna<4","kind":"conflict","installed":"4.0"}]}"#);
+        /// ```
+        pub fn to_json(&self) -> String {
+            let problems = self
+                .problems
+                .iter()
+                .map(|problem| {
+                    let (kind, installed) = match &problem.kind {
+                        ProblemKind::Missing => ("missing", None),
+                        ProblemKind::Conflict { installed } => ("conflict", Some(installed.to_string())),
+                        ProblemKind::PrereleaseOnly { installed } => ("prerelease-only", Some(installed.to_string())),
+                    };
+                    ProblemJson {
+                        name: problem.name.to_string(),
+                        version: problem.version.to_string(),
+                        requirement: problem.requirement.to_string(),
+                        kind,
+                        installed,
+                    }
+                })
+                .collect();
+            serde_json::to_string(&ReportJson { problems }).expect("a check report serializes to JSON")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::*;
+    use crate::installed::DistributionKind;
+    use crate::metadata::Metadata;
+    use std::path::PathBuf;
+
+    fn distribution(name: &str, version: &str, requires: &[&str]) -> InstalledDistribution {
+        let mut contents = format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n", name, version);
+        for requirement in requires {
+            contents.push_str(&format!("Requires-Dist: {}\n", requirement));
+        }
+        let metadata = Metadata::parse(&contents).unwrap();
+        InstalledDistribution {
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            path: PathBuf::from(format!("{}-{}.dist-info", name, version)),
+            kind: DistributionKind::DistInfo,
+            metadata,
+            installer: None,
+        }
+    }
+
+    fn linux_311() -> MarkerEnvironment {
+        MarkerEnvironment {
+            implementation_name: "cpython".to_string(),
+            implementation_version: "3.11.4".to_string(),
+            os_name: "posix".to_string(),
+            platform_machine: "x86_64".to_string(),
+            platform_python_implementation: "CPython".to_string(),
+            platform_release: "6.1.0-13-amd64".to_string(),
+            platform_system: "Linux".to_string(),
+            platform_version: "#1 SMP PREEMPT_DYNAMIC Debian 6.1.55-1".to_string(),
+            python_full_version: "3.11.4".to_string(),
+            python_version: "3.11".to_string(),
+            sys_platform: "linux".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut site_packages = SitePackages::default();
+        for distribution in [
+            distribution("app", "1.0", &[
+                "requests>=2",
+                "missing",
+                "colorama; sys_platform == 'win32'",
+                "tomli; python_version < '3.12'",
+                "pytest; extra == 'test'",
+                "lib @ https://example.com/lib-9.0.tar.gz",
+                "beta>=1.0",
+            ]),
+            distribution("requests", "2.31.0", &["idna<4,>=2.5"]),
+            distribution("requests", "1.0", &["gone"]),
+            distribution("idna", "4.0", &[]),
+            distribution("tomli", "2.0.1", &[]),
+            distribution("lib", "1.0", &[]),
+            distribution("beta", "2.0b1", &[]),
+        ] {
+            site_packages.packages.entry(distribution.name.clone()).or_default().push(distribution);
+        }
+
+        let report = CheckReport::check(&site_packages, &linux_311());
+        let problems: Vec<String> = report.problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(problems, vec![
+            "app 1.0 requires missing, which is not installed.",
+            "app 1.0 has requirement beta>=1.0, which is only satisfied by the pre-release beta 2.0b1.",
+            "requests 2.31.0 has requirement idna<4, >=2.5, but you have idna 4.0.",
+        ]);
+        assert!(!report.is_ok());
+
+        let report = CheckReport { problems: report.problems[1..2].to_vec() };
+        assert!(report.is_ok());
+    }
+}
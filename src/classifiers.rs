@@ -0,0 +1,181 @@
+//! Cross-checking of `Programming Language :: Python :: X.Y` trove classifiers
+//! against `Requires-Python`.
+
+use crate::requires_python::RequiresPython;
+use crate::Version;
+
+const PYTHON_CLASSIFIER_PREFIX: &str = "Programming Language :: Python :: ";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The outcome of `check_classifiers()`.
+pub struct ClassifierCheck {
+    /// Classifiers for minors which `Requires-Python` allows, but which the
+    /// project does not declare.
+    pub missing: Vec<String>,
+    /// Declared classifiers for versions which `Requires-Python` excludes.
+    pub extraneous: Vec<String>,
+}
+
+impl ClassifierCheck {
+    /// Returns `true` if the classifiers and `Requires-Python` agree.
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.extraneous.is_empty()
+    }
+}
+
+/// Parses the Python version out of a `Programming Language :: Python :: X`
+/// or `Programming Language :: Python :: X.Y` classifier. Any other classifier,
+/// including `Programming Language :: Python :: 3 :: Only` and implementation
+/// classifiers, gives `None`.
+///
+/// ```
+/// # use pep440::Version;
+/// # use pep440::classifiers::python_classifier_version;
+/// let ver = python_classifier_version("Programming Language :: Python :: 3.12");
+/// assert_eq!(ver, Version::parse("3.12"));
+/// let ver = python_classifier_version("Programming Language :: Python :: 3 :: Only");
+/// assert_eq!(ver, None);
+/// ```
+pub fn python_classifier_version(classifier: &str) -> Option<Version> {
+    let version = classifier.trim().strip_prefix(PYTHON_CLASSIFIER_PREFIX)?;
+    let release = version
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    if release.len() > 2 {
+        return None;
+    }
+    Some(Version::from_release(release))
+}
+
+/// Returns the classifier string for the given `major.minor` version.
+pub fn python_classifier(minor: &Version) -> String {
+    format!("{}{}", PYTHON_CLASSIFIER_PREFIX, minor.release_str())
+}
+
+/// Compares the Python version classifiers of a project with the minors which
+/// its `Requires-Python` allows, out of the given catalogue of interpreters.
+///
+/// A `X.Y` classifier is extraneous if no interpreter of that minor in the
+/// catalogue is allowed (or, if the catalogue has no interpreter of that minor
+/// at all, if `X.Y.0` is not allowed). A major-only `X` classifier is
+/// extraneous if no allowed interpreter has that major version. A classifier
+/// is missing for every allowed minor which is not declared.
+///
+/// ```
+/// # use pep440::Version;
+/// # use pep440::classifiers::check_classifiers;
+/// # use pep440::requires_python::RequiresPython;
+/// let catalogue: Vec<Version> = (7..=13)
+///     .map(|minor| Version::from_release(vec![3, minor]))
+///     .collect();
+/// let requires = RequiresPython::parse(">=3.9").unwrap();
+/// let classifiers = vec![
+///     "Programming Language :: Python :: 3",
+///     "Programming Language :: Python :: 3.8",
+///     "Programming Language :: Python :: 3.9",
+///     "Programming Language :: Python :: 3.10",
+///     "Programming Language :: Python :: 3.11",
+///     "Programming Language :: Python :: 3.12",
+/// ];
+/// let check = check_classifiers(&requires, &classifiers, &catalogue);
+/// assert_eq!(check.missing, vec!["Programming Language :: Python :: 3.13"]);
+/// assert_eq!(check.extraneous, vec!["Programming Language :: Python :: 3.8"]);
+/// ```
+pub fn check_classifiers<S: AsRef<str>>(
+    requires_python: &RequiresPython,
+    classifiers: &[S],
+    catalogue: &[Version],
+) -> ClassifierCheck {
+    let allowed = requires_python.evaluate(catalogue).minors;
+    let known: Vec<Version> = catalogue.iter().map(|python| python.truncate_release(2)).collect();
+
+    let mut declared: Vec<Version> = vec![];
+    let mut extraneous: Vec<String> = vec![];
+    for classifier in classifiers {
+        let classifier = classifier.as_ref();
+        let version = match python_classifier_version(classifier) {
+            Some(version) => version,
+            None => continue,
+        };
+        let consistent = if version.release.len() == 1 {
+            allowed.iter().any(|minor| minor.release[0] == version.release[0])
+        } else if known.contains(&version) {
+            allowed.contains(&version)
+        } else {
+            requires_python.contains(&version)
+        };
+        if !consistent {
+            extraneous.push(classifier.to_string());
+        }
+        declared.push(version);
+    }
+
+    let missing = allowed
+        .iter()
+        .filter(|minor| !declared.contains(minor))
+        .map(python_classifier)
+        .collect();
+
+    ClassifierCheck { missing, extraneous }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classifiers::*;
+
+    fn catalogue() -> Vec<Version> {
+        ["2.7", "3.6", "3.7", "3.8", "3.9", "3.10", "3.11", "3.12", "3.13"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_classifier_version() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("Programming Language :: Python :: 3.10", Some("3.10")),
+            ("Programming Language :: Python :: 2", Some("2")),
+            ("Programming Language :: Python :: 2 :: Only", None),
+            ("Programming Language :: Python :: Implementation :: CPython", None),
+            ("Programming Language :: Python :: 3.10.1", None),
+            ("Programming Language :: Rust", None),
+        ];
+        for (classifier, expected) in cases {
+            assert_eq!(
+                python_classifier_version(classifier),
+                expected.map(|v| Version::parse(v).unwrap()),
+                "classifier={}", classifier);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let requires = RequiresPython::parse(">=3.8, !=3.9.*, <3.12").unwrap();
+        let classifiers = [
+            "Programming Language :: Python :: 2",
+            "Programming Language :: Python :: 3",
+            "Programming Language :: Python :: 3 :: Only",
+            "Programming Language :: Python :: 3.8",
+            "Programming Language :: Python :: 3.9",
+            "Programming Language :: Python :: 3.11",
+            "Programming Language :: Python :: 3.14",
+            "Intended Audience :: Developers",
+        ];
+        let check = check_classifiers(&requires, &classifiers, &catalogue());
+        assert_eq!(check.missing, vec!["Programming Language :: Python :: 3.10"]);
+        assert_eq!(check.extraneous, vec![
+            "Programming Language :: Python :: 2",
+            "Programming Language :: Python :: 3.9",
+            "Programming Language :: Python :: 3.14",
+        ]);
+        assert!(!check.is_consistent());
+
+        let classifiers = [
+            "Programming Language :: Python :: 3.8",
+            "Programming Language :: Python :: 3.10",
+            "Programming Language :: Python :: 3.11",
+        ];
+        assert!(check_classifiers(&requires, &classifiers, &catalogue()).is_consistent());
+    }
+}
@@ -0,0 +1,436 @@
+//! Direct URLs, as used in PEP508 requirements such as
+//! `pkg @ git+https://host/repo.git@v1.2#subdirectory=x`, and as recorded in
+//! the `direct_url.json` of an installed distribution (PEP610, now the
+//! "Recording the Direct URL Origin of installed distributions"
+//! specification).
+//!
+//! Reading and writing `direct_url.json` requires the `json` feature.
+
+use crate::error::Error;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The hash algorithms recognized in the fragment of an archive URL.
+const HASH_ALGORITHMS: &[&str] = &["md5", "sha1", "sha224", "sha256", "sha384", "sha512"];
+
+/// The extensions of the archives which pip can install from a URL.
+const ARCHIVE_EXTENSIONS: &[&str] = &[".whl", ".tar.gz", ".tgz", ".tar.bz2", ".tbz", ".tar.xz", ".txz", ".tar", ".zip"];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// A version control system.
+pub enum VcsKind {
+    Git,
+    Mercurial,
+    Bazaar,
+    Subversion,
+}
+
+impl VcsKind {
+    /// Parses the name used in URL schemes such as `git+https` and in
+    /// `direct_url.json`.
+    pub fn parse(name: &str) -> Option<VcsKind> {
+        match name {
+            "git" => Some(VcsKind::Git),
+            "hg" => Some(VcsKind::Mercurial),
+            "bzr" => Some(VcsKind::Bazaar),
+            "svn" => Some(VcsKind::Subversion),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VcsKind::Git => "git",
+            VcsKind::Mercurial => "hg",
+            VcsKind::Bazaar => "bzr",
+            VcsKind::Subversion => "svn",
+        }
+    }
+
+    /// Returns `true` if the revision is a full commit id rather than a
+    /// branch or tag name.
+    fn is_commit_id(&self, revision: &str) -> bool {
+        let lengths: &[usize] = match self {
+            VcsKind::Git => &[40, 64],
+            VcsKind::Mercurial => &[40],
+            VcsKind::Bazaar | VcsKind::Subversion => &[],
+        };
+        lengths.contains(&revision.len()) && revision.chars().all(|c| c.is_ascii_hexdigit())
+    }
+}
+
+impl fmt::Display for VcsKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// What a direct URL points to.
+pub enum DirectUrlInfo {
+    /// A wheel or a source archive.
+    Archive {
+        /// Keyed by algorithm, e.g. `sha256`. May be empty.
+        hashes: BTreeMap<String, String>,
+    },
+    /// A revision in a version control repository.
+    Vcs {
+        vcs: VcsKind,
+        /// The branch, tag or commit the user asked for.
+        requested_revision: Option<String>,
+        /// The exact commit, if known. It is always known once installed.
+        commit_id: Option<String>,
+    },
+    /// A local directory.
+    Directory { editable: bool },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A parsed direct URL.
+pub struct DirectUrl {
+    /// The location, without the VCS prefix of the scheme, the revision or
+    /// the fragment.
+    pub url: String,
+    /// The directory of the project within the archive or repository.
+    pub subdirectory: Option<String>,
+    pub info: DirectUrlInfo,
+}
+
+impl DirectUrl {
+    /// Attempt to parse the URL of a PEP508 direct reference.
+    ///
+    /// `file:` URLs of anything but an archive are taken to be directories.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::direct_url::{DirectUrl, DirectUrlInfo, VcsKind};
+    /// let direct = DirectUrl::parse("git+https://host/repo.git@v1.2#subdirectory=x").unwrap();
+    /// assert_eq!(direct.url, "https://host/repo.git");
+    /// assert_eq!(direct.subdirectory.as_deref(), Some("x"));
+    /// assert_eq!(direct.info, DirectUrlInfo::Vcs {
+    ///     vcs: VcsKind::Git,
+    ///     requested_revision: Some("v1.2".to_string()),
+    ///     commit_id: None,
+    /// });
+    /// assert_eq!(direct.revision_version(), Version::parse("1.2"));
+    ///
+    /// let archive = DirectUrl::parse("https://example.com/pip-24.0.tar.gz#sha256=abc").unwrap();
+    /// assert_eq!(archive.to_string(), "https://example.com/pip-24.0.tar.gz#sha256=abc");
+    /// ```
+    pub fn parse(url: &str) -> Result<DirectUrl, Error> {
+        let error = |reason: &str| Error::direct_url_error(format!("`{}`: {}", url, reason));
+        let (location, fragment) = match url.trim().split_once('#') {
+            Some((location, fragment)) => (location, Some(fragment)),
+            None => (url.trim(), None),
+        };
+        let (scheme, rest) = location.split_once("://").ok_or_else(|| error("expected a scheme"))?;
+
+        let mut subdirectory = None;
+        let mut hashes = BTreeMap::new();
+        for (key, value) in fragment.into_iter().flat_map(|fragment| fragment.split('&')).filter_map(|pair| pair.split_once('=')) {
+            if key == "subdirectory" {
+                subdirectory = Some(value.to_string());
+            } else if HASH_ALGORITHMS.contains(&key) {
+                hashes.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        if let Some((vcs, scheme)) = scheme.split_once('+') {
+            let vcs = VcsKind::parse(vcs).ok_or_else(|| error(&format!("unknown version control system `{}`", vcs)))?;
+            if !hashes.is_empty() {
+                return Err(error("a repository can't have a hash"));
+            }
+            // The revision follows the last `@` of the path, not of the user
+            // name.
+            let path_start = rest.find('/').unwrap_or(rest.len());
+            let (rest, requested_revision) = match rest[path_start..].rsplit_once('@') {
+                Some((path, revision)) if !revision.is_empty() => {
+                    (format!("{}{}", &rest[..path_start], path), Some(revision.to_string()))
+                }
+                Some(_) => return Err(error("empty revision")),
+                None => (rest.to_string(), None),
+            };
+            let commit_id = requested_revision.clone().filter(|revision| vcs.is_commit_id(revision));
+            return Ok(DirectUrl {
+                url: format!("{}://{}", scheme, rest),
+                subdirectory,
+                info: DirectUrlInfo::Vcs { vcs, requested_revision, commit_id },
+            });
+        }
+
+        let is_archive = ARCHIVE_EXTENSIONS.iter().any(|extension| location.ends_with(extension));
+        let info = if scheme == "file" && !is_archive {
+            if !hashes.is_empty() {
+                return Err(error("a directory can't have a hash"));
+            }
+            DirectUrlInfo::Directory { editable: false }
+        } else {
+            DirectUrlInfo::Archive { hashes }
+        };
+        Ok(DirectUrl { url: location.to_string(), subdirectory, info })
+    }
+
+    /// Returns the version named by the requested revision of a repository,
+    /// e.g. `1.2` for the tag `v1.2`, unless the revision is a commit id.
+    pub fn revision_version(&self) -> Option<Version> {
+        match &self.info {
+            DirectUrlInfo::Vcs { vcs, requested_revision: Some(revision), .. } if !vcs.is_commit_id(revision) => {
+                Version::parse(revision)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DirectUrl {
+    /// Writes the URL as it appears in a PEP508 requirement. A repository is
+    /// pinned to its commit id if it is known.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fragment = vec![];
+        match &self.info {
+            DirectUrlInfo::Archive { hashes } => {
+                write!(f, "{}", self.url)?;
+                fragment.extend(hashes.iter().map(|(algorithm, hash)| format!("{}={}", algorithm, hash)));
+            }
+            DirectUrlInfo::Vcs { vcs, requested_revision, commit_id } => {
+                write!(f, "{}+{}", vcs, self.url)?;
+                if let Some(revision) = commit_id.as_ref().or(requested_revision.as_ref()) {
+                    write!(f, "@{}", revision)?;
+                }
+            }
+            DirectUrlInfo::Directory { .. } => write!(f, "{}", self.url)?,
+        }
+        if let Some(subdirectory) = &self.subdirectory {
+            fragment.push(format!("subdirectory={}", subdirectory));
+        }
+        if !fragment.is_empty() {
+            write!(f, "#{}", fragment.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DirectUrl {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DirectUrl::parse(s)
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use crate::direct_url::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct DirectUrlJson {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdirectory: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        archive_info: Option<ArchiveInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        vcs_info: Option<VcsInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dir_info: Option<DirInfo>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct ArchiveInfo {
+        /// `{algorithm}={hash}`, superseded by `hashes`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hashes: Option<BTreeMap<String, String>>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct VcsInfo {
+        vcs: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        requested_revision: Option<String>,
+        commit_id: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct DirInfo {
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        editable: bool,
+    }
+
+    impl DirectUrl {
+        /// Parses the contents of a `direct_url.json`.
+        ///
+        /// ```
+        /// # use pep440::direct_url::{DirectUrl, DirectUrlInfo, VcsKind};
+        /// let direct = DirectUrl::from_json(r#"{
+        ///     "url": "https://github.com/pypa/pip.git",
+        ///     "vcs_info": {"vcs": "git", "requested_revision": "24.0", "commit_id": "ef78c129b1a966dbbbdb8ebfffc43723e89110d1"}
+        /// }"#).unwrap();
+        /// assert_eq!(direct.to_string(), "git+https://github.com/pypa/pip.git@ef78c129b1a966dbbbdb8ebfffc43723e89110d1");
+        /// assert_eq!(direct.revision_version().unwrap().to_string(), "24.0");
+        /// assert_eq!(DirectUrl::from_json(&direct.to_json().unwrap()).unwrap(), direct);
+        /// ```
+        pub fn from_json(input: &str) -> Result<DirectUrl, Error> {
+            let error = |reason: String| Error::direct_url_error(format!("direct_url.json: {}", reason));
+            let json: DirectUrlJson = serde_json::from_str(input).map_err(|err| error(err.to_string()))?;
+            let info = match (json.archive_info, json.vcs_info, json.dir_info) {
+                (Some(archive), None, None) => {
+                    let mut hashes = archive.hashes.unwrap_or_default();
+                    if let Some((algorithm, hash)) = archive.hash.as_deref().and_then(|hash| hash.split_once('=')) {
+                        hashes.entry(algorithm.to_string()).or_insert_with(|| hash.to_string());
+                    }
+                    DirectUrlInfo::Archive { hashes }
+                }
+                (None, Some(vcs), None) => DirectUrlInfo::Vcs {
+                    vcs: VcsKind::parse(&vcs.vcs)
+                        .ok_or_else(|| error(format!("unknown version control system `{}`", vcs.vcs)))?,
+                    requested_revision: vcs.requested_revision,
+                    commit_id: Some(vcs.commit_id),
+                },
+                (None, None, Some(dir)) => DirectUrlInfo::Directory { editable: dir.editable },
+                _ => return Err(error("expected exactly one of `archive_info`, `vcs_info` and `dir_info`".to_string())),
+            };
+            Ok(DirectUrl { url: json.url, subdirectory: json.subdirectory, info })
+        }
+
+        /// Writes the contents of a `direct_url.json`. A repository needs its
+        /// commit id.
+        pub fn to_json(&self) -> Result<String, Error> {
+            let mut json = DirectUrlJson {
+                url: self.url.clone(),
+                subdirectory: self.subdirectory.clone(),
+                archive_info: None,
+                vcs_info: None,
+                dir_info: None,
+            };
+            match &self.info {
+                DirectUrlInfo::Archive { hashes } => {
+                    json.archive_info = Some(ArchiveInfo {
+                        hash: None,
+                        hashes: Some(hashes.clone()).filter(|hashes| !hashes.is_empty()),
+                    });
+                }
+                DirectUrlInfo::Vcs { vcs, requested_revision, commit_id } => {
+                    let commit_id = commit_id.clone().ok_or_else(|| {
+                        Error::direct_url_error(format!("`{}`: the commit id is not known", self))
+                    })?;
+                    json.vcs_info = Some(VcsInfo {
+                        vcs: vcs.as_str().to_string(),
+                        requested_revision: requested_revision.clone(),
+                        commit_id,
+                    });
+                }
+                DirectUrlInfo::Directory { editable } => json.dir_info = Some(DirInfo { editable: *editable }),
+            }
+            Ok(serde_json::to_string(&json).expect("a direct URL serializes to JSON"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::direct_url::*;
+
+    #[test]
+    fn test_parse() {
+        let cases: &[(&str, &str, Option<&str>, DirectUrlInfo)] = &[
+            (
+                "git+ssh://git@github.com/org/repo.git@main",
+                "ssh://git@github.com/org/repo.git",
+                None,
+                DirectUrlInfo::Vcs { vcs: VcsKind::Git, requested_revision: Some("main".to_string()), commit_id: None },
+            ),
+            (
+                "git+https://user@host/repo",
+                "https://user@host/repo",
+                None,
+                DirectUrlInfo::Vcs { vcs: VcsKind::Git, requested_revision: None, commit_id: None },
+            ),
+            (
+                "hg+https://host/repo@0123456789abcdef0123456789abcdef01234567#egg=repo&subdirectory=sub/dir",
+                "https://host/repo",
+                Some("sub/dir"),
+                DirectUrlInfo::Vcs {
+                    vcs: VcsKind::Mercurial,
+                    requested_revision: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+                    commit_id: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+                },
+            ),
+            (
+                "https://example.com/pkg-1.0-py3-none-any.whl#sha256=abc&md5=def",
+                "https://example.com/pkg-1.0-py3-none-any.whl",
+                None,
+                DirectUrlInfo::Archive {
+                    hashes: vec![("md5", "def"), ("sha256", "abc")]
+                        .into_iter()
+                        .map(|(algorithm, hash)| (algorithm.to_string(), hash.to_string()))
+                        .collect(),
+                },
+            ),
+            ("file:///home/user/project", "file:///home/user/project", None, DirectUrlInfo::Directory { editable: false }),
+            ("file:///tmp/pkg-1.0.tar.gz", "file:///tmp/pkg-1.0.tar.gz", None, DirectUrlInfo::Archive { hashes: BTreeMap::new() }),
+        ];
+        for (input, url, subdirectory, info) in cases {
+            let direct = DirectUrl::parse(input).unwrap();
+            assert_eq!(direct.url, *url, "input={}", input);
+            assert_eq!(direct.subdirectory.as_deref(), *subdirectory, "input={}", input);
+            assert_eq!(direct.info, *info, "input={}", input);
+        }
+
+        for (input, reason) in &[
+            ("example.com/pkg.tar.gz", "expected a scheme"),
+            ("cvs+https://host/repo", "unknown version control system `cvs`"),
+            ("git+https://host/repo@", "empty revision"),
+            ("git+https://host/repo#sha256=abc", "a repository can't have a hash"),
+        ] {
+            let err = DirectUrl::parse(input).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid direct URL: `{}`: {}", input, reason));
+        }
+    }
+
+    #[test]
+    fn test_revision_version() {
+        let version = |url: &str| DirectUrl::parse(url).unwrap().revision_version().map(|version| version.to_string());
+        assert_eq!(version("git+https://host/repo@v1.2.3"), Some("1.2.3".to_string()));
+        assert_eq!(version("git+https://host/repo@2024.1rc1#subdirectory=x"), Some("2024.1rc1".to_string()));
+        assert_eq!(version("git+https://host/repo@main"), None);
+        assert_eq!(version("git+https://host/repo@1234567890123456789012345678901234567890"), None);
+        assert_eq!(version("https://example.com/pkg-1.0.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_display() {
+        for url in &[
+            "git+ssh://git@github.com/org/repo.git@v1#subdirectory=x",
+            "svn+https://host/repo",
+            "https://example.com/pkg-1.0.zip#sha256=abc&subdirectory=pkg",
+            "file:///home/user/project",
+        ] {
+            assert_eq!(DirectUrl::parse(url).unwrap().to_string(), *url);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let archive = DirectUrl::from_json(r#"{"url": "https://example.com/pkg-1.0.tar.gz", "archive_info": {"hash": "sha256=abc"}}"#).unwrap();
+        assert_eq!(archive.to_string(), "https://example.com/pkg-1.0.tar.gz#sha256=abc");
+        assert_eq!(
+            archive.to_json().unwrap(),
+            r#"{"url":"https://example.com/pkg-1.0.tar.gz","archive_info":{"hashes":{"sha256":"abc"}}}"#);
+
+        let directory = DirectUrl::from_json(r#"{"url": "file:///src/project", "dir_info": {"editable": true}}"#).unwrap();
+        assert_eq!(directory.info, DirectUrlInfo::Directory { editable: true });
+        assert_eq!(DirectUrl::from_json(&directory.to_json().unwrap()).unwrap(), directory);
+
+        let err = DirectUrl::from_json(r#"{"url": "file:///src", "dir_info": {}, "archive_info": {}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid direct URL: direct_url.json: expected exactly one of `archive_info`, `vcs_info` and `dir_info`");
+        let err = DirectUrl::parse("git+https://host/repo@main").unwrap().to_json().unwrap_err();
+        assert_eq!(err.to_string(), "Invalid direct URL: `git+https://host/repo@main`: the commit id is not known");
+    }
+}
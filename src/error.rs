@@ -1,8 +1,57 @@
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
+/// The errors of the crate.
+///
+/// This enum is `#[non_exhaustive]`, which is a breaking change from 0.2:
+/// variants are added as the crate grows, so a `match` on it outside the
+/// crate needs a wildcard arm.
+#[non_exhaustive]
 pub enum Error {
     ParseError(String),
+    SpecifierParseError(String),
+    NameParseError(String),
+    FilenameParseError(String),
+    TimestampParseError(String),
+    MarkerParseError { input: String, reason: String },
+    RequirementParseError { input: String, reason: String },
+    /// An invalid `pyproject.toml`; `key` is the dotted path to the offending
+    /// value, e.g. `project.dependencies[2]`.
+    PyProjectError { key: String, reason: String },
+    /// An invalid `pylock.toml`; `key` is the dotted path to the offending
+    /// value, e.g. `packages[0].wheels[1]`.
+    PyLockError { key: String, reason: String },
+    /// An invalid requirements file; `line` is 1-based, or `0` if the error
+    /// concerns the file as a whole.
+    RequirementsTxtError { path: PathBuf, line: usize, reason: String },
+    /// Invalid inline script metadata; `line` is the 1-based line of the
+    /// script, or `0` if the error concerns the script as a whole.
+    ScriptMetadataError { line: usize, reason: String },
+    /// Invalid core metadata (`METADATA` or `PKG-INFO`).
+    MetadataError(String),
+    /// An invalid `RECORD`; `line` is 1-based.
+    RecordError { line: usize, reason: String },
+    /// A wheel which could not be installed.
+    InstallError(String),
+    /// A distribution which could not be uninstalled.
+    UninstallError(String),
+    /// A file or directory which could not be read or written.
+    IoError { path: PathBuf, reason: String },
+    /// A wheel or sdist which can't be read as an archive, or whose filename
+    /// can't be parsed.
+    ArchiveError { path: PathBuf, reason: String },
+    /// A direct URL or a `direct_url.json` which can't be understood.
+    DirectUrlError(String),
+    /// A malformed response from a package index.
+    SimpleApiError(String),
+    /// None of the files of a project could be selected; the string explains
+    /// why.
+    NoMatchingDistribution(String),
+    /// A package index could not be read or is misconfigured.
+    IndexError(String),
+    /// Dependency resolution failed; the string derives the conflict.
+    NoSolution(String),
 }
 
 impl Error {
@@ -11,16 +60,158 @@ impl Error {
         Error::ParseError(input)
     }
 
+    #[inline]
+    pub fn specifier_parse_error(input: String) -> Error {
+        Error::SpecifierParseError(input)
+    }
+
+    #[inline]
+    pub fn name_parse_error(input: String) -> Error {
+        Error::NameParseError(input)
+    }
+
+    #[inline]
+    pub fn filename_parse_error(input: String) -> Error {
+        Error::FilenameParseError(input)
+    }
+
+    #[inline]
+    pub fn timestamp_parse_error(input: String) -> Error {
+        Error::TimestampParseError(input)
+    }
+
+    #[inline]
+    pub fn marker_parse_error(input: String, reason: String) -> Error {
+        Error::MarkerParseError { input, reason }
+    }
+
+    #[inline]
+    pub fn requirement_parse_error(input: String, reason: String) -> Error {
+        Error::RequirementParseError { input, reason }
+    }
+
+    #[inline]
+    pub fn pyproject_error(key: String, reason: String) -> Error {
+        Error::PyProjectError { key, reason }
+    }
+
+    #[inline]
+    pub fn pylock_error(key: String, reason: String) -> Error {
+        Error::PyLockError { key, reason }
+    }
+
+    #[inline]
+    pub fn requirements_txt_error(path: PathBuf, line: usize, reason: String) -> Error {
+        Error::RequirementsTxtError { path, line, reason }
+    }
+
+    #[inline]
+    pub fn script_metadata_error(line: usize, reason: String) -> Error {
+        Error::ScriptMetadataError { line, reason }
+    }
+
+    #[inline]
+    pub fn metadata_error(reason: String) -> Error {
+        Error::MetadataError(reason)
+    }
+
+    #[inline]
+    pub fn record_error(line: usize, reason: String) -> Error {
+        Error::RecordError { line, reason }
+    }
+
+    #[inline]
+    pub fn install_error(reason: String) -> Error {
+        Error::InstallError(reason)
+    }
+
+    #[inline]
+    pub fn uninstall_error(reason: String) -> Error {
+        Error::UninstallError(reason)
+    }
+
+    #[inline]
+    pub fn io_error(path: &Path, err: std::io::Error) -> Error {
+        Error::IoError { path: path.to_path_buf(), reason: err.to_string() }
+    }
+
+    #[inline]
+    pub fn archive_error(path: &Path, reason: String) -> Error {
+        Error::ArchiveError { path: path.to_path_buf(), reason }
+    }
+
+    #[inline]
+    pub fn direct_url_error(reason: String) -> Error {
+        Error::DirectUrlError(reason)
+    }
+
+    #[inline]
+    pub fn simple_api_error(reason: String) -> Error {
+        Error::SimpleApiError(reason)
+    }
+
+    #[inline]
+    pub fn no_matching_distribution(reason: String) -> Error {
+        Error::NoMatchingDistribution(reason)
+    }
+
+    #[inline]
+    pub fn index_error(reason: String) -> Error {
+        Error::IndexError(reason)
+    }
+
+    #[inline]
+    pub fn no_solution(derivation: String) -> Error {
+        Error::NoSolution(derivation)
+    }
+
     pub fn get_parse_error(&self) -> Option<String> {
         match self {
             Error::ParseError(s) => Some(s.to_string()),
+            Error::SpecifierParseError(s) => Some(s.to_string()),
+            Error::NameParseError(s) => Some(s.to_string()),
+            Error::FilenameParseError(s) => Some(s.to_string()),
+            Error::TimestampParseError(s) => Some(s.to_string()),
+            Error::MarkerParseError { input, .. } => Some(input.to_string()),
+            Error::RequirementParseError { input, .. } => Some(input.to_string()),
+            Error::PyProjectError { .. } => None,
+            Error::PyLockError { .. } => None,
+            Error::RequirementsTxtError { .. } => None,
+            Error::ScriptMetadataError { .. } => None,
+            Error::MetadataError(_) => None,
+            Error::RecordError { .. } => None,
+            Error::InstallError(_) => None,
+            Error::UninstallError(_) => None,
+            Error::IoError { .. } => None,
+            Error::ArchiveError { .. } => None,
+            Error::DirectUrlError(_) => None,
+            Error::SimpleApiError(_) => None,
+            Error::NoMatchingDistribution(_) => None,
+            Error::IndexError(_) => None,
+            Error::NoSolution(_) => None,
         }
     }
 
+    /// Returns `true` if a version failed to parse. Other parse errors, e.g.
+    /// of specifiers or markers, are covered by
+    /// [`is_any_parse_error`](Error::is_any_parse_error).
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::specifier::SpecifierSet;
+    /// let version = "1.0-".parse::<Version>().unwrap_err();
+    /// assert!(version.is_parse_error());
+    /// let specifiers = ">=1.0-".parse::<SpecifierSet>().unwrap_err();
+    /// assert!(!specifiers.is_parse_error() && specifiers.is_any_parse_error());
+    /// ```
     pub fn is_parse_error(&self) -> bool {
-        match self {
-            Error::ParseError(_) => true,
-        }
+        matches!(self, Error::ParseError(_))
+    }
+
+    /// Returns `true` for every parse error, i.e. when
+    /// [`get_parse_error`](Error::get_parse_error) returns the input.
+    pub fn is_any_parse_error(&self) -> bool {
+        self.get_parse_error().is_some()
     }
 }
 
@@ -29,6 +220,58 @@ impl fmt::Display for Error {
         match self {
             Error::ParseError(input) =>
                 write!(f, "Failed to parse version: {}", input),
+            Error::SpecifierParseError(input) =>
+                write!(f, "Failed to parse version specifier: {}", input),
+            Error::NameParseError(input) =>
+                write!(f, "Failed to parse name: {}", input),
+            Error::FilenameParseError(input) =>
+                write!(f, "Failed to parse distribution filename: {}", input),
+            Error::TimestampParseError(input) =>
+                write!(f, "Failed to parse timestamp: {}", input),
+            Error::MarkerParseError { input, reason } =>
+                write!(f, "Failed to parse marker: {} ({})", input, reason),
+            Error::RequirementParseError { input, reason } =>
+                write!(f, "Failed to parse requirement: {} ({})", input, reason),
+            Error::PyProjectError { key, reason } if key.is_empty() =>
+                write!(f, "Invalid pyproject.toml: {}", reason),
+            Error::PyProjectError { key, reason } =>
+                write!(f, "Invalid pyproject.toml: `{}`: {}", key, reason),
+            Error::PyLockError { key, reason } if key.is_empty() =>
+                write!(f, "Invalid pylock.toml: {}", reason),
+            Error::PyLockError { key, reason } =>
+                write!(f, "Invalid pylock.toml: `{}`: {}", key, reason),
+            Error::RequirementsTxtError { path, line: 0, reason } =>
+                write!(f, "{}: {}", path.display(), reason),
+            Error::RequirementsTxtError { path, line, reason } =>
+                write!(f, "{}:{}: {}", path.display(), line, reason),
+            Error::ScriptMetadataError { line: 0, reason } =>
+                write!(f, "Invalid script metadata: {}", reason),
+            Error::ScriptMetadataError { line, reason } =>
+                write!(f, "Invalid script metadata: line {}: {}", line, reason),
+            Error::MetadataError(reason) =>
+                write!(f, "Invalid metadata: {}", reason),
+            Error::RecordError { line, reason } =>
+                write!(f, "Invalid RECORD: line {}: {}", line, reason),
+            Error::InstallError(reason) =>
+                write!(f, "Installation failed: {}", reason),
+            Error::UninstallError(reason) =>
+                write!(f, "Uninstallation failed: {}", reason),
+            Error::IoError { path, reason } =>
+                write!(f, "{}: {}", path.display(), reason),
+            Error::ArchiveError { path, reason } =>
+                write!(f, "Invalid archive {}: {}", path.display(), reason),
+            Error::DirectUrlError(reason) =>
+                write!(f, "Invalid direct URL: {}", reason),
+            Error::SimpleApiError(reason) =>
+                write!(f, "Invalid simple repository API response: {}", reason),
+            Error::NoMatchingDistribution(reason) =>
+                write!(f, "No matching distribution found: {}", reason),
+            Error::IndexError(reason) =>
+                write!(f, "Index lookup failed: {}", reason),
+            Error::NoSolution(derivation) =>
+                write!(f, "No solution found:\n{}", derivation),
         }
     }
 }
+
+impl std::error::Error for Error {}
@@ -0,0 +1,307 @@
+//! Parsing of distribution filenames: wheels, as described in the binary
+//! distribution format specification (PEP427), and sdists, as described in
+//! the source distribution format specification (PEP625).
+
+use crate::error::Error;
+use crate::name::PackageName;
+use crate::Version;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// The optional build tag of a wheel, e.g. the `1` of
+/// `foo-1.0-1-py3-none-any.whl`. Build tags sort numerically first, then by
+/// the remaining string.
+pub struct BuildTag {
+    pub number: u32,
+    pub suffix: String,
+}
+
+impl BuildTag {
+    /// Attempt to parse a build tag, which must start with a digit.
+    pub fn parse(input: &str) -> Option<BuildTag> {
+        let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+        let number = input[..split].parse().ok()?;
+        Some(BuildTag { number, suffix: input[split..].to_string() })
+    }
+}
+
+impl Ord for BuildTag {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.number, &self.suffix).cmp(&(other.number, &other.suffix))
+    }
+}
+
+impl PartialOrd for BuildTag {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BuildTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.number, self.suffix)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A parsed wheel filename,
+/// `{name}-{version}(-{build tag})?-{python tag}-{abi tag}-{platform tag}.whl`.
+///
+/// Compressed tag sets such as `py2.py3` are split into their components.
+pub struct WheelFilename {
+    pub name: PackageName,
+    pub version: Version,
+    pub build_tag: Option<BuildTag>,
+    pub python_tags: Vec<String>,
+    pub abi_tags: Vec<String>,
+    pub platform_tags: Vec<String>,
+}
+
+impl WheelFilename {
+    /// Attempt to parse the given wheel filename.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::filename::WheelFilename;
+    /// let wheel = WheelFilename::parse("Foo_Bar-1.0.post1-2-py2.py3-none-any.whl").unwrap();
+    /// assert_eq!(wheel.name.as_str(), "foo-bar");
+    /// assert_eq!(wheel.version, Version::parse("1.0.post1").unwrap());
+    /// assert_eq!(wheel.build_tag.unwrap().number, 2);
+    /// assert_eq!(wheel.python_tags, vec!["py2", "py3"]);
+    /// ```
+    pub fn parse(filename: &str) -> Option<WheelFilename> {
+        let stem = filename.strip_suffix(".whl")?;
+        let parts: Vec<&str> = stem.split('-').collect();
+        let (name, version, build_tag, tags) = match parts.as_slice() {
+            [name, version, python, abi, platform] => (name, version, None, [python, abi, platform]),
+            [name, version, build, python, abi, platform] => {
+                (name, version, Some(BuildTag::parse(build)?), [python, abi, platform])
+            }
+            _ => return None,
+        };
+        let split = |tag: &str| tag.split('.').map(str::to_string).collect::<Vec<String>>();
+        Some(WheelFilename {
+            name: PackageName::parse(name)?,
+            version: Version::parse(version)?,
+            build_tag,
+            python_tags: split(tags[0]),
+            abi_tags: split(tags[1]),
+            platform_tags: split(tags[2]),
+        })
+    }
+
+    /// Returns every `(python, abi, platform)` tag triple the wheel supports.
+    pub fn tags(&self) -> Vec<(String, String, String)> {
+        let mut tags = vec![];
+        for python in &self.python_tags {
+            for abi in &self.abi_tags {
+                for platform in &self.platform_tags {
+                    tags.push((python.clone(), abi.clone(), platform.clone()));
+                }
+            }
+        }
+        tags
+    }
+}
+
+impl fmt::Display for WheelFilename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.name.as_dist_info_name(), self.version)?;
+        if let Some(build_tag) = &self.build_tag {
+            write!(f, "-{}", build_tag)?;
+        }
+        write!(
+            f,
+            "-{}-{}-{}.whl",
+            self.python_tags.join("."),
+            self.abi_tags.join("."),
+            self.platform_tags.join("."))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// The archive format of an sdist. Only `.tar.gz` is standard; `.zip` is
+/// still found on older releases.
+pub enum SdistExtension {
+    TarGz,
+    Zip,
+}
+
+impl SdistExtension {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SdistExtension::TarGz => ".tar.gz",
+            SdistExtension::Zip => ".zip",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A parsed sdist filename, `{name}-{version}.tar.gz`.
+///
+/// Legacy sdists may use an unnormalized name containing `-`, so the version
+/// is taken to be everything after the last `-`.
+pub struct SdistFilename {
+    pub name: PackageName,
+    pub version: Version,
+    pub extension: SdistExtension,
+}
+
+impl SdistFilename {
+    /// Attempt to parse the given sdist filename.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::filename::SdistFilename;
+    /// let sdist = SdistFilename::parse("python-dateutil-2.8.2.tar.gz").unwrap();
+    /// assert_eq!(sdist.name.as_str(), "python-dateutil");
+    /// assert_eq!(sdist.version, Version::parse("2.8.2").unwrap());
+    /// ```
+    pub fn parse(filename: &str) -> Option<SdistFilename> {
+        let (stem, extension) = if let Some(stem) = filename.strip_suffix(".tar.gz") {
+            (stem, SdistExtension::TarGz)
+        } else if let Some(stem) = filename.strip_suffix(".zip") {
+            (stem, SdistExtension::Zip)
+        } else {
+            return None;
+        };
+        let (name, version) = stem.rsplit_once('-')?;
+        Some(SdistFilename {
+            name: PackageName::parse(name)?,
+            version: Version::parse(version)?,
+            extension,
+        })
+    }
+}
+
+impl fmt::Display for SdistFilename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}{}", self.name.as_dist_info_name(), self.version, self.extension.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// Either a wheel or an sdist filename.
+pub enum DistFilename {
+    Wheel(WheelFilename),
+    Sdist(SdistFilename),
+}
+
+impl DistFilename {
+    /// Attempt to parse the given filename as a wheel or an sdist.
+    ///
+    /// ```
+    /// # use pep440::filename::DistFilename;
+    /// assert!(DistFilename::parse("foo-1.0-py3-none-any.whl").unwrap().is_wheel());
+    /// assert!(!DistFilename::parse("foo-1.0.tar.gz").unwrap().is_wheel());
+    /// assert!(DistFilename::parse("foo-1.0.win32.exe").is_none());
+    /// ```
+    pub fn parse(filename: &str) -> Option<DistFilename> {
+        if filename.ends_with(".whl") {
+            WheelFilename::parse(filename).map(DistFilename::Wheel)
+        } else {
+            SdistFilename::parse(filename).map(DistFilename::Sdist)
+        }
+    }
+
+    pub fn name(&self) -> &PackageName {
+        match self {
+            DistFilename::Wheel(wheel) => &wheel.name,
+            DistFilename::Sdist(sdist) => &sdist.name,
+        }
+    }
+
+    pub fn version(&self) -> &Version {
+        match self {
+            DistFilename::Wheel(wheel) => &wheel.version,
+            DistFilename::Sdist(sdist) => &sdist.version,
+        }
+    }
+
+    pub fn is_wheel(&self) -> bool {
+        matches!(self, DistFilename::Wheel(_))
+    }
+}
+
+impl fmt::Display for DistFilename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistFilename::Wheel(wheel) => write!(f, "{}", wheel),
+            DistFilename::Sdist(sdist) => write!(f, "{}", sdist),
+        }
+    }
+}
+
+impl FromStr for DistFilename {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DistFilename::parse(s).ok_or_else(|| Error::filename_parse_error(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filename::*;
+
+    const VALID_WHEELS: &[&str] = &[
+        "foo-1.0-py3-none-any.whl",
+        "foo-1.0-1-py3-none-any.whl",
+        "foo-1.0-1b-py3-none-any.whl",
+        "numpy-1.26.2-cp312-cp312-manylinux_2_17_x86_64.manylinux2014_x86_64.whl",
+        "Pillow-10.1.0-pp310-pypy310_pp73-win_amd64.whl",
+        "foo-1!2.0rc1.post3.dev4+local.1-py2.py3-none-any.whl",
+    ];
+
+    const INVALID_WHEELS: &[&str] = &[
+        "foo-1.0-py3-none.whl", "foo-1.0-py3-none-any.tar.gz",
+        "foo-1.0-b1-py3-none-any.whl", "foo-one-py3-none-any.whl",
+        "foo-1.0-1-2-py3-none-any.whl", "-1.0-py3-none-any.whl",
+    ];
+
+    #[test]
+    fn test_wheels() {
+        for filename in VALID_WHEELS {
+            let wheel = WheelFilename::parse(filename);
+            assert!(wheel.is_some(), "Failed to parse wheel: '{}'", filename);
+        }
+        for filename in INVALID_WHEELS {
+            assert!(WheelFilename::parse(filename).is_none(), "Parsed wheel: '{}'", filename);
+        }
+
+        let wheel = WheelFilename::parse(VALID_WHEELS[3]).unwrap();
+        assert_eq!(wheel.tags().len(), 2);
+        assert_eq!(wheel.to_string(), VALID_WHEELS[3]);
+    }
+
+    #[test]
+    fn test_build_tag_ordering() {
+        let tags: Vec<BuildTag> = ["2", "10", "1b", "1"]
+            .iter()
+            .map(|tag| BuildTag::parse(tag).unwrap())
+            .collect();
+        let mut sorted = tags.clone();
+        sorted.sort();
+        let sorted: Vec<String> = sorted.iter().map(|tag| tag.to_string()).collect();
+        assert_eq!(sorted, vec!["1", "1b", "2", "10"]);
+    }
+
+    #[test]
+    fn test_sdists() {
+        let cases: &[(&str, Option<(&str, &str)>)] = &[
+            ("foo-1.0.tar.gz", Some(("foo", "1.0"))),
+            ("Foo.Bar-2023.1.zip", Some(("foo-bar", "2023.1"))),
+            ("foo_bar-1.0rc1.tar.gz", Some(("foo-bar", "1.0rc1"))),
+            ("foo-1.0.tar.bz2", None),
+            ("foo.tar.gz", None),
+            ("foo-bar.tar.gz", None),
+        ];
+        for (filename, expected) in cases {
+            let sdist = SdistFilename::parse(filename);
+            let actual = sdist.as_ref().map(|sdist| (sdist.name.to_string(), sdist.version.to_string()));
+            let expected = expected.map(|(name, version)| (name.to_string(), version.to_string()));
+            assert_eq!(actual, expected, "filename={}", filename);
+        }
+    }
+}
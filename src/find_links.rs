@@ -0,0 +1,180 @@
+//! Scanning of a flat directory of wheels and sdists, as used with pip's
+//! `--find-links` for air-gapped installs.
+//!
+//! Only the directory itself is scanned, not its subdirectories, like pip.
+
+use crate::error::Error;
+use crate::filename::DistFilename;
+use crate::index::IndexSource;
+use crate::name::PackageName;
+use crate::simple::{file_url, File};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A wheel or sdist found in the directory.
+pub struct FlatCandidate {
+    pub path: PathBuf,
+    pub filename: DistFilename,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A file which was skipped, and why.
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The result of scanning a directory.
+pub struct FlatIndex {
+    /// Candidates grouped by normalized name, newest version first.
+    pub packages: BTreeMap<PackageName, Vec<FlatCandidate>>,
+    /// Files whose names could not be parsed.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl FlatIndex {
+    /// Scans `directory`. The paths of the candidates are absolute, so that
+    /// they can be turned into `file://` URLs.
+    pub fn scan<P: AsRef<Path>>(directory: P) -> Result<FlatIndex, Error> {
+        let directory = directory.as_ref();
+        let read_error = |err: std::io::Error| Error::index_error(format!("{}: {}", directory.display(), err));
+        let directory = directory.canonicalize().map_err(read_error)?;
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(&directory).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+            if !entry.path().is_dir() {
+                paths.push(entry.path());
+            }
+        }
+        // `read_dir` returns entries in no particular order.
+        paths.sort();
+
+        let mut index = FlatIndex::default();
+        for path in paths {
+            match parse_path(&path) {
+                Ok(filename) => index
+                    .packages
+                    .entry(filename.name().clone())
+                    .or_default()
+                    .push(FlatCandidate { path, filename }),
+                Err(reason) => index.diagnostics.push(Diagnostic { path, reason: reason.to_string() }),
+            }
+        }
+        for candidates in index.packages.values_mut() {
+            candidates.sort_by(|a, b| b.filename.version().cmp(a.filename.version()).then_with(|| a.path.cmp(&b.path)));
+        }
+        Ok(index)
+    }
+
+    /// Returns the candidates for a project, newest version first.
+    pub fn get(&self, name: &PackageName) -> &[FlatCandidate] {
+        self.packages.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+fn parse_path(path: &Path) -> Result<DistFilename, &'static str> {
+    let filename = path.file_name().and_then(|name| name.to_str()).ok_or("filename is not valid UTF-8")?;
+    DistFilename::parse(filename).ok_or_else(|| {
+        if filename.ends_with(".whl") {
+            "invalid wheel filename"
+        } else if filename.ends_with(".tar.gz") || filename.ends_with(".zip") {
+            "invalid sdist filename"
+        } else {
+            "not a wheel or sdist"
+        }
+    })
+}
+
+impl IndexSource for FlatIndex {
+    fn project_files(&self, name: &PackageName) -> Result<Option<Vec<File>>, Error> {
+        let candidates = match self.packages.get(name) {
+            Some(candidates) => candidates,
+            None => return Ok(None),
+        };
+        let files = candidates
+            .iter()
+            .map(|candidate| File {
+                filename: candidate.path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                url: file_url(&candidate.path),
+                hashes: BTreeMap::new(),
+                requires_python: None,
+                yanked: false,
+                yanked_reason: None,
+                core_metadata: None,
+                size: None,
+                upload_time: None,
+                dist: Some(candidate.filename.clone()),
+            })
+            .collect();
+        Ok(Some(files))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::find_links::*;
+    use crate::scratch::ScratchDir;
+
+    #[test]
+    fn test_scan() {
+        let dir = ScratchDir::new("find-links");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let filenames = [
+            "foo-1.0.tar.gz",
+            "Foo-2.0-py3-none-any.whl",
+            "foo-10.0rc1-cp312-cp312-manylinux_2_17_x86_64.whl",
+            "foo-2.0.tar.gz",
+            "foo_bar-0.1.zip",
+            "foo-1.0-py3-none.whl",
+            "foo-one.tar.gz",
+            "README.txt",
+            "nested/bar-1.0.tar.gz",
+        ];
+        for filename in &filenames {
+            fs::write(dir.join(filename), b"").unwrap();
+        }
+
+        let index = FlatIndex::scan(&dir).unwrap();
+        let foo: Vec<String> = index
+            .get(&PackageName::parse("FOO").unwrap())
+            .iter()
+            .map(|candidate| candidate.filename.to_string())
+            .collect();
+        assert_eq!(foo, vec![
+            "foo-10.0rc1-cp312-cp312-manylinux_2_17_x86_64.whl",
+            "foo-2.0-py3-none-any.whl",
+            "foo-2.0.tar.gz",
+            "foo-1.0.tar.gz",
+        ]);
+        assert_eq!(index.packages.len(), 2);
+        assert!(index.get(&PackageName::parse("bar").unwrap()).is_empty());
+
+        let diagnostics: Vec<(String, &str)> = index
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.path.file_name().unwrap().to_string_lossy().into_owned(), diagnostic.reason.as_str()))
+            .collect();
+        assert_eq!(diagnostics, vec![
+            ("README.txt".to_string(), "not a wheel or sdist"),
+            ("foo-1.0-py3-none.whl".to_string(), "invalid wheel filename"),
+            ("foo-one.tar.gz".to_string(), "invalid sdist filename"),
+        ]);
+
+        let files = index.project_files(&PackageName::parse("foo-bar").unwrap()).unwrap().unwrap();
+        assert!(files[0].url.starts_with("file:///"));
+        assert!(files[0].url.ends_with("/foo_bar-0.1.zip"));
+
+        // A relative directory still gives absolute URLs.
+        let current = std::env::current_dir().unwrap();
+        let up: PathBuf = current.components().skip(1).map(|_| "..").collect();
+        let relative = up.join(dir.strip_prefix("/").unwrap());
+        let index = FlatIndex::scan(&relative).unwrap();
+        let files = index.project_files(&PackageName::parse("foo-bar").unwrap()).unwrap().unwrap();
+        assert_eq!(files[0].url, file_url(&dir.canonicalize().unwrap().join("foo_bar-0.1.zip")));
+        assert!(FlatIndex::scan(dir.join("missing")).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Lookup of the files of a project across several package indexes.
+//!
+//! When a project name exists on more than one index, e.g. on a private index
+//! and on a public mirror, merging the files of all indexes lets anyone who
+//! registers the name publicly take over the dependency ("dependency
+//! confusion"). The [`IndexStrategy`] controls which indexes are eligible, and
+//! packages can be pinned to a single index.
+
+use crate::error::Error;
+use crate::name::PackageName;
+use crate::simple::{File, ProjectPage};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// A source of project files, such as a remote or a local index.
+pub trait IndexSource {
+    /// Returns the files of the project, or `None` if the index doesn't know
+    /// the project at all.
+    fn project_files(&self, name: &PackageName) -> Result<Option<Vec<File>>, Error>;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An index on the local filesystem, laid out like the simple repository API:
+/// `{root}/{normalized name}/index.html`.
+pub struct LocalIndex {
+    pub root: PathBuf,
+}
+
+impl LocalIndex {
+    pub fn new<P: Into<PathBuf>>(root: P) -> LocalIndex {
+        LocalIndex { root: root.into() }
+    }
+}
+
+impl IndexSource for LocalIndex {
+    fn project_files(&self, name: &PackageName) -> Result<Option<Vec<File>>, Error> {
+        let directory = self.root.join(name.as_str());
+        if !directory.join("index.html").is_file() {
+            return Ok(None);
+        }
+        let read_error = |err: std::io::Error| Error::index_error(format!("{}: {}", directory.display(), err));
+        let directory = directory.canonicalize().map_err(read_error)?;
+        let html = fs::read_to_string(directory.join("index.html")).map_err(read_error)?;
+        let page_url = format!("file://{}/", directory.display());
+        Ok(Some(ProjectPage::parse(&html, &page_url)?.files))
+    }
+}
+
+/// A named index.
+pub struct Index {
+    pub name: String,
+    pub source: Box<dyn IndexSource>,
+    /// Only use the index for packages pinned to it.
+    pub explicit: bool,
+}
+
+impl Index {
+    pub fn new<S: IndexSource + 'static>(name: &str, source: S) -> Index {
+        Index { name: name.to_string(), source: Box::new(source), explicit: false }
+    }
+}
+
+impl fmt::Debug for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Index")
+            .field("name", &self.name)
+            .field("explicit", &self.explicit)
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+/// How the files of a project are gathered from several indexes.
+pub enum IndexStrategy {
+    /// Only use the first index, in order, that knows the project. This is
+    /// the safe default: a public index can't shadow a private one listed
+    /// before it.
+    #[default]
+    FirstMatch,
+    /// Use the files of every index, so that the best version across all of
+    /// them is selected, like pip's `--extra-index-url`.
+    BestMatch,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A file together with the name of the index it was found on.
+pub struct IndexFile {
+    pub index: String,
+    pub file: File,
+}
+
+impl AsRef<File> for IndexFile {
+    fn as_ref(&self) -> &File {
+        &self.file
+    }
+}
+
+#[derive(Debug, Default)]
+/// An ordered list of indexes with a lookup strategy.
+pub struct IndexSet {
+    pub indexes: Vec<Index>,
+    pub strategy: IndexStrategy,
+    /// Packages which may only come from the named index, regardless of the
+    /// strategy.
+    pub pins: BTreeMap<PackageName, String>,
+}
+
+impl IndexSet {
+    /// Gathers the files of a project according to the pins and the strategy.
+    /// The result can be passed to a
+    /// [`CandidateSelector`](crate::candidate::CandidateSelector).
+    pub fn files(&self, name: &PackageName) -> Result<Vec<IndexFile>, Error> {
+        if let Some(pinned) = self.pins.get(name) {
+            let index = self.indexes.iter().find(|index| index.name == *pinned).ok_or_else(|| {
+                Error::index_error(format!("`{}` is pinned to unknown index `{}`", name, pinned))
+            })?;
+            return Ok(index_files(index, index.source.project_files(name)?.unwrap_or_default()));
+        }
+
+        let mut files = vec![];
+        for index in self.indexes.iter().filter(|index| !index.explicit) {
+            if let Some(found) = index.source.project_files(name)? {
+                files.extend(index_files(index, found));
+                if self.strategy == IndexStrategy::FirstMatch {
+                    break;
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+fn index_files(index: &Index, files: Vec<File>) -> Vec<IndexFile> {
+    files.into_iter().map(|file| IndexFile { index: index.name.clone(), file }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::candidate::CandidateSelector;
+    use crate::index::*;
+    use crate::scratch::ScratchDir;
+    use std::path::Path;
+
+    fn write_project(root: &Path, project: &str, filenames: &[&str]) {
+        let directory = root.join(project);
+        fs::create_dir_all(&directory).unwrap();
+        let html: String = filenames
+            .iter()
+            .map(|filename| format!("<a href=\"{}\">{}</a>\n", filename, filename))
+            .collect();
+        fs::write(directory.join("index.html"), html).unwrap();
+    }
+
+    fn indexes(root: &Path) -> IndexSet {
+        let private = root.join("private");
+        let public = root.join("public");
+        write_project(&private, "internal-tool", &["internal_tool-1.0.tar.gz"]);
+        write_project(&private, "requests", &["requests-2.0.tar.gz"]);
+        write_project(&public, "internal-tool", &["internal_tool-99.0.tar.gz"]);
+        write_project(&public, "requests", &["requests-2.31.0.tar.gz"]);
+        write_project(&public, "numpy", &["numpy-1.26.0.tar.gz"]);
+        IndexSet {
+            indexes: vec![Index::new("private", LocalIndex::new(private)), Index::new("public", LocalIndex::new(public))],
+            ..Default::default()
+        }
+    }
+
+    fn best(indexes: &IndexSet, name: &str) -> Option<(String, String)> {
+        let files = indexes.files(&PackageName::parse(name).unwrap()).unwrap();
+        let best = CandidateSelector::default().select(&files).best?;
+        Some((best.index.clone(), best.file.filename.clone()))
+    }
+
+    #[test]
+    fn test_first_match() {
+        let dir = ScratchDir::new("index-first");
+        let indexes = indexes(&dir);
+        assert_eq!(best(&indexes, "internal-tool").unwrap(), ("private".to_string(), "internal_tool-1.0.tar.gz".to_string()));
+        assert_eq!(best(&indexes, "numpy").unwrap().0, "public");
+        assert!(best(&indexes, "missing").is_none());
+
+        let files = indexes.files(&PackageName::parse("Internal_Tool").unwrap()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].file.url.starts_with("file:///"));
+        assert!(files[0].file.url.ends_with("/private/internal-tool/internal_tool-1.0.tar.gz"));
+    }
+
+    #[test]
+    fn test_best_match() {
+        let dir = ScratchDir::new("index-best");
+        let indexes = IndexSet { strategy: IndexStrategy::BestMatch, ..indexes(&dir) };
+        assert_eq!(best(&indexes, "internal-tool").unwrap(), ("public".to_string(), "internal_tool-99.0.tar.gz".to_string()));
+        assert_eq!(indexes.files(&PackageName::parse("requests").unwrap()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pins() {
+        let dir = ScratchDir::new("index-pins");
+        let mut indexes = IndexSet { strategy: IndexStrategy::BestMatch, ..indexes(&dir) };
+        indexes.pins.insert(PackageName::parse("internal-tool").unwrap(), "private".to_string());
+        assert_eq!(best(&indexes, "internal-tool").unwrap().1, "internal_tool-1.0.tar.gz");
+        assert_eq!(best(&indexes, "requests").unwrap().1, "requests-2.31.0.tar.gz");
+
+        // An explicit index is only used for the packages pinned to it.
+        indexes.indexes[0].explicit = true;
+        assert_eq!(best(&indexes, "internal-tool").unwrap().0, "private");
+        let files = indexes.files(&PackageName::parse("requests").unwrap()).unwrap();
+        assert!(files.iter().all(|file| file.index == "public"));
+
+        indexes.pins.insert(PackageName::parse("numpy").unwrap(), "mirror".to_string());
+        let err = indexes.files(&PackageName::parse("numpy").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Index lookup failed: `numpy` is pinned to unknown index `mirror`");
+    }
+}
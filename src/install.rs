@@ -0,0 +1,532 @@
+//! Installation of a wheel into a set of scheme directories, as described in
+//! the "Binary distribution format" specification.
+//!
+//! This module requires the `installer` feature. The wheel is validated in
+//! full before anything is written: its `WHEEL` version, that `METADATA`
+//! matches the filename, and that every file matches its `RECORD` entry.
+//! Entry point wrappers are not generated and nothing is byte-compiled.
+
+use crate::error::Error;
+use crate::filename::WheelFilename;
+use crate::installed::InstalledDistribution;
+use crate::metadata::{Metadata, WheelMetadata};
+use crate::name::PackageName;
+use crate::record::{Record, RecordEntry, RecordHash};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The directories a wheel installs into, as `sysconfig.get_paths()` gives
+/// them for the target interpreter.
+pub struct Scheme {
+    pub purelib: PathBuf,
+    pub platlib: PathBuf,
+    pub scripts: PathBuf,
+    pub data: PathBuf,
+    /// The directory for this distribution's headers, e.g.
+    /// `include/site/python3.12/{name}`.
+    pub headers: PathBuf,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Installs wheels into a scheme.
+pub struct Installer {
+    pub scheme: Scheme,
+    /// The interpreter that the `#!python` shebangs of scripts are rewritten
+    /// to use.
+    pub python: PathBuf,
+    /// The name written to the `INSTALLER` file.
+    pub name: String,
+}
+
+/// A file of a wheel, read into memory.
+pub(crate) struct WheelEntry {
+    pub(crate) path: String,
+    pub(crate) contents: Vec<u8>,
+    pub(crate) executable: bool,
+}
+
+impl Scheme {
+    /// Returns the directory for a `.data` subdirectory name.
+    fn get(&self, key: &str) -> Option<&Path> {
+        match key {
+            "purelib" => Some(&self.purelib),
+            "platlib" => Some(&self.platlib),
+            "scripts" => Some(&self.scripts),
+            "data" => Some(&self.data),
+            "headers" => Some(&self.headers),
+            _ => None,
+        }
+    }
+}
+
+impl Installer {
+    /// Installs the wheel at `wheel`, and returns the installed
+    /// distribution. Refuses to install over an existing `.dist-info`
+    /// directory.
+    ///
+    /// ```no_run
+    /// # use pep440::install::{Installer, Scheme};
+    /// let venv = std::path::Path::new("/opt/venv");
+    /// let site_packages = venv.join("lib/python3.12/site-packages");
+    /// let installer = Installer {
+    ///     scheme: Scheme {
+    ///         purelib: site_packages.clone(),
+    ///         platlib: site_packages,
+    ///         scripts: venv.join("bin"),
+    ///         data: venv.to_path_buf(),
+    ///         headers: venv.join("include/site/python3.12/six"),
+    ///     },
+    ///     python: venv.join("bin/python"),
+    ///     name: "pep440-rs".to_string(),
+    /// };
+    /// let installed = installer.install("six-1.16.0-py2.py3-none-any.whl").unwrap();
+    /// println!("Installed {} {}", installed.name, installed.version);
+    /// ```
+    pub fn install<P: AsRef<Path>>(&self, wheel: P) -> Result<InstalledDistribution, Error> {
+        let wheel = wheel.as_ref();
+        let filename = wheel
+            .file_name()
+            .and_then(|filename| filename.to_str())
+            .and_then(WheelFilename::parse)
+            .ok_or_else(|| error(wheel, "invalid wheel filename".to_string()))?;
+        let entries = read_wheel(wheel)?;
+
+        let dist_info = dist_info_directory(entries.iter().map(|entry| entry.path.as_str()), &filename.name)
+            .map_err(|reason| error(wheel, reason))?;
+        let data = format!("{}.data", dist_info.trim_end_matches(".dist-info"));
+        let read = |name: &str| -> Result<String, Error> {
+            let path = format!("{}/{}", dist_info, name);
+            let entry = entries
+                .iter()
+                .find(|entry| entry.path == path)
+                .ok_or_else(|| error(wheel, format!("missing `{}`", path)))?;
+            String::from_utf8(entry.contents.clone()).map_err(|_| error(wheel, format!("`{}` is not UTF-8", path)))
+        };
+
+        let wheel_metadata = WheelMetadata::parse(&read("WHEEL")?).map_err(|err| error(wheel, err.to_string()))?;
+        if !wheel_metadata.is_supported() {
+            return Err(error(wheel, format!("unsupported `Wheel-Version` {}", wheel_metadata.wheel_version)));
+        }
+        let metadata = Metadata::parse(&read("METADATA")?).map_err(|err| error(wheel, err.to_string()))?;
+        if metadata.name != filename.name {
+            return Err(error(wheel, format!("name `{}` in METADATA doesn't match the filename", metadata.raw_name)));
+        }
+        if metadata.version != filename.version {
+            return Err(error(
+                wheel,
+                format!("version {} in METADATA doesn't match the filename's {}", metadata.version, filename.version)));
+        }
+        let record = Record::parse(&read("RECORD")?).map_err(|err| error(wheel, err.to_string()))?;
+        check_record(&entries, &record, &dist_info).map_err(|reason| error(wheel, reason))?;
+
+        let root = if wheel_metadata.root_is_purelib { &self.scheme.purelib } else { &self.scheme.platlib };
+        let dist_info_path = root.join(&dist_info);
+        if dist_info_path.exists() {
+            return Err(error(wheel, format!("{} is already installed", dist_info_path.display())));
+        }
+
+        // Work out every destination before writing anything.
+        let mut files = vec![];
+        for entry in &entries {
+            if is_record(&entry.path, &dist_info) {
+                continue;
+            }
+            let (directory, relative, is_script) = match entry.path.strip_prefix(&format!("{}/", data)) {
+                Some(rest) => {
+                    let (key, relative) = rest
+                        .split_once('/')
+                        .ok_or_else(|| error(wheel, format!("unexpected file `{}`", entry.path)))?;
+                    let directory = self
+                        .scheme
+                        .get(key)
+                        .ok_or_else(|| error(wheel, format!("unknown scheme `{}` in `{}`", key, entry.path)))?;
+                    (directory, relative, key == "scripts")
+                }
+                None => (root.as_path(), entry.path.as_str(), false),
+            };
+            let contents = if is_script { self.rewrite_shebang(&entry.contents) } else { entry.contents.clone() };
+            files.push((directory.join(relative), contents, entry.executable || is_script));
+        }
+
+        let mut installed = Record::default();
+        for (path, contents, executable) in files {
+            write(&path, &contents, executable)?;
+            installed.entries.push(RecordEntry {
+                path: relative_path(root, &path),
+                hash: Some(RecordHash::sha256(&contents)),
+                size: Some(contents.len() as u64),
+            });
+        }
+        let installer = format!("{}\n", self.name);
+        write(&dist_info_path.join("INSTALLER"), installer.as_bytes(), false)?;
+        installed.entries.push(RecordEntry {
+            path: format!("{}/INSTALLER", dist_info),
+            hash: Some(RecordHash::sha256(installer.as_bytes())),
+            size: Some(installer.len() as u64),
+        });
+        installed.entries.push(RecordEntry { path: format!("{}/RECORD", dist_info), hash: None, size: None });
+        write(&dist_info_path.join("RECORD"), installed.to_string().as_bytes(), false)?;
+
+        InstalledDistribution::read(&dist_info_path)
+    }
+
+    /// Replaces a `#!python` or `#!pythonw` shebang with the interpreter.
+    fn rewrite_shebang(&self, contents: &[u8]) -> Vec<u8> {
+        let rest = match contents.strip_prefix(b"#!python") {
+            Some(rest) => rest.strip_prefix(b"w").unwrap_or(rest),
+            None => return contents.to_vec(),
+        };
+        if !matches!(rest.first(), None | Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n')) {
+            return contents.to_vec();
+        }
+        let mut rewritten = format!("#!{}", self.python.display()).into_bytes();
+        rewritten.extend_from_slice(rest);
+        rewritten
+    }
+}
+
+fn error(wheel: &Path, reason: String) -> Error {
+    Error::install_error(format!("{}: {}", wheel.display(), reason))
+}
+
+/// Reads every file of the wheel, rejecting paths which would escape the
+/// installation directories.
+fn read_wheel(wheel: &Path) -> Result<Vec<WheelEntry>, Error> {
+    let entries = read_archive(wheel)?;
+    if let Some(entry) = entries.iter().find(|entry| !is_safe_path(&entry.path)) {
+        return Err(error(wheel, format!("unsafe path `{}`", entry.path)));
+    }
+    Ok(entries)
+}
+
+/// The largest file read from a wheel, since files are read into memory.
+const MAX_ENTRY_SIZE: u64 = 1 << 31;
+
+/// Reads every file of a wheel archive, in archive order. The size declared
+/// in the archive is not trusted: a file larger than [`MAX_ENTRY_SIZE`], or
+/// whose contents don't have the declared size, is an error.
+pub(crate) fn read_archive(wheel: &Path) -> Result<Vec<WheelEntry>, Error> {
+    let file = fs::File::open(wheel).map_err(|err| Error::io_error(wheel, err))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|err| Error::archive_error(wheel, err.to_string()))?;
+    let mut entries = vec![];
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|err| Error::archive_error(wheel, err.to_string()))?;
+        if file.is_dir() {
+            continue;
+        }
+        let path = file.name().to_string();
+        let size = file.size();
+        if size > MAX_ENTRY_SIZE {
+            return Err(Error::archive_error(wheel, format!("`{}` is too large ({} bytes)", path, size)));
+        }
+        let mut contents = vec![];
+        (&mut file)
+            .take(size + 1)
+            .read_to_end(&mut contents)
+            .map_err(|err| Error::archive_error(wheel, format!("`{}`: {}", path, err)))?;
+        if contents.len() as u64 != size {
+            return Err(Error::archive_error(wheel, format!("`{}` doesn't have its declared size of {} bytes", path, size)));
+        }
+        let executable = file.unix_mode().is_some_and(|mode| mode & 0o111 != 0);
+        entries.push(WheelEntry { path, contents, executable });
+    }
+    Ok(entries)
+}
+
+/// Returns `true` for a relative path without `.` or `..` components.
+pub(crate) fn is_safe_path(path: &str) -> bool {
+    !path.contains('\\')
+        && Path::new(path).components().all(|component| matches!(component, Component::Normal(_)))
+        && !path.is_empty()
+}
+
+/// Finds the single `.dist-info` directory, which must be for `name`.
+pub(crate) fn dist_info_directory<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+    name: &PackageName,
+) -> Result<String, String> {
+    let mut directories: Vec<&str> = paths
+        .into_iter()
+        .filter_map(|path| path.split_once('/'))
+        .map(|(top, _)| top)
+        .filter(|top| top.ends_with(".dist-info"))
+        .collect();
+    directories.sort_unstable();
+    directories.dedup();
+    let directory = match directories.as_slice() {
+        [directory] => directory.to_string(),
+        [] => return Err("no `.dist-info` directory".to_string()),
+        _ => return Err(format!("more than one `.dist-info` directory: {}", directories.join(", "))),
+    };
+    let stem = directory.trim_end_matches(".dist-info");
+    let matches = stem
+        .rsplit_once('-')
+        .and_then(|(dist_name, _)| PackageName::parse(dist_name))
+        .is_some_and(|dist_name| dist_name == *name);
+    if !matches {
+        return Err(format!("`{}` is not for {}", directory, name));
+    }
+    Ok(directory)
+}
+
+/// Returns `true` for `RECORD` and its signatures, which aren't recorded.
+pub(crate) fn is_record(path: &str, dist_info: &str) -> bool {
+    path.strip_prefix(dist_info)
+        .and_then(|name| name.strip_prefix('/'))
+        .is_some_and(|name| matches!(name, "RECORD" | "RECORD.jws" | "RECORD.p7s"))
+}
+
+/// Checks that every file is recorded with the right hash and size.
+fn check_record(entries: &[WheelEntry], record: &Record, dist_info: &str) -> Result<(), String> {
+    for entry in entries {
+        if is_record(&entry.path, dist_info) {
+            continue;
+        }
+        let recorded = record.get(&entry.path).ok_or_else(|| format!("`{}` is not in RECORD", entry.path))?;
+        let expected = recorded.hash.as_ref().ok_or_else(|| format!("`{}` has no hash in RECORD", entry.path))?;
+        let actual = RecordHash::compute(&expected.algorithm, &entry.contents)
+            .ok_or_else(|| format!("unsupported hash algorithm `{}` for `{}`", expected.algorithm, entry.path))?;
+        if actual != *expected {
+            return Err(format!("hash of `{}` is {}, but {} is recorded", entry.path, actual, expected));
+        }
+        if recorded.size.is_some_and(|size| size != entry.contents.len() as u64) {
+            return Err(format!("size of `{}` doesn't match RECORD", entry.path));
+        }
+    }
+    Ok(())
+}
+
+fn write(path: &Path, contents: &[u8], executable: bool) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| Error::io_error(parent, err))?;
+    }
+    fs::write(path, contents).map_err(|err| Error::io_error(path, err))?;
+    #[cfg(unix)]
+    {
+        if executable {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755)).map_err(|err| Error::io_error(path, err))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
+
+/// Returns `path` relative to `base`, with `/` separators, going up with
+/// `..` where needed as `RECORD` does for scripts.
+fn relative_path(base: &Path, path: &Path) -> String {
+    let base: Vec<Component> = base.components().collect();
+    let path: Vec<Component> = path.components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let ups = std::iter::repeat_n("..".to_string(), base.len() - common);
+    let downs = path[common..].iter().map(|component| component.as_os_str().to_string_lossy().into_owned());
+    ups.chain(downs).collect::<Vec<String>>().join("/")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::install::*;
+    use crate::scratch::ScratchDir;
+    use std::io::Write;
+
+    /// The paths and contents of the files of a wheel.
+    pub(crate) type WheelFiles<'a> = Vec<(&'a str, &'a [u8])>;
+
+    /// Writes a wheel with the given files, and a `RECORD` listing them
+    /// unless one is given.
+    pub(crate) fn write_wheel(path: &Path, files: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        let mut record = Record::default();
+        let mut dist_info = None;
+        let mut explicit_record = false;
+        for (name, contents) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(contents).unwrap();
+            if name.ends_with(".dist-info/RECORD") {
+                explicit_record = true;
+            }
+            if let Some((top, _)) = name.split_once('/').filter(|(top, _)| top.ends_with(".dist-info")) {
+                dist_info = Some(top.to_string());
+            }
+            record.entries.push(RecordEntry {
+                path: name.to_string(),
+                hash: Some(RecordHash::sha256(contents)),
+                size: Some(contents.len() as u64),
+            });
+        }
+        if let Some(dist_info) = dist_info.filter(|_| !explicit_record) {
+            record.entries.push(RecordEntry { path: format!("{}/RECORD", dist_info), hash: None, size: None });
+            writer.start_file(format!("{}/RECORD", dist_info), options).unwrap();
+            writer.write_all(record.to_string().as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    pub(crate) const METADATA: &[u8] = b"Metadata-Version: 2.1\nName: Spam.Eggs\nVersion: 1.0\n";
+    pub(crate) const WHEEL: &[u8] = b"Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n";
+
+    fn installer(dir: &Path) -> Installer {
+        Installer {
+            scheme: Scheme {
+                purelib: dir.join("lib/site-packages"),
+                platlib: dir.join("lib64/site-packages"),
+                scripts: dir.join("bin"),
+                data: dir.to_path_buf(),
+                headers: dir.join("include/spam-eggs"),
+            },
+            python: dir.join("bin/python"),
+            name: "pep440-rs".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_install() {
+        let dir = ScratchDir::new("install");
+        let wheel = dir.join("spam_eggs-1.0-py3-none-any.whl");
+        write_wheel(&wheel, &[
+            ("spam_eggs/__init__.py", b"print('spam')\n"),
+            ("spam_eggs-1.0.data/scripts/spam", b"#!python\nimport spam_eggs\n"),
+            ("spam_eggs-1.0.data/scripts/eggs.sh", b"#!/bin/sh\necho eggs\n"),
+            ("spam_eggs-1.0.data/data/share/spam.txt", b"data"),
+            ("spam_eggs-1.0.data/headers/spam.h", b"int spam;"),
+            ("spam_eggs-1.0.dist-info/METADATA", METADATA),
+            ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+        ]);
+
+        let installer = installer(&dir);
+        let installed = installer.install(&wheel).unwrap();
+        assert_eq!(installed.name.as_str(), "spam-eggs");
+        assert_eq!(installed.installer.as_deref(), Some("pep440-rs"));
+        assert_eq!(installed.path, dir.join("lib/site-packages/spam_eggs-1.0.dist-info"));
+
+        let script = fs::read_to_string(dir.join("bin/spam")).unwrap();
+        assert_eq!(script, format!("#!{}\nimport spam_eggs\n", dir.join("bin/python").display()));
+        assert_eq!(fs::read_to_string(dir.join("bin/eggs.sh")).unwrap(), "#!/bin/sh\necho eggs\n");
+        assert_eq!(fs::read_to_string(dir.join("share/spam.txt")).unwrap(), "data");
+        assert!(dir.join("include/spam-eggs/spam.h").is_file());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join("bin/spam")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        let record = Record::read(&installed).unwrap();
+        let paths: Vec<&str> = record.entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec![
+            "spam_eggs/__init__.py",
+            "../../bin/spam",
+            "../../bin/eggs.sh",
+            "../../share/spam.txt",
+            "../../include/spam-eggs/spam.h",
+            "spam_eggs-1.0.dist-info/METADATA",
+            "spam_eggs-1.0.dist-info/WHEEL",
+            "spam_eggs-1.0.dist-info/INSTALLER",
+            "spam_eggs-1.0.dist-info/RECORD",
+        ]);
+        assert_eq!(record.entries[1].hash, Some(RecordHash::sha256(script.as_bytes())));
+        assert!(crate::record::Verification::verify(&installed).is_ok());
+
+        let err = installer.install(&wheel).unwrap_err();
+        assert!(err.to_string().ends_with("spam_eggs-1.0.dist-info is already installed"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_wheels() {
+        let dir = ScratchDir::new("install-invalid");
+        let installer = installer(&dir);
+
+        let wheel_2: &[u8] = b"Wheel-Version: 2.0\nRoot-Is-Purelib: true\n";
+        let other_version: &[u8] = b"Metadata-Version: 2.1\nName: spam-eggs\nVersion: 1.0.post1\n";
+        let cases: Vec<(WheelFiles, &str)> = vec![
+            (
+                vec![("spam_eggs-1.0.dist-info/METADATA", METADATA), ("spam_eggs-1.0.dist-info/WHEEL", wheel_2)],
+                "unsupported `Wheel-Version` 2.0",
+            ),
+            (
+                vec![("spam_eggs-1.0.dist-info/METADATA", other_version), ("spam_eggs-1.0.dist-info/WHEEL", WHEEL)],
+                "version 1.0.post1 in METADATA doesn't match the filename's 1.0",
+            ),
+            (
+                vec![("spam_eggs-1.0.dist-info/WHEEL", WHEEL)],
+                "missing `spam_eggs-1.0.dist-info/METADATA`",
+            ),
+            (
+                vec![("ham-1.0.dist-info/METADATA", METADATA), ("ham-1.0.dist-info/WHEEL", WHEEL)],
+                "`ham-1.0.dist-info` is not for spam-eggs",
+            ),
+            (vec![("spam_eggs/__init__.py", b"")], "no `.dist-info` directory"),
+            (
+                vec![
+                    ("spam_eggs-1.0.dist-info/METADATA", METADATA),
+                    ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+                    ("spam_eggs-1.0.dist-info/RECORD", b"spam_eggs-1.0.dist-info/METADATA,,\n"),
+                    ("spam_eggs/__init__.py", b""),
+                ],
+                "`spam_eggs-1.0.dist-info/METADATA` has no hash in RECORD",
+            ),
+            (
+                vec![
+                    ("spam_eggs-1.0.dist-info/METADATA", METADATA),
+                    ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+                    ("spam_eggs-1.0.data/lib/x", b""),
+                ],
+                "unknown scheme `lib` in `spam_eggs-1.0.data/lib/x`",
+            ),
+            (
+                vec![("../evil.py", b""), ("spam_eggs-1.0.dist-info/WHEEL", WHEEL)],
+                "unsafe path `../evil.py`",
+            ),
+        ];
+        for (index, (files, reason)) in cases.iter().enumerate() {
+            let wheel = dir.join(format!("case{}", index)).join("spam_eggs-1.0-py3-none-any.whl");
+            fs::create_dir_all(wheel.parent().unwrap()).unwrap();
+            write_wheel(&wheel, files);
+            let err = installer.install(&wheel).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Installation failed: {}: {}", wheel.display(), reason));
+        }
+        assert!(!dir.join("lib").exists());
+    }
+
+    #[test]
+    fn test_declared_sizes() {
+        let dir = ScratchDir::new("install-sizes");
+        let wheel = dir.join("spam_eggs-1.0-py3-none-any.whl");
+
+        // Rewrites the uncompressed size in the central directory, which is
+        // the size the archive declares.
+        let declare = |size: u32| {
+            write_wheel(&wheel, &[("spam_eggs/__init__.py", b"print('spam')\n")]);
+            let mut bytes = fs::read(&wheel).unwrap();
+            let header = bytes.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+            bytes[header + 24..header + 28].copy_from_slice(&size.to_le_bytes());
+            fs::write(&wheel, bytes).unwrap();
+            read_archive(&wheel).map(|_| ()).unwrap_err().to_string()
+        };
+        assert_eq!(
+            declare(u32::MAX - 1),
+            format!("Invalid archive {}: `spam_eggs/__init__.py` is too large (4294967294 bytes)", wheel.display()));
+        assert_eq!(
+            declare(5),
+            format!(
+                "Invalid archive {}: `spam_eggs/__init__.py` doesn't have its declared size of 5 bytes",
+                wheel.display()));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let base = Path::new("/venv/lib/python3.12/site-packages");
+        assert_eq!(relative_path(base, Path::new("/venv/lib/python3.12/site-packages/a/b.py")), "a/b.py");
+        assert_eq!(relative_path(base, Path::new("/venv/bin/tool")), "../../../bin/tool");
+        assert!(is_safe_path("a/b.py"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path("a/../../b"));
+        assert!(!is_safe_path("a\\..\\b"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Inventory of the distributions installed in a `site-packages` directory,
+//! from their `.dist-info` directories and legacy `.egg-info` directories or
+//! files.
+//!
+//! Only the directory itself is scanned, like `pip list --path`.
+
+use crate::error::Error;
+use crate::find_links::Diagnostic;
+use crate::metadata::Metadata;
+use crate::name::PackageName;
+use crate::requirement::Requirement;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// How a distribution records its installation.
+pub enum DistributionKind {
+    /// A `.dist-info` directory with a `METADATA` file.
+    DistInfo,
+    /// A `.egg-info` directory with a `PKG-INFO` file, or a `.egg-info` file
+    /// which is itself the `PKG-INFO`.
+    EggInfo,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An installed distribution.
+pub struct InstalledDistribution {
+    /// The normalized name from the metadata.
+    pub name: PackageName,
+    /// The version from the metadata.
+    pub version: Version,
+    /// The `.dist-info` or `.egg-info` path.
+    pub path: PathBuf,
+    pub kind: DistributionKind,
+    pub metadata: Metadata,
+    /// The contents of the `INSTALLER` file, e.g. `pip`.
+    pub installer: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The result of scanning a `site-packages` directory.
+pub struct SitePackages {
+    /// Distributions grouped by normalized name, newest version first.
+    pub packages: BTreeMap<PackageName, Vec<InstalledDistribution>>,
+    /// Metadata directories which could not be read.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl InstalledDistribution {
+    /// Reads a `.dist-info` directory, or a `.egg-info` directory or file.
+    ///
+    /// The `Requires-Dist` of an `.egg-info` directory without any are read
+    /// from its `requires.txt`, where setuptools writes them.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<InstalledDistribution, Error> {
+        let path = path.as_ref();
+        let kind = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dist-info") => DistributionKind::DistInfo,
+            Some("egg-info") => DistributionKind::EggInfo,
+            _ => return Err(Error::metadata_error(format!("{}: not a `.dist-info` or `.egg-info`", path.display()))),
+        };
+        let metadata_path = match kind {
+            DistributionKind::DistInfo => path.join("METADATA"),
+            DistributionKind::EggInfo if path.is_dir() => path.join("PKG-INFO"),
+            DistributionKind::EggInfo => path.to_path_buf(),
+        };
+        let contents = fs::read_to_string(&metadata_path).map_err(|err| Error::io_error(&metadata_path, err))?;
+        let mut metadata = Metadata::parse(&contents)
+            .map_err(|err| Error::metadata_error(format!("{}: {}", metadata_path.display(), err)))?;
+
+        let requires_txt = path.join("requires.txt");
+        if kind == DistributionKind::EggInfo && metadata.requires_dist.is_empty() && requires_txt.is_file() {
+            let contents = fs::read_to_string(&requires_txt).map_err(|err| Error::io_error(&requires_txt, err))?;
+            metadata.requires_dist = parse_requires_txt(&contents)
+                .map_err(|err| Error::metadata_error(format!("{}: {}", requires_txt.display(), err)))?;
+        }
+
+        let installer = fs::read_to_string(path.join("INSTALLER"))
+            .ok()
+            .map(|installer| installer.trim().to_string())
+            .filter(|installer| !installer.is_empty());
+        Ok(InstalledDistribution {
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            path: path.to_path_buf(),
+            kind,
+            metadata,
+            installer,
+        })
+    }
+}
+
+impl SitePackages {
+    /// Scans `directory`.
+    ///
+    /// ```no_run
+    /// # use pep440::installed::SitePackages;
+    /// let site_packages = SitePackages::scan("/usr/lib/python3/dist-packages").unwrap();
+    /// for distribution in site_packages.iter() {
+    ///     println!("{} {}", distribution.name, distribution.version);
+    /// }
+    /// for (name, installs) in site_packages.duplicates() {
+    ///     eprintln!("{} is installed {} times", name, installs.len());
+    /// }
+    /// ```
+    pub fn scan<P: AsRef<Path>>(directory: P) -> Result<SitePackages, Error> {
+        let directory = directory.as_ref();
+        let read_error = |err: std::io::Error| Error::io_error(directory, err);
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(directory).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if extension == Some("dist-info") || extension == Some("egg-info") {
+                paths.push(path);
+            }
+        }
+        // `read_dir` returns entries in no particular order.
+        paths.sort();
+
+        let mut site_packages = SitePackages::default();
+        for path in paths {
+            match InstalledDistribution::read(&path) {
+                Ok(distribution) => site_packages
+                    .packages
+                    .entry(distribution.name.clone())
+                    .or_default()
+                    .push(distribution),
+                Err(err) => site_packages.diagnostics.push(Diagnostic { path, reason: err.to_string() }),
+            }
+        }
+        for distributions in site_packages.packages.values_mut() {
+            distributions.sort_by(|a, b| b.version.cmp(&a.version).then_with(|| a.path.cmp(&b.path)));
+        }
+        Ok(site_packages)
+    }
+
+    /// Returns the installs of a project, newest version first. There is
+    /// normally at most one.
+    pub fn get(&self, name: &PackageName) -> &[InstalledDistribution] {
+        self.packages.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns every distribution, by name.
+    pub fn iter(&self) -> impl Iterator<Item = &InstalledDistribution> {
+        self.packages.values().flatten()
+    }
+
+    /// Returns the projects which are installed more than once, whether at
+    /// different versions or not. Python imports whichever it finds first.
+    pub fn duplicates(&self) -> impl Iterator<Item = (&PackageName, &[InstalledDistribution])> {
+        self.packages
+            .iter()
+            .filter(|(_, distributions)| distributions.len() > 1)
+            .map(|(name, distributions)| (name, distributions.as_slice()))
+    }
+}
+
+/// Parses the `requires.txt` of an `.egg-info` directory: requirements,
+/// grouped into `[extra]`, `[:marker]` and `[extra:marker]` sections.
+fn parse_requires_txt(contents: &str) -> Result<Vec<Requirement>, Error> {
+    let mut section_marker: Option<String> = None;
+    let mut requirements = vec![];
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            let (extra, marker) = section.split_once(':').unwrap_or((section, ""));
+            section_marker = match (extra.trim(), marker.trim()) {
+                ("", "") => None,
+                ("", marker) => Some(marker.to_string()),
+                (extra, "") => Some(format!("extra == \"{}\"", extra)),
+                (extra, marker) => Some(format!("({}) and extra == \"{}\"", marker, extra)),
+            };
+            continue;
+        }
+        let requirement = match (&section_marker, line.split_once(';')) {
+            (None, _) => line.to_string(),
+            (Some(marker), None) => format!("{}; {}", line, marker),
+            (Some(marker), Some((requirement, own))) => format!("{}; ({}) and ({})", requirement, own, marker),
+        };
+        requirements.push(Requirement::parse(&requirement)?);
+    }
+    Ok(requirements)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::installed::*;
+    use crate::scratch::ScratchDir;
+
+    fn metadata(name: &str, version: &str, extra: &str) -> String {
+        format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n{}", name, version, extra)
+    }
+
+    #[test]
+    fn test_scan() {
+        let dir = ScratchDir::new("installed");
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(
+            "requests-2.31.0.dist-info/METADATA",
+            &metadata("requests", "2.31.0", "Requires-Python: >=3.7\nRequires-Dist: idna<4,>=2.5\n"),
+        );
+        write("requests-2.31.0.dist-info/INSTALLER", "pip\n");
+        write("Requests-2.28.1.dist-info/METADATA", &metadata("Requests", "2.28.1", ""));
+        write(
+            "legacy.egg-info/PKG-INFO",
+            &metadata("Legacy", "0.1", ""),
+        );
+        write("legacy.egg-info/requires.txt", "six\n\n[socks]\nPySocks>=1.5\n\n[:sys_platform == \"win32\"]\ncolorama\n");
+        write("single-1.0-py3.8.egg-info", &metadata("single", "1.0", ""));
+        write("broken-1.0.dist-info/METADATA", "Name: broken\n");
+        write("missing-1.0.dist-info/RECORD", "");
+        write("requests/__init__.py", "");
+
+        let site_packages = SitePackages::scan(&dir).unwrap();
+        let names: Vec<String> = site_packages
+            .iter()
+            .map(|distribution| format!("{} {}", distribution.name, distribution.version))
+            .collect();
+        assert_eq!(names, vec!["legacy 0.1", "requests 2.31.0", "requests 2.28.1", "single 1.0"]);
+
+        let requests = PackageName::parse("requests").unwrap();
+        let installed = &site_packages.get(&requests)[0];
+        assert_eq!(installed.kind, DistributionKind::DistInfo);
+        assert_eq!(installed.installer.as_deref(), Some("pip"));
+        assert_eq!(installed.metadata.requires_python.as_ref().unwrap().to_string(), ">=3.7");
+        assert_eq!(installed.metadata.requires_dist[0].to_string(), "idna<4, >=2.5");
+        let duplicates: Vec<(&PackageName, usize)> =
+            site_packages.duplicates().map(|(name, installs)| (name, installs.len())).collect();
+        assert_eq!(duplicates, vec![(&requests, 2)]);
+
+        let legacy = &site_packages.get(&PackageName::parse("legacy").unwrap())[0];
+        assert_eq!(legacy.kind, DistributionKind::EggInfo);
+        let requires: Vec<String> = legacy.metadata.requires_dist.iter().map(|req| req.to_string()).collect();
+        assert_eq!(requires, vec![
+            "six",
+            "pysocks>=1.5; extra == \"socks\"",
+            "colorama; sys_platform == \"win32\"",
+        ]);
+
+        let failed: Vec<&str> = site_packages
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(failed, vec!["broken-1.0.dist-info", "missing-1.0.dist-info"]);
+        assert!(site_packages.diagnostics[0].reason.contains("missing `Metadata-Version`"));
+    }
+
+    #[test]
+    fn test_requires_txt() {
+        let requirements = parse_requires_txt(
+            "a\n[test:python_version < \"3.8\"]\nb; os_name == \"nt\"\n[]\nc\n",
+        )
+        .unwrap();
+        let requirements: Vec<String> = requirements.iter().map(|req| req.to_string()).collect();
+        assert_eq!(requirements, vec![
+            "a",
+            "b; os_name == \"nt\" and python_version < \"3.8\" and extra == \"test\"",
+            "c",
+        ]);
+    }
+}
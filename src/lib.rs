@@ -20,10 +20,98 @@
 //! * An `is_canonical()` function which can check whether or not a version
 //!   string is in canonical form.
 //! * Tons of tests (copied from `packaging.version`).
+//! * Version specifiers and specifier sets, in the `specifier` module.
+//! * Evaluation of `Requires-Python` against interpreter versions, in the
+//!   `requires_python` module.
+//! * Cross-checking of Python version trove classifiers against
+//!   `Requires-Python`, in the `classifiers` module.
+//! * PEP508 requirements, markers and names, in the `requirement`, `marker`
+//!   and `name` modules.
+//! * Parsing of the `[project]`, `[build-system]` and `[dependency-groups]`
+//!   tables of `pyproject.toml`, in the `pyproject` module (behind the
+//!   `pyproject` feature).
+//! * Reading and writing of `pylock.toml` lock files, in the `pylock` module
+//!   (behind the `pylock` feature).
+//! * Extraction of inline `# /// script` metadata from Python scripts, in
+//!   the `script` module (behind the `script` feature).
+//! * Parsing of pip requirements files, in the `requirements_txt` module.
+//! * Parsing of direct URLs such as `git+https://host/repo.git@v1.2`, in the
+//!   `direct_url` module, which also reads and writes `direct_url.json`
+//!   (behind the `json` feature).
+//! * Parsing of core metadata (`METADATA` and `PKG-INFO`), in the `metadata`
+//!   module, and an inventory of the distributions installed in a
+//!   `site-packages` directory, in the `installed` module.
+//! * Checking that the dependencies of installed distributions are satisfied,
+//!   like `pip check`, in the `check` module.
+//! * Parsing of `RECORD` files, in the `record` module, which also verifies
+//!   installed files against them (behind the `record` feature).
+//! * Installation of wheels into a set of scheme directories, in the
+//!   `install` module (behind the `installer` feature).
+//! * Validation of wheel archives before publishing, like
+//!   `check-wheel-contents`, in the `wheel_check` module (behind the
+//!   `installer` feature).
+//! * Uninstallation of distributions by their `RECORD`, in the `uninstall`
+//!   module.
+//! * Parsing of wheel and sdist filenames, in the `filename` module.
+//! * Reading of `PKG-INFO` and `pyproject.toml` from sdists, and validation
+//!   of their versions, in the `sdist` module (behind the `sdist` feature).
+//! * Parsing of simple repository API project pages, in the `simple` module,
+//!   and models of its JSON flavour in the `simple_json` module (behind the
+//!   `json` feature).
+//! * Selection of the best file to install from a project's files, optionally
+//!   ignoring files uploaded after a cutoff, in the `candidate` module, with
+//!   the upload timestamps in the `timestamp` module.
+//! * Lookup of a project's files across several indexes, with strategies
+//!   against dependency confusion, in the `index` module.
+//! * Scanning of flat `--find-links` directories, in the `find_links` module.
+//! * Dependency resolution with the PubGrub algorithm, in the `resolver`
+//!   module, over the version sets of the `ranges` module. Universal
+//!   resolution for several platforms and Python versions at once reasons
+//!   about environments with the `marker_set` module.
 #[macro_use]
 extern crate lazy_static;
 
+pub mod candidate;
+pub mod check;
+pub mod classifiers;
+pub mod direct_url;
 mod error;
+pub mod filename;
+pub mod find_links;
+pub mod index;
+#[cfg(feature = "installer")]
+pub mod install;
+pub mod installed;
+pub mod marker;
+pub mod marker_set;
+pub mod metadata;
+pub mod name;
+#[cfg(feature = "pyproject")]
+pub mod pyproject;
+#[cfg(feature = "pylock")]
+pub mod pylock;
+pub mod ranges;
+pub mod record;
+pub mod requirement;
+pub mod requirements_txt;
+pub mod requires_python;
+pub mod resolver;
+#[cfg(test)]
+mod scratch;
+#[cfg(feature = "script")]
+pub mod script;
+#[cfg(feature = "sdist")]
+pub mod sdist;
+pub mod simple;
+#[cfg(feature = "json")]
+pub mod simple_json;
+pub mod specifier;
+pub mod timestamp;
+pub mod uninstall;
+#[cfg(feature = "installer")]
+pub mod wheel_check;
+
+pub use error::Error;
 
 use regex::{Captures, Regex};
 use std::cmp::Ordering;
@@ -148,11 +236,8 @@ impl Version {
                 Some(n1) => n1.as_str().parse().ok(),
                 None => match captures.name("post_n2") {
                     Some(n2) => n2.as_str().parse().ok(),
-                    None => match captures.name("post_l") {
-                        // "1.2.3.post" -- default 0 in this case
-                        Some(_) => Some(0),
-                        None => None
-                    }
+                    // "1.2.3.post" -- default 0 in this case
+                    None => captures.name("post_l").map(|_| 0),
                 }
             }
         }
@@ -391,6 +476,95 @@ impl Version {
     pub fn normalize(&self) -> String {
         format!("{}{}", self.public_str(), self.local_str())
     }
+
+    /// Constructs a final release version (epoch `0`, no pre-, post-, dev- or
+    /// local segments) from the given release segments.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// let ver = Version::from_release(vec![3, 12]);
+    /// assert_eq!(ver, Version::parse("3.12.0").unwrap());
+    /// ```
+    pub fn from_release(release: Vec<u32>) -> Version {
+        Version {
+            epoch: 0,
+            release,
+            pre: None,
+            post: None,
+            dev: None,
+            local: vec![],
+        }
+    }
+
+    /// Returns `true` if the version is a pre-release, that is, if it has a
+    /// pre-release or a dev-release segment.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// assert!(Version::parse("3.13.0rc1").unwrap().is_prerelease());
+    /// assert!(Version::parse("1.0.post1.dev0").unwrap().is_prerelease());
+    /// assert!(!Version::parse("1.0.post1").unwrap().is_prerelease());
+    /// ```
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Returns `true` if the version has a post-release segment.
+    pub fn is_postrelease(&self) -> bool {
+        self.post.is_some()
+    }
+
+    /// Returns `true` if the version has a dev-release segment.
+    pub fn is_devrelease(&self) -> bool {
+        self.dev.is_some()
+    }
+
+    /// Returns a copy of the version with the local segment removed.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// let ver = Version::parse("1.2.3rc1+ubuntu.1").unwrap();
+    /// assert_eq!(ver.public().normalize(), "1.2.3rc1".to_string());
+    /// ```
+    pub fn public(&self) -> Version {
+        Version {
+            local: vec![],
+            ..self.clone()
+        }
+    }
+
+    /// Returns the base version, which is the epoch and release segments only.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// let ver = Version::parse("1!1.2.3rc1.post2.dev3+abc").unwrap();
+    /// assert_eq!(ver.base().normalize(), "1!1.2.3".to_string());
+    /// ```
+    pub fn base(&self) -> Version {
+        Version {
+            epoch: self.epoch,
+            ..Version::from_release(self.release.clone())
+        }
+    }
+
+    /// Returns the base version with its release truncated to exactly `len`
+    /// segments. Missing segments are filled in with zeros, so truncating
+    /// `3.9` to three segments gives `3.9.0`.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// let ver = Version::parse("3.13.0rc1").unwrap();
+    /// assert_eq!(ver.truncate_release(2).normalize(), "3.13".to_string());
+    /// assert_eq!(ver.truncate_release(4).normalize(), "3.13.0.0".to_string());
+    /// ```
+    pub fn truncate_release(&self, len: usize) -> Version {
+        let mut release: Vec<u32> = self.release.iter().copied().take(len).collect();
+        release.resize(len, 0);
+        Version {
+            epoch: self.epoch,
+            ..Version::from_release(release)
+        }
+    }
 }
 
 /// This implementation is returns the normalized version of the version.
@@ -449,7 +623,7 @@ struct CmpKey<'a> {
 }
 
 impl Version {
-    fn cmp_key(&self) -> CmpKey {
+    fn cmp_key(&self) -> CmpKey<'_> {
         let epoch = self.epoch;
 
         // We want to ignore trailing zeros in the release, so make a slice of just the
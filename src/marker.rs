@@ -0,0 +1,613 @@
+//! Environment markers, as described in PEP508, e.g.
+//! `python_version >= "3.8" and sys_platform == "linux"`.
+//!
+//! Evaluation follows `packaging.markers`: when the operator and the right
+//! side form a version specifier, the left side is compared as a version, and
+//! otherwise the comparison falls back to comparing strings, as Python does.
+
+use crate::error::Error;
+use crate::name::PackageName;
+use crate::specifier::Specifier;
+use crate::Version;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A marker variable, i.e. a value taken from the target environment.
+pub enum MarkerVariable {
+    ImplementationName,
+    ImplementationVersion,
+    OsName,
+    PlatformMachine,
+    PlatformPythonImplementation,
+    PlatformRelease,
+    PlatformSystem,
+    PlatformVersion,
+    PythonFullVersion,
+    PythonVersion,
+    SysPlatform,
+    Extra,
+}
+
+impl MarkerVariable {
+    fn from_str(name: &str) -> Option<MarkerVariable> {
+        use MarkerVariable::*;
+        match name {
+            "implementation_name" => Some(ImplementationName),
+            "implementation_version" => Some(ImplementationVersion),
+            "os_name" | "os.name" => Some(OsName),
+            "platform_machine" | "platform.machine" => Some(PlatformMachine),
+            "platform_python_implementation"
+            | "platform.python_implementation"
+            | "python_implementation" => Some(PlatformPythonImplementation),
+            "platform_release" => Some(PlatformRelease),
+            "platform_system" => Some(PlatformSystem),
+            "platform_version" | "platform.version" => Some(PlatformVersion),
+            "python_full_version" => Some(PythonFullVersion),
+            "python_version" => Some(PythonVersion),
+            "sys_platform" | "sys.platform" => Some(SysPlatform),
+            "extra" => Some(Extra),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for the variables which hold a Python version and can
+    /// therefore be reasoned about with version ordering.
+    pub fn is_python_version(&self) -> bool {
+        matches!(self, MarkerVariable::PythonVersion | MarkerVariable::PythonFullVersion)
+    }
+}
+
+impl fmt::Display for MarkerVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MarkerVariable::*;
+        let name = match self {
+            ImplementationName => "implementation_name",
+            ImplementationVersion => "implementation_version",
+            OsName => "os_name",
+            PlatformMachine => "platform_machine",
+            PlatformPythonImplementation => "platform_python_implementation",
+            PlatformRelease => "platform_release",
+            PlatformSystem => "platform_system",
+            PlatformVersion => "platform_version",
+            PythonFullVersion => "python_full_version",
+            PythonVersion => "python_version",
+            SysPlatform => "sys_platform",
+            Extra => "extra",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// Either side of a marker comparison.
+pub enum MarkerValue {
+    Variable(MarkerVariable),
+    Literal(String),
+}
+
+impl fmt::Display for MarkerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerValue::Variable(variable) => write!(f, "{}", variable),
+            MarkerValue::Literal(value) if value.contains('"') => write!(f, "'{}'", value),
+            MarkerValue::Literal(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A marker comparison operator.
+pub enum MarkerOperator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+    Compatible,
+    ArbitraryEqual,
+    In,
+    NotIn,
+}
+
+impl fmt::Display for MarkerOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MarkerOperator::*;
+        let operator = match self {
+            Equal => "==",
+            NotEqual => "!=",
+            LessThan => "<",
+            LessThanEqual => "<=",
+            GreaterThan => ">",
+            GreaterThanEqual => ">=",
+            Compatible => "~=",
+            ArbitraryEqual => "===",
+            In => "in",
+            NotIn => "not in",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A single comparison, such as `python_version >= "3.8"`.
+pub struct MarkerExpression {
+    pub lhs: MarkerValue,
+    pub operator: MarkerOperator,
+    pub rhs: MarkerValue,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A parsed marker: comparisons combined with `and` and `or`.
+pub enum MarkerTree {
+    Expression(MarkerExpression),
+    And(Vec<MarkerTree>),
+    Or(Vec<MarkerTree>),
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+/// The values of the marker variables for a target environment, as they
+/// would be computed by Python on that environment.
+pub struct MarkerEnvironment {
+    pub implementation_name: String,
+    pub implementation_version: String,
+    pub os_name: String,
+    pub platform_machine: String,
+    pub platform_python_implementation: String,
+    pub platform_release: String,
+    pub platform_system: String,
+    pub platform_version: String,
+    pub python_full_version: String,
+    pub python_version: String,
+    pub sys_platform: String,
+}
+
+impl MarkerEnvironment {
+    /// Returns the value of the given variable. `extra` has no value in the
+    /// environment, so gives the empty string.
+    pub fn get(&self, variable: MarkerVariable) -> &str {
+        use MarkerVariable::*;
+        match variable {
+            ImplementationName => &self.implementation_name,
+            ImplementationVersion => &self.implementation_version,
+            OsName => &self.os_name,
+            PlatformMachine => &self.platform_machine,
+            PlatformPythonImplementation => &self.platform_python_implementation,
+            PlatformRelease => &self.platform_release,
+            PlatformSystem => &self.platform_system,
+            PlatformVersion => &self.platform_version,
+            PythonFullVersion => &self.python_full_version,
+            PythonVersion => &self.python_version,
+            SysPlatform => &self.sys_platform,
+            Extra => "",
+        }
+    }
+}
+
+impl MarkerExpression {
+    /// Evaluates the comparison in the given environment, with the given
+    /// extras requested.
+    pub fn evaluate(&self, env: &MarkerEnvironment, extras: &[PackageName]) -> bool {
+        use MarkerValue::*;
+        match (&self.lhs, &self.rhs) {
+            (Variable(MarkerVariable::Extra), Literal(extra))
+            | (Literal(extra), Variable(MarkerVariable::Extra)) => {
+                let requested = PackageName::parse(extra)
+                    .map(|extra| extras.contains(&extra))
+                    .unwrap_or(false);
+                match self.operator {
+                    MarkerOperator::Equal => requested,
+                    MarkerOperator::NotEqual => !requested,
+                    _ => false,
+                }
+            }
+            (lhs, rhs) => {
+                let lhs = resolve(lhs, env);
+                let rhs = resolve(rhs, env);
+                compare(lhs, self.operator, rhs)
+            }
+        }
+    }
+}
+
+fn resolve<'a>(value: &'a MarkerValue, env: &'a MarkerEnvironment) -> &'a str {
+    match value {
+        MarkerValue::Variable(variable) => env.get(*variable),
+        MarkerValue::Literal(literal) => literal,
+    }
+}
+
+/// Compares two marker values, as versions if possible and as strings if not.
+pub(crate) fn compare(lhs: &str, operator: MarkerOperator, rhs: &str) -> bool {
+    use MarkerOperator::*;
+    match operator {
+        In => return rhs.contains(lhs),
+        NotIn => return !rhs.contains(lhs),
+        _ => {}
+    }
+    let specifier = Specifier::parse(&format!("{}{}", operator, rhs));
+    if let (Some(specifier), Some(version)) = (&specifier, Version::parse(lhs)) {
+        return specifier.contains(&version);
+    }
+    match operator {
+        Equal | ArbitraryEqual => lhs == rhs,
+        NotEqual => lhs != rhs,
+        // A version specifier doesn't order something which isn't a version.
+        _ if specifier.is_some() => false,
+        LessThan => lhs < rhs,
+        LessThanEqual => lhs <= rhs,
+        GreaterThan => lhs > rhs,
+        GreaterThanEqual => lhs >= rhs,
+        // `~=` has no string meaning.
+        Compatible | In | NotIn => false,
+    }
+}
+
+impl MarkerTree {
+    /// Attempt to parse the given marker string.
+    ///
+    /// ```
+    /// # use pep440::marker::MarkerTree;
+    /// let marker = MarkerTree::parse(
+    ///     "python_version>='3.8' and (sys_platform=='linux' or os_name=='nt')").unwrap();
+    /// assert_eq!(
+    ///     marker.to_string(),
+    ///     "python_version >= \"3.8\" and (sys_platform == \"linux\" or os_name == \"nt\")");
+    /// ```
+    pub fn parse(input: &str) -> Result<MarkerTree, Error> {
+        let mut parser = Parser { input, tokens: tokenize(input)?, pos: 0 };
+        let tree = parser.parse_or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(tree)
+    }
+
+    /// Evaluates the marker in the given environment, with the given extras
+    /// requested.
+    ///
+    /// ```
+    /// # use pep440::marker::{MarkerEnvironment, MarkerTree};
+    /// # use pep440::name::PackageName;
+    /// let env = MarkerEnvironment {
+    ///     python_full_version: "3.11.4".to_string(),
+    ///     python_version: "3.11".to_string(),
+    ///     sys_platform: "linux".to_string(),
+    ///     ..MarkerEnvironment::default()
+    /// };
+    /// let marker = MarkerTree::parse("python_version < '3.12' and extra == 'Test'").unwrap();
+    /// assert!(!marker.evaluate(&env, &[]));
+    /// assert!(marker.evaluate(&env, &[PackageName::parse("test").unwrap()]));
+    /// ```
+    pub fn evaluate(&self, env: &MarkerEnvironment, extras: &[PackageName]) -> bool {
+        match self {
+            MarkerTree::Expression(expression) => expression.evaluate(env, extras),
+            MarkerTree::And(trees) => trees.iter().all(|tree| tree.evaluate(env, extras)),
+            MarkerTree::Or(trees) => trees.iter().any(|tree| tree.evaluate(env, extras)),
+        }
+    }
+
+    /// Calls `f` on every comparison in the marker.
+    pub fn for_each_expression<F: FnMut(&MarkerExpression)>(&self, f: &mut F) {
+        match self {
+            MarkerTree::Expression(expression) => f(expression),
+            MarkerTree::And(trees) | MarkerTree::Or(trees) => {
+                for tree in trees {
+                    tree.for_each_expression(f);
+                }
+            }
+        }
+    }
+
+    /// Returns the extras which the marker compares against with `extra ==`.
+    pub fn extras(&self) -> Vec<PackageName> {
+        let mut extras = vec![];
+        self.for_each_expression(&mut |expression| {
+            use MarkerValue::*;
+            if let (Variable(MarkerVariable::Extra), Literal(extra))
+            | (Literal(extra), Variable(MarkerVariable::Extra)) = (&expression.lhs, &expression.rhs)
+            {
+                if let Some(extra) = PackageName::parse(extra) {
+                    if !extras.contains(&extra) {
+                        extras.push(extra);
+                    }
+                }
+            }
+        });
+        extras
+    }
+}
+
+impl fmt::Display for MarkerExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.operator, self.rhs)
+    }
+}
+
+impl fmt::Display for MarkerTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerTree::Expression(expression) => write!(f, "{}", expression),
+            MarkerTree::And(trees) => {
+                let joined = trees
+                    .iter()
+                    .map(|tree| match tree {
+                        MarkerTree::Or(_) => format!("({})", tree),
+                        _ => tree.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" and ");
+                write!(f, "{}", joined)
+            }
+            MarkerTree::Or(trees) => {
+                let joined = trees
+                    .iter()
+                    .map(|tree| tree.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or ");
+                write!(f, "{}", joined)
+            }
+        }
+    }
+}
+
+impl FromStr for MarkerTree {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MarkerTree::parse(s)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Identifier(String),
+    String(String),
+    Operator(MarkerOperator),
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let error = |reason: &str| Error::marker_parse_error(input.to_string(), reason.to_string());
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c == '(' {
+            tokens.push(Token::LeftParen);
+            pos += 1;
+        } else if c == ')' {
+            tokens.push(Token::RightParen);
+            pos += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars[pos + 1..]
+                .iter()
+                .position(|&quote| quote == c)
+                .ok_or_else(|| error("unterminated string"))?;
+            tokens.push(Token::String(chars[pos + 1..pos + 1 + end].iter().collect()));
+            pos += end + 2;
+        } else if "<>=!~".contains(c) {
+            let start = pos;
+            while pos < chars.len() && "<>=!~".contains(chars[pos]) {
+                pos += 1;
+            }
+            let operator: String = chars[start..pos].iter().collect();
+            let operator = match operator.as_str() {
+                "==" => MarkerOperator::Equal,
+                "!=" => MarkerOperator::NotEqual,
+                "<" => MarkerOperator::LessThan,
+                "<=" => MarkerOperator::LessThanEqual,
+                ">" => MarkerOperator::GreaterThan,
+                ">=" => MarkerOperator::GreaterThanEqual,
+                "~=" => MarkerOperator::Compatible,
+                "===" => MarkerOperator::ArbitraryEqual,
+                _ => return Err(error(&format!("invalid operator `{}`", operator))),
+            };
+            tokens.push(Token::Operator(operator));
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            let start = pos;
+            while pos < chars.len()
+                && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_' || chars[pos] == '.')
+            {
+                pos += 1;
+            }
+            let word: String = chars[start..pos].iter().collect();
+            match word.as_str() {
+                "and" => tokens.push(Token::And),
+                "or" => tokens.push(Token::Or),
+                "in" => tokens.push(Token::Operator(MarkerOperator::In)),
+                "not" => match next_word_is_in(&chars, pos) {
+                    Some(end) => {
+                        tokens.push(Token::Operator(MarkerOperator::NotIn));
+                        pos = end;
+                    }
+                    _ => return Err(error("expected `in` after `not`")),
+                },
+                _ => tokens.push(Token::Identifier(word)),
+            }
+        } else {
+            return Err(error(&format!("unexpected character `{}`", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// If the word following position `pos` (after some whitespace) is `in`,
+/// returns the position just after it.
+fn next_word_is_in(chars: &[char], pos: usize) -> Option<usize> {
+    let mut start = pos;
+    while start < chars.len() && chars[start].is_whitespace() {
+        start += 1;
+    }
+    if start == pos {
+        return None;
+    }
+    let mut end = start;
+    while end < chars.len() && chars[end].is_ascii_alphabetic() {
+        end += 1;
+    }
+    let word: String = chars[start..end].iter().collect();
+    if word == "in" {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> Error {
+        Error::marker_parse_error(self.input.to_string(), reason.to_string())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<MarkerTree, Error> {
+        let mut trees = vec![self.parse_and()?];
+        while self.tokens.get(self.pos) == Some(&Token::Or) {
+            self.pos += 1;
+            trees.push(self.parse_and()?);
+        }
+        Ok(if trees.len() == 1 { trees.remove(0) } else { MarkerTree::Or(trees) })
+    }
+
+    fn parse_and(&mut self) -> Result<MarkerTree, Error> {
+        let mut trees = vec![self.parse_atom()?];
+        while self.tokens.get(self.pos) == Some(&Token::And) {
+            self.pos += 1;
+            trees.push(self.parse_atom()?);
+        }
+        Ok(if trees.len() == 1 { trees.remove(0) } else { MarkerTree::And(trees) })
+    }
+
+    fn parse_atom(&mut self) -> Result<MarkerTree, Error> {
+        if self.tokens.get(self.pos) == Some(&Token::LeftParen) {
+            self.pos += 1;
+            let tree = self.parse_or()?;
+            return match self.next() {
+                Some(Token::RightParen) => Ok(tree),
+                _ => Err(self.error("expected `)`")),
+            };
+        }
+        let lhs = self.parse_value()?;
+        let operator = match self.next() {
+            Some(Token::Operator(operator)) => operator,
+            _ => return Err(self.error("expected a comparison operator")),
+        };
+        let rhs = self.parse_value()?;
+        Ok(MarkerTree::Expression(MarkerExpression { lhs, operator, rhs }))
+    }
+
+    fn parse_value(&mut self) -> Result<MarkerValue, Error> {
+        match self.next() {
+            Some(Token::String(value)) => Ok(MarkerValue::Literal(value)),
+            Some(Token::Identifier(name)) => match MarkerVariable::from_str(&name) {
+                Some(variable) => Ok(MarkerValue::Variable(variable)),
+                None => Err(self.error(&format!("unknown marker variable `{}`", name))),
+            },
+            _ => Err(self.error("expected a marker variable or a quoted string")),
+        }
+    }
+}
+
+#[cfg(test)]
+/// Some of the test data here is pulled from the python-packaging tests,
+/// https://github.com/pypa/packaging/blob/main/tests/test_markers.py
+mod tests {
+    use crate::marker::*;
+
+    fn linux_311() -> MarkerEnvironment {
+        MarkerEnvironment {
+            implementation_name: "cpython".to_string(),
+            implementation_version: "3.11.4".to_string(),
+            os_name: "posix".to_string(),
+            platform_machine: "x86_64".to_string(),
+            platform_python_implementation: "CPython".to_string(),
+            platform_release: "6.1.0-13-amd64".to_string(),
+            platform_system: "Linux".to_string(),
+            platform_version: "#1 SMP PREEMPT_DYNAMIC Debian 6.1.55-1".to_string(),
+            python_full_version: "3.11.4".to_string(),
+            python_version: "3.11".to_string(),
+            sys_platform: "linux".to_string(),
+        }
+    }
+
+    const INVALID_MARKERS: &[&str] = &[
+        "", "python_version", "python_version >=", "python_version >= '3.8' and",
+        "(python_version >= '3.8'", "foo == 'bar'",
+        "python_version => '3.8'", "python_version not '3.8'",
+        "python_version == '3.8", "python_version ~ '3.8'",
+    ];
+
+    // (marker, expected) against linux_311().
+    const EVALUATIONS: &[(&str, bool)] = &[
+        ("python_version >= '3.8'", true),
+        ("python_version < '3.11'", false),
+        ("python_version == '3.11.*'", true),
+        ("python_full_version ~= '3.11.0'", true),
+        ("python_version > '3.9' and python_version < '3.12'", true),
+        ("os_name == 'nt' or sys_platform == 'linux'", true),
+        ("os_name == 'nt' or (sys_platform == 'linux' and platform_machine == 'aarch64')", false),
+        ("'linux' in sys_platform", true),
+        ("'win' not in sys_platform", true),
+        ("platform_release >= '6'", false),
+        ("platform_system != 'Windows'", true),
+        ("os.name == 'posix'", true),
+        ("sys_platform < 'win32'", true),
+        ("sys_platform >= 'win32'", false),
+        ("platform_machine <= 'x86_64'", true),
+        ("'3.8' == '3.8'", true),
+        ("'3.8' > '3.10'", false),
+        ("extra == 'test'", false),
+        ("extra != 'test'", true),
+        ("implementation_name == \"cpython\"", true),
+    ];
+
+    #[test]
+    fn test_invalid() {
+        for marker in INVALID_MARKERS {
+            assert!(MarkerTree::parse(marker).is_err(), "Parsed marker: '{}'", marker);
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let env = linux_311();
+        for (marker, expected) in EVALUATIONS {
+            let parsed = MarkerTree::parse(marker).expect(marker);
+            assert_eq!(parsed.evaluate(&env, &[]), *expected, "marker={}", marker);
+        }
+    }
+
+    #[test]
+    fn test_extras() {
+        let marker = MarkerTree::parse("extra == 'Foo_Bar' or extra == 'baz'").unwrap();
+        let foo = PackageName::parse("foo-bar").unwrap();
+        assert!(marker.evaluate(&linux_311(), std::slice::from_ref(&foo)));
+        assert_eq!(marker.extras(), vec![foo, PackageName::parse("baz").unwrap()]);
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let marker = "(os_name == 'nt' or os_name == 'posix') and \
+                      python_version not in '2.7, 3.4' or extra == 'a'";
+        let parsed = MarkerTree::parse(marker).unwrap();
+        let reparsed = MarkerTree::parse(&parsed.to_string()).unwrap();
+        assert_eq!(parsed, reparsed);
+    }
+}
@@ -0,0 +1,630 @@
+//! Sets of target environments, described by markers. These are what
+//! universal resolution reasons about: unlike a [`MarkerTree`], a set can be
+//! complemented, and it is known whether two sets overlap, e.g. that
+//! `python_version < "3.10"` and `python_version >= "3.10"` are disjoint and
+//! cover every environment together.
+//!
+//! A set is a union of conjunctions, and each conjunction constrains the
+//! marker variables independently of each other: the Python version to a
+//! [`Ranges`], and every other variable to the values it may or may not
+//! have. `python_version` and `python_full_version` are one and the same
+//! variable. Comparisons which can't be reasoned about, such as `in` on
+//! strings or an ordering of `platform_release`, are kept as conditions which
+//! either hold or don't.
+
+use crate::marker::{compare, MarkerEnvironment, MarkerExpression, MarkerOperator, MarkerTree, MarkerValue, MarkerVariable};
+use crate::name::PackageName;
+use crate::ranges::Ranges;
+use crate::requires_python::RequiresPython;
+use crate::specifier::Operator;
+use crate::Version;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// What a constraint of a conjunction is about.
+enum Dimension {
+    Python,
+    Variable(MarkerVariable),
+    /// A comparison in canonical form, see [`canonical`].
+    Condition(MarkerExpression),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// The values allowed in one dimension. Constraints which allow nothing or
+/// everything are never stored.
+enum Constraint {
+    Python(Ranges),
+    OneOf(BTreeSet<String>),
+    NoneOf(BTreeSet<String>),
+    Holds(bool),
+}
+
+/// Every dimension which is missing is unconstrained.
+type Conjunction = BTreeMap<Dimension, Constraint>;
+
+impl Constraint {
+    /// Returns `None` if no value is allowed by both constraints.
+    fn intersection(&self, other: &Constraint) -> Option<Constraint> {
+        use Constraint::*;
+        let intersection = match (self, other) {
+            (Python(a), Python(b)) => Python(a.intersection(b)),
+            (OneOf(a), OneOf(b)) => OneOf(a.intersection(b).cloned().collect()),
+            (OneOf(a), NoneOf(b)) | (NoneOf(b), OneOf(a)) => OneOf(a.difference(b).cloned().collect()),
+            (NoneOf(a), NoneOf(b)) => NoneOf(a.union(b).cloned().collect()),
+            (Holds(a), Holds(b)) if a == b => Holds(*a),
+            _ => return None,
+        };
+        match &intersection {
+            Python(ranges) if ranges.is_empty() => None,
+            OneOf(values) if values.is_empty() => None,
+            _ => Some(intersection),
+        }
+    }
+
+    /// Returns `None` if every value is allowed by either constraint.
+    fn union(&self, other: &Constraint) -> Option<Constraint> {
+        use Constraint::*;
+        let union = match (self, other) {
+            (Python(a), Python(b)) => Python(a.union(b)),
+            (OneOf(a), OneOf(b)) => OneOf(a.union(b).cloned().collect()),
+            (OneOf(a), NoneOf(b)) | (NoneOf(b), OneOf(a)) => NoneOf(b.difference(a).cloned().collect()),
+            (NoneOf(a), NoneOf(b)) => NoneOf(a.intersection(b).cloned().collect()),
+            (Holds(a), Holds(b)) if a == b => Holds(*a),
+            _ => return None,
+        };
+        match &union {
+            Python(ranges) if *ranges == Ranges::full() => None,
+            NoneOf(values) if values.is_empty() => None,
+            _ => Some(union),
+        }
+    }
+
+    fn complement(&self) -> Constraint {
+        use Constraint::*;
+        match self {
+            Python(ranges) => Python(ranges.complement()),
+            OneOf(values) => NoneOf(values.clone()),
+            NoneOf(values) => OneOf(values.clone()),
+            Holds(holds) => Holds(!holds),
+        }
+    }
+
+    fn subset_of(&self, other: &Constraint) -> bool {
+        self.intersection(&other.complement()).is_none()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A set of target environments.
+///
+/// ```
+/// # use pep440::marker::MarkerTree;
+/// # use pep440::marker_set::MarkerSet;
+/// let old = MarkerSet::from_marker(&MarkerTree::parse("python_version < '3.10'").unwrap(), &[]);
+/// let windows = MarkerSet::from_marker(&MarkerTree::parse("sys_platform == 'win32'").unwrap(), &[]);
+/// assert_eq!(old.complement().to_string(), "python_version >= \"3.10\"");
+/// assert!(old.union(&old.complement()).is_full());
+/// assert!(!old.is_disjoint(&windows));
+/// assert_eq!(
+///     old.intersection(&windows.complement()).to_string(),
+///     "python_version < \"3.10\" and sys_platform != \"win32\"");
+/// ```
+pub struct MarkerSet {
+    conjunctions: Vec<Conjunction>,
+}
+
+impl MarkerSet {
+    /// The set of every environment.
+    pub fn full() -> MarkerSet {
+        MarkerSet { conjunctions: vec![Conjunction::new()] }
+    }
+
+    /// The set of no environment.
+    pub fn empty() -> MarkerSet {
+        MarkerSet { conjunctions: vec![] }
+    }
+
+    fn constrained(dimension: Dimension, constraint: Constraint) -> MarkerSet {
+        MarkerSet { conjunctions: vec![std::iter::once((dimension, constraint)).collect()] }
+    }
+
+    fn constant(holds: bool) -> MarkerSet {
+        if holds {
+            MarkerSet::full()
+        } else {
+            MarkerSet::empty()
+        }
+    }
+
+    /// The environments in which the marker holds, when the given extras are
+    /// requested.
+    pub fn from_marker(marker: &MarkerTree, extras: &[PackageName]) -> MarkerSet {
+        match marker {
+            MarkerTree::Expression(expression) => MarkerSet::from_expression(expression, extras),
+            MarkerTree::And(trees) => trees
+                .iter()
+                .fold(MarkerSet::full(), |set, tree| set.intersection(&MarkerSet::from_marker(tree, extras))),
+            MarkerTree::Or(trees) => trees
+                .iter()
+                .fold(MarkerSet::empty(), |set, tree| set.union(&MarkerSet::from_marker(tree, extras))),
+        }
+    }
+
+    fn from_expression(expression: &MarkerExpression, extras: &[PackageName]) -> MarkerSet {
+        use MarkerValue::*;
+        let (variable, operator, value) = match (&expression.lhs, &expression.rhs) {
+            (Variable(MarkerVariable::Extra), Literal(_)) | (Literal(_), Variable(MarkerVariable::Extra)) => {
+                return MarkerSet::constant(expression.evaluate(&MarkerEnvironment::default(), extras));
+            }
+            (Literal(lhs), Literal(rhs)) => return MarkerSet::constant(compare(lhs, expression.operator, rhs)),
+            (Variable(variable), Literal(value)) => (*variable, expression.operator, value),
+            (Literal(value), Variable(variable)) => match flip(expression.operator) {
+                Some(operator) => (*variable, operator, value),
+                None => return MarkerSet::condition(expression),
+            },
+            (Variable(_), Variable(_)) => return MarkerSet::condition(expression),
+        };
+
+        if variable.is_python_version() {
+            if let Some(ranges) = python_ranges(variable, operator, value) {
+                return MarkerSet::python(ranges);
+            }
+        } else {
+            let values = std::iter::once(value.clone()).collect();
+            match operator {
+                MarkerOperator::Equal | MarkerOperator::ArbitraryEqual => {
+                    return MarkerSet::constrained(Dimension::Variable(variable), Constraint::OneOf(values));
+                }
+                MarkerOperator::NotEqual => {
+                    return MarkerSet::constrained(Dimension::Variable(variable), Constraint::NoneOf(values));
+                }
+                _ => {}
+            }
+        }
+        MarkerSet::condition(expression)
+    }
+
+    fn condition(expression: &MarkerExpression) -> MarkerSet {
+        match canonical(expression) {
+            Some((expression, holds)) => MarkerSet::constrained(Dimension::Condition(expression), Constraint::Holds(holds)),
+            // A comparison without a negation couldn't be written out after
+            // a complement, so it is assumed to hold.
+            None => MarkerSet::full(),
+        }
+    }
+
+    fn python(ranges: Ranges) -> MarkerSet {
+        if ranges.is_empty() {
+            MarkerSet::empty()
+        } else if ranges == Ranges::full() {
+            MarkerSet::full()
+        } else {
+            MarkerSet::constrained(Dimension::Python, Constraint::Python(ranges))
+        }
+    }
+
+    /// The environments whose interpreter is allowed by `Requires-Python`.
+    ///
+    /// ```
+    /// # use pep440::marker_set::MarkerSet;
+    /// # use pep440::requires_python::RequiresPython;
+    /// let python = MarkerSet::from_requires_python(&RequiresPython::parse(">=3.9, <3.14").unwrap());
+    /// assert_eq!(python.to_string(), "python_version >= \"3.9\" and python_version < \"3.14\"");
+    /// ```
+    pub fn from_requires_python(requires_python: &RequiresPython) -> MarkerSet {
+        let ranges = requires_python.specifiers.specifiers.iter().fold(Ranges::full(), |ranges, specifier| {
+            let operator = match specifier.operator {
+                Operator::Compatible => MarkerOperator::Compatible,
+                Operator::Equal => MarkerOperator::Equal,
+                Operator::NotEqual => MarkerOperator::NotEqual,
+                Operator::LessThanEqual => MarkerOperator::LessThanEqual,
+                Operator::GreaterThanEqual => MarkerOperator::GreaterThanEqual,
+                Operator::LessThan => MarkerOperator::LessThan,
+                Operator::GreaterThan => MarkerOperator::GreaterThan,
+                Operator::ArbitraryEqual => MarkerOperator::ArbitraryEqual,
+            };
+            let value = if specifier.wildcard {
+                format!("{}.*", specifier.version)
+            } else {
+                specifier.version.to_string()
+            };
+            let specified = python_ranges(MarkerVariable::PythonFullVersion, operator, &value)
+                .unwrap_or_else(|| Ranges::from_specifier(specifier));
+            ranges.intersection(&specified)
+        });
+        MarkerSet::python(ranges)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conjunctions.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.complement().is_empty()
+    }
+
+    pub fn intersection(&self, other: &MarkerSet) -> MarkerSet {
+        let mut conjunctions = vec![];
+        for left in &self.conjunctions {
+            for right in &other.conjunctions {
+                if let Some(conjunction) = intersect(left, right) {
+                    conjunctions.push(conjunction);
+                }
+            }
+        }
+        MarkerSet { conjunctions: simplify(conjunctions) }
+    }
+
+    pub fn union(&self, other: &MarkerSet) -> MarkerSet {
+        let conjunctions = self.conjunctions.iter().chain(&other.conjunctions).cloned().collect();
+        MarkerSet { conjunctions: simplify(conjunctions) }
+    }
+
+    pub fn complement(&self) -> MarkerSet {
+        self.conjunctions.iter().fold(MarkerSet::full(), |set, conjunction| {
+            let conjunctions = conjunction
+                .iter()
+                .map(|(dimension, constraint)| std::iter::once((dimension.clone(), constraint.complement())).collect())
+                .collect();
+            set.intersection(&MarkerSet { conjunctions: simplify(conjunctions) })
+        })
+    }
+
+    /// Returns `true` if every environment of this set is in `other`.
+    pub fn subset_of(&self, other: &MarkerSet) -> bool {
+        self.intersection(&other.complement()).is_empty()
+    }
+
+    pub fn is_disjoint(&self, other: &MarkerSet) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// Writes the set out as a marker, or returns `None` for the full set.
+    /// The empty set is an empty `or`, which never holds.
+    pub fn to_marker(&self) -> Option<MarkerTree> {
+        if self.is_full() {
+            return None;
+        }
+        let mut trees: Vec<MarkerTree> = self.conjunctions.iter().filter_map(conjunction_marker).collect();
+        if trees.len() == 1 {
+            trees.pop()
+        } else {
+            Some(MarkerTree::Or(trees))
+        }
+    }
+}
+
+impl PartialEq for MarkerSet {
+    /// Sets are equal if they hold in the same environments, however they
+    /// are written.
+    fn eq(&self, other: &MarkerSet) -> bool {
+        self.subset_of(other) && other.subset_of(self)
+    }
+}
+
+impl Eq for MarkerSet {}
+
+impl fmt::Display for MarkerSet {
+    /// Formats the set as a marker. The full set is `*` and the empty set `∅`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_marker() {
+            None => write!(f, "*"),
+            Some(_) if self.is_empty() => write!(f, "∅"),
+            Some(marker) => write!(f, "{}", marker),
+        }
+    }
+}
+
+/// The operator which gives the same result with the operands swapped.
+fn flip(operator: MarkerOperator) -> Option<MarkerOperator> {
+    use MarkerOperator::*;
+    match operator {
+        Equal | NotEqual | ArbitraryEqual => Some(operator),
+        LessThan => Some(GreaterThan),
+        LessThanEqual => Some(GreaterThanEqual),
+        GreaterThan => Some(LessThan),
+        GreaterThanEqual => Some(LessThanEqual),
+        Compatible | In | NotIn => None,
+    }
+}
+
+/// The operator which gives the opposite result.
+fn negate(operator: MarkerOperator) -> Option<MarkerOperator> {
+    use MarkerOperator::*;
+    match operator {
+        Equal => Some(NotEqual),
+        NotEqual => Some(Equal),
+        LessThan => Some(GreaterThanEqual),
+        GreaterThanEqual => Some(LessThan),
+        GreaterThan => Some(LessThanEqual),
+        LessThanEqual => Some(GreaterThan),
+        In => Some(NotIn),
+        NotIn => Some(In),
+        Compatible | ArbitraryEqual => None,
+    }
+}
+
+/// Writes a comparison and its negation the same way, e.g. both `a == b` and
+/// `a != b` as `a == b`, together with whether the comparison holds when the
+/// canonical one does. Returns `None` if the comparison can't be negated.
+fn canonical(expression: &MarkerExpression) -> Option<(MarkerExpression, bool)> {
+    use MarkerOperator::*;
+    let negated = negate(expression.operator)?;
+    match expression.operator {
+        Equal | LessThan | GreaterThan | In => Some((expression.clone(), true)),
+        _ => Some((MarkerExpression { operator: negated, ..expression.clone() }, false)),
+    }
+}
+
+/// The interpreters whose `python_full_version` (or the `major.minor` part
+/// of it, for `python_version`) compares to `value` with `operator`, or
+/// `None` if the value is not a plain release.
+fn python_ranges(variable: MarkerVariable, operator: MarkerOperator, value: &str) -> Option<Ranges> {
+    use MarkerOperator::*;
+    let (prefix, wildcard) = match value.trim().strip_suffix(".*") {
+        Some(prefix) => (prefix, true),
+        None => (value, false),
+    };
+    let version = Version::parse(prefix)?;
+    if version.epoch != 0 || version != version.base() || !version.local.is_empty() {
+        return None;
+    }
+    let minor = variable == MarkerVariable::PythonVersion;
+    if minor && version.release.len() > 2 || wildcard && !matches!(operator, Equal | NotEqual) {
+        return None;
+    }
+    // The first release after every release starting with `release`.
+    let next = |release: &[u32]| -> Option<Version> {
+        let mut release = release.to_vec();
+        *release.last_mut()? += 1;
+        Some(Version::from_release(release))
+    };
+    let next_minor = || next(&version.truncate_release(2).release);
+
+    let ranges = match operator {
+        Equal if wildcard => Ranges::between(version.clone(), next(&version.release)?),
+        Equal | ArbitraryEqual if minor => Ranges::between(version.clone(), next_minor()?),
+        Equal | ArbitraryEqual => Ranges::singleton(version),
+        NotEqual => python_ranges(variable, Equal, value)?.complement(),
+        LessThan => Ranges::strictly_lower_than(version),
+        LessThanEqual if minor => Ranges::strictly_lower_than(next_minor()?),
+        LessThanEqual => Ranges::from_segment(Unbounded, Included(version)),
+        GreaterThan if minor => Ranges::higher_than(next_minor()?),
+        GreaterThan => Ranges::from_segment(Excluded(version), Unbounded),
+        GreaterThanEqual => Ranges::higher_than(version),
+        Compatible if version.release.len() >= 2 => {
+            let upper = next(&version.release[..version.release.len() - 1])?;
+            Ranges::between(version, upper)
+        }
+        Compatible | In | NotIn => return None,
+    };
+    Some(ranges)
+}
+
+fn intersect(left: &Conjunction, right: &Conjunction) -> Option<Conjunction> {
+    let mut conjunction = left.clone();
+    for (dimension, constraint) in right {
+        let constraint = match conjunction.get(dimension) {
+            Some(existing) => existing.intersection(constraint)?,
+            None => constraint.clone(),
+        };
+        conjunction.insert(dimension.clone(), constraint);
+    }
+    Some(conjunction)
+}
+
+/// Returns `true` if every environment of `left` is in `right`.
+fn is_subset(left: &Conjunction, right: &Conjunction) -> bool {
+    right.iter().all(|(dimension, constraint)| {
+        matches!(left.get(dimension), Some(existing) if existing.subset_of(constraint))
+    })
+}
+
+/// Widens `left` in one dimension if `right` covers the difference: if every
+/// other constraint of `right` is implied by `left`, then `left` may allow
+/// the values of `right` in that dimension as well, without changing the
+/// union of both. E.g. `a and b or not a` is `b or not a`.
+fn widen(left: &Conjunction, right: &Conjunction) -> Option<Conjunction> {
+    for (dimension, constraint) in right {
+        let existing = match left.get(dimension) {
+            Some(existing) if !constraint.subset_of(existing) => existing,
+            _ => continue,
+        };
+        let implied = right.iter().filter(|(other, _)| *other != dimension).all(|(other, constraint)| {
+            matches!(left.get(other), Some(existing) if existing.subset_of(constraint))
+        });
+        if implied {
+            let mut widened = left.clone();
+            match existing.union(constraint) {
+                Some(union) => widened.insert(dimension.clone(), union),
+                None => widened.remove(dimension),
+            };
+            return Some(widened);
+        }
+    }
+    None
+}
+
+fn simplify(mut conjunctions: Vec<Conjunction>) -> Vec<Conjunction> {
+    'merging: loop {
+        for i in 0..conjunctions.len() {
+            for j in i + 1..conjunctions.len() {
+                if is_subset(&conjunctions[j], &conjunctions[i]) {
+                    conjunctions.remove(j);
+                    continue 'merging;
+                }
+                if is_subset(&conjunctions[i], &conjunctions[j]) {
+                    conjunctions.remove(i);
+                    continue 'merging;
+                }
+                if let Some(widened) = widen(&conjunctions[i], &conjunctions[j]) {
+                    conjunctions[i] = widened;
+                    continue 'merging;
+                }
+                if let Some(widened) = widen(&conjunctions[j], &conjunctions[i]) {
+                    conjunctions[j] = widened;
+                    continue 'merging;
+                }
+            }
+        }
+        return conjunctions;
+    }
+}
+
+fn expression(variable: MarkerVariable, operator: MarkerOperator, value: String) -> MarkerTree {
+    MarkerTree::Expression(MarkerExpression {
+        lhs: MarkerValue::Variable(variable),
+        operator,
+        rhs: MarkerValue::Literal(value),
+    })
+}
+
+/// Returns `true` for the versions which `python_version` can be compared to
+/// exactly.
+fn is_minor(version: &Version) -> bool {
+    version.release.len() <= 2 && *version == version.base() && version.epoch == 0 && version.local.is_empty()
+}
+
+fn python_markers(ranges: &Ranges) -> Vec<MarkerTree> {
+    use MarkerOperator::*;
+    use MarkerVariable::{PythonFullVersion, PythonVersion};
+    let mut segments: Vec<Vec<MarkerTree>> = ranges
+        .segments()
+        .map(|segment| {
+            let mut markers = vec![];
+            match segment {
+                (Included(lower), Included(upper)) if lower == upper => {
+                    return vec![expression(PythonFullVersion, Equal, lower.to_string())];
+                }
+                (Included(lower), Excluded(upper))
+                    if is_minor(lower) && python_ranges(PythonVersion, Equal, &lower.to_string())
+                        == Some(Ranges::between(lower.clone(), upper.clone())) =>
+                {
+                    return vec![expression(PythonVersion, Equal, lower.to_string())];
+                }
+                _ => {}
+            }
+            match segment.0 {
+                Unbounded => {}
+                Included(lower) if is_minor(lower) => markers.push(expression(PythonVersion, GreaterThanEqual, lower.to_string())),
+                Included(lower) => markers.push(expression(PythonFullVersion, GreaterThanEqual, lower.to_string())),
+                Excluded(lower) => markers.push(expression(PythonFullVersion, GreaterThan, lower.to_string())),
+            }
+            match segment.1 {
+                Unbounded => {}
+                Excluded(upper) if is_minor(upper) => markers.push(expression(PythonVersion, LessThan, upper.to_string())),
+                Excluded(upper) => markers.push(expression(PythonFullVersion, LessThan, upper.to_string())),
+                Included(upper) => markers.push(expression(PythonFullVersion, LessThanEqual, upper.to_string())),
+            }
+            markers
+        })
+        .collect();
+    if segments.len() == 1 {
+        return segments.pop().unwrap();
+    }
+    let segments = segments
+        .into_iter()
+        .map(|mut markers| if markers.len() == 1 { markers.pop().unwrap() } else { MarkerTree::And(markers) })
+        .collect();
+    vec![MarkerTree::Or(segments)]
+}
+
+fn conjunction_marker(conjunction: &Conjunction) -> Option<MarkerTree> {
+    let mut trees = vec![];
+    for (dimension, constraint) in conjunction {
+        match (dimension, constraint) {
+            (Dimension::Python, Constraint::Python(ranges)) => trees.extend(python_markers(ranges)),
+            (Dimension::Variable(variable), Constraint::OneOf(values)) => {
+                let mut values: Vec<MarkerTree> = values
+                    .iter()
+                    .map(|value| expression(*variable, MarkerOperator::Equal, value.clone()))
+                    .collect();
+                trees.push(if values.len() == 1 { values.pop().unwrap() } else { MarkerTree::Or(values) });
+            }
+            (Dimension::Variable(variable), Constraint::NoneOf(values)) => trees.extend(
+                values.iter().map(|value| expression(*variable, MarkerOperator::NotEqual, value.clone()))),
+            (Dimension::Condition(condition), Constraint::Holds(true)) => {
+                trees.push(MarkerTree::Expression(condition.clone()));
+            }
+            (Dimension::Condition(condition), Constraint::Holds(false)) => {
+                let operator = negate(condition.operator).expect("conditions can be negated");
+                trees.push(MarkerTree::Expression(MarkerExpression { operator, ..condition.clone() }));
+            }
+            _ => unreachable!("constraint of the wrong kind for {:?}", dimension),
+        }
+    }
+    match trees.len() {
+        0 => None,
+        1 => trees.pop(),
+        _ => Some(MarkerTree::And(trees)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marker_set::*;
+
+    fn set(marker: &str) -> MarkerSet {
+        MarkerSet::from_marker(&MarkerTree::parse(marker).unwrap(), &[])
+    }
+
+    #[test]
+    fn test_python_version() {
+        let cases = &[
+            ("python_version >= '3.9'", "python_version >= \"3.9\""),
+            ("python_version > '3.9'", "python_version >= \"3.10\""),
+            ("python_version <= '3.9'", "python_version < \"3.10\""),
+            ("'3.9' < python_version", "python_version >= \"3.10\""),
+            ("python_version == '3.9'", "python_version == \"3.9\""),
+            ("python_full_version >= '3.9' and python_full_version < '3.10'", "python_version == \"3.9\""),
+            ("python_version != '3.9'", "python_version < \"3.9\" or python_version >= \"3.10\""),
+            ("python_version == '3.*'", "python_version >= \"3\" and python_version < \"4\""),
+            ("python_version ~= '3.8'", "python_version >= \"3.8\" and python_version < \"4\""),
+            ("python_full_version > '3.8.1'", "python_full_version > \"3.8.1\""),
+            ("python_full_version == '3.8.1'", "python_full_version == \"3.8.1\""),
+            ("python_full_version >= '3.13.0rc1'", "python_full_version >= \"3.13.0rc1\""),
+            ("python_version < '3.8' and python_version >= '3.10'", "∅"),
+            ("python_version < '3.10' or python_version >= '3.9'", "*"),
+        ];
+        for (marker, expected) in cases {
+            assert_eq!(set(marker).to_string(), *expected, "marker={}", marker);
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let linux = set("sys_platform == 'linux'");
+        let not_windows = set("sys_platform != 'win32'");
+        assert!(linux.subset_of(&not_windows));
+        assert!(!not_windows.subset_of(&linux));
+        assert_eq!(
+            linux.union(&set("sys_platform == 'darwin'")).to_string(),
+            "sys_platform == \"darwin\" or sys_platform == \"linux\"");
+        assert_eq!(not_windows.complement().to_string(), "sys_platform == \"win32\"");
+
+        let marker = set("python_version < '3.10' and sys_platform == 'win32'");
+        assert_eq!(
+            marker.complement().to_string(),
+            "python_version >= \"3.10\" or sys_platform != \"win32\"");
+        assert!(marker.union(&marker.complement()).is_full());
+        assert!(marker.is_disjoint(&marker.complement()));
+
+        // Conditions which can't be reasoned about are only known to be
+        // disjoint with their negation.
+        let arm = set("'arm' in platform_machine");
+        assert_eq!(arm.complement().to_string(), "\"arm\" not in platform_machine");
+        assert!(arm.is_disjoint(&set("'arm' not in platform_machine")));
+        assert!(!arm.is_disjoint(&set("platform_machine == 'x86_64'")));
+        assert!(set("platform_machine ~= '1.0'").is_full());
+    }
+
+    #[test]
+    fn test_extras() {
+        let marker = MarkerTree::parse("extra == 'test' and os_name == 'nt'").unwrap();
+        assert!(MarkerSet::from_marker(&marker, &[]).is_empty());
+        let extras = [PackageName::parse("Test").unwrap()];
+        assert_eq!(MarkerSet::from_marker(&marker, &extras).to_string(), "os_name == \"nt\"");
+    }
+}
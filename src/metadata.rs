@@ -0,0 +1,308 @@
+//! Parsing of core metadata, the `METADATA` file of a wheel or installed
+//! distribution and the `PKG-INFO` file of an sdist, as described in the
+//! "Core metadata specifications".
+//!
+//! The format is that of email headers: `Key: value` lines, where lines
+//! starting with whitespace continue the previous value, optionally followed
+//! by a blank line and the description. The `WHEEL` file of a wheel has the
+//! same format.
+
+use crate::error::Error;
+use crate::name::PackageName;
+use crate::requirement::Requirement;
+use crate::requires_python::RequiresPython;
+use crate::Version;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Parsed core metadata.
+pub struct Metadata {
+    pub metadata_version: Version,
+    pub name: PackageName,
+    /// The name exactly as written in the `Name` field.
+    pub raw_name: String,
+    pub version: Version,
+    pub summary: Option<String>,
+    pub requires_python: Option<RequiresPython>,
+    pub requires_dist: Vec<Requirement>,
+    pub provides_extra: Vec<PackageName>,
+    /// The fields which the sdist's build backend may fill in differently
+    /// (metadata 2.2 and later).
+    pub dynamic: Vec<String>,
+    /// Every field in order of appearance, including those modelled above.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Parse and validate the given `METADATA` or `PKG-INFO` contents.
+    ///
+    /// ```
+    /// # use pep440::metadata::Metadata;
+    /// let metadata = Metadata::parse("\
+    /// Metadata-Version: 2.1
+    /// Name: Typing_Extensions
+    /// Version: 4.12.2
+    /// Summary: Backported and Experimental Type Hints
+    /// Requires-Python: >=3.8
+    /// Requires-Dist: pytest; extra == 'test'
+    /// Provides-Extra: test
+    ///
+    /// The description.
+    /// ").unwrap();
+    /// assert_eq!(metadata.name.as_str(), "typing-extensions");
+    /// assert_eq!(metadata.version.to_string(), "4.12.2");
+    /// assert_eq!(metadata.requires_dist[0].to_string(), "pytest; extra == \"test\"");
+    /// assert_eq!(metadata.get("summary"), Some("Backported and Experimental Type Hints"));
+    ///
+    /// let err = Metadata::parse("Metadata-Version: 2.1\nName: x\n").unwrap_err();
+    /// assert_eq!(err.to_string(), "Invalid metadata: missing `Version`");
+    /// ```
+    pub fn parse(input: &str) -> Result<Metadata, Error> {
+        let fields = parse_fields(input)?;
+
+        let single = |key: &'static str| -> Result<Option<&str>, Error> {
+            let mut values = values(&fields, key);
+            let value = values.next();
+            if values.next().is_some() {
+                return Err(error(&format!("more than one `{}`", key)));
+            }
+            Ok(value)
+        };
+        let required = |key: &'static str| single(key)?.ok_or_else(|| error(&format!("missing `{}`", key)));
+
+        let metadata_version = required("Metadata-Version")?;
+        let metadata_version = Version::parse(metadata_version)
+            .ok_or_else(|| error(&format!("invalid `Metadata-Version` `{}`", metadata_version)))?;
+        let raw_name = required("Name")?;
+        let name = PackageName::parse(raw_name).ok_or_else(|| error(&format!("invalid `Name` `{}`", raw_name)))?;
+        let version = required("Version")?;
+        let version = Version::parse(version).ok_or_else(|| error(&format!("invalid `Version` `{}`", version)))?;
+        let summary = single("Summary")?.map(str::to_string);
+        let requires_python = match single("Requires-Python")? {
+            Some(specifiers) => Some(
+                RequiresPython::parse(specifiers)
+                    .ok_or_else(|| error(&format!("invalid `Requires-Python` `{}`", specifiers)))?),
+            None => None,
+        };
+        let requires_dist = values(&fields, "Requires-Dist")
+            .map(|requirement| {
+                Requirement::parse(requirement).map_err(|err| error(&format!("`Requires-Dist`: {}", err)))
+            })
+            .collect::<Result<Vec<Requirement>, Error>>()?;
+        let provides_extra = values(&fields, "Provides-Extra")
+            .map(|extra| PackageName::parse(extra).ok_or_else(|| error(&format!("invalid `Provides-Extra` `{}`", extra))))
+            .collect::<Result<Vec<PackageName>, Error>>()?;
+        let dynamic = values(&fields, "Dynamic").map(str::to_string).collect();
+
+        Ok(Metadata {
+            metadata_version,
+            name,
+            raw_name: raw_name.to_string(),
+            version,
+            summary,
+            requires_python,
+            requires_dist,
+            provides_extra,
+            dynamic,
+            fields,
+        })
+    }
+
+    /// Returns the first value of a field; keys are case-insensitive.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        values(&self.fields, key).next()
+    }
+
+    /// Returns every value of a multiple-use field, e.g. `Classifier`.
+    pub fn get_all<'a>(&'a self, key: &str) -> impl Iterator<Item = &'a str> + 'a {
+        values(&self.fields, key)
+    }
+}
+
+impl FromStr for Metadata {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metadata::parse(s)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The parsed `WHEEL` file of a wheel's `.dist-info` directory, as described
+/// in the "Binary distribution format" specification.
+pub struct WheelMetadata {
+    pub wheel_version: Version,
+    pub generator: Option<String>,
+    /// `true` if the root of the wheel goes into purelib rather than platlib.
+    pub root_is_purelib: bool,
+    /// The expanded tags, e.g. `py3-none-any`.
+    pub tags: Vec<String>,
+    pub build: Option<String>,
+}
+
+impl WheelMetadata {
+    /// The newest version of the wheel format this crate understands.
+    pub const SUPPORTED_VERSION: &'static str = "1.0";
+
+    /// Parse and validate the given `WHEEL` contents.
+    ///
+    /// ```
+    /// # use pep440::metadata::WheelMetadata;
+    /// let wheel = WheelMetadata::parse("\
+    /// Wheel-Version: 1.0
+    /// Generator: bdist_wheel (0.43.0)
+    /// Root-Is-Purelib: true
+    /// Tag: py3-none-any
+    /// ").unwrap();
+    /// assert_eq!(wheel.wheel_version.to_string(), "1.0");
+    /// assert!(wheel.root_is_purelib);
+    /// assert!(wheel.is_supported());
+    /// ```
+    pub fn parse(input: &str) -> Result<WheelMetadata, Error> {
+        let error = |reason: String| Error::metadata_error(format!("WHEEL: {}", reason));
+        let fields = parse_fields(input)?;
+        let single = |key: &'static str| -> Result<Option<&str>, Error> {
+            let mut values = values(&fields, key);
+            let value = values.next();
+            if values.next().is_some() {
+                return Err(error(format!("more than one `{}`", key)));
+            }
+            Ok(value)
+        };
+        let wheel_version = single("Wheel-Version")?.ok_or_else(|| error("missing `Wheel-Version`".to_string()))?;
+        let wheel_version = Version::parse(wheel_version)
+            .ok_or_else(|| error(format!("invalid `Wheel-Version` `{}`", wheel_version)))?;
+        let root_is_purelib = match single("Root-Is-Purelib")? {
+            Some(value) if value.eq_ignore_ascii_case("true") => true,
+            Some(value) if value.eq_ignore_ascii_case("false") => false,
+            Some(value) => return Err(error(format!("invalid `Root-Is-Purelib` `{}`", value))),
+            None => return Err(error("missing `Root-Is-Purelib`".to_string())),
+        };
+        Ok(WheelMetadata {
+            wheel_version,
+            generator: single("Generator")?.map(str::to_string),
+            root_is_purelib,
+            tags: values(&fields, "Tag").map(str::to_string).collect(),
+            build: single("Build")?.map(str::to_string),
+        })
+    }
+
+    /// Returns `true` if the major version of the format is supported.
+    /// Installers must refuse other major versions, and should warn about
+    /// newer minor versions.
+    pub fn is_supported(&self) -> bool {
+        self.wheel_version.release.first() == Some(&1)
+    }
+}
+
+/// Splits email-style headers into fields, up to the first blank line.
+fn parse_fields(input: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut fields: Vec<(String, String)> = vec![];
+    for line in input.lines() {
+        if line.trim().is_empty() {
+            // The rest is the description.
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            match fields.last_mut() {
+                Some((_, value)) => {
+                    value.push('\n');
+                    value.push_str(line.trim());
+                }
+                None => return Err(error(&format!("unexpected continuation line `{}`", line))),
+            }
+            continue;
+        }
+        match line.split_once(':') {
+            Some((key, value)) if !key.is_empty() && !key.contains(' ') => {
+                fields.push((key.to_string(), value.trim().to_string()));
+            }
+            _ => return Err(error(&format!("expected `Key: value`, found `{}`", line))),
+        }
+    }
+    Ok(fields)
+}
+
+fn values<'a>(fields: &'a [(String, String)], key: &str) -> impl Iterator<Item = &'a str> + 'a {
+    let key = key.to_string();
+    fields
+        .iter()
+        .filter(move |(field, _)| field.eq_ignore_ascii_case(&key))
+        .map(|(_, value)| value.as_str())
+}
+
+fn error(reason: &str) -> Error {
+    Error::metadata_error(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::*;
+
+    #[test]
+    fn test_parse() {
+        let metadata = Metadata::parse(
+            "Metadata-Version: 2.2\n\
+             Name: spam.eggs\n\
+             version: 1.0.POST1\n\
+             License: Line one\n        line two\n\
+             Classifier: A\n\
+             Classifier: B\n\
+             Requires-Dist: attrs>=22\n\
+             Requires-Dist: tomli; python_version < \"3.11\"\n\
+             Dynamic: Requires-Dist\n",
+        )
+        .unwrap();
+        assert_eq!(metadata.metadata_version, Version::parse("2.2").unwrap());
+        assert_eq!(metadata.name.as_str(), "spam-eggs");
+        assert_eq!(metadata.raw_name, "spam.eggs");
+        assert_eq!(metadata.version.to_string(), "1.0.post1");
+        assert_eq!(metadata.get("LICENSE"), Some("Line one\nline two"));
+        assert_eq!(metadata.get_all("classifier").collect::<Vec<_>>(), vec!["A", "B"]);
+        assert_eq!(metadata.requires_dist.len(), 2);
+        assert_eq!(metadata.requires_python, None);
+        assert_eq!(metadata.dynamic, vec!["Requires-Dist"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let header = "Metadata-Version: 2.1\nName: spam\nVersion: 1.0\n";
+        let cases: &[(String, &str)] = &[
+            ("  continued\n".to_string(), "unexpected continuation line `  continued`"),
+            ("Name spam\n".to_string(), "expected `Key: value`, found `Name spam`"),
+            ("Name: spam\nVersion: 1\n".to_string(), "missing `Metadata-Version`"),
+            ("Metadata-Version: 2.1\nName: spam\nName: eggs\nVersion: 1\n".to_string(), "more than one `Name`"),
+            ("Metadata-Version: 2.1\nName: -spam\nVersion: 1\n".to_string(), "invalid `Name` `-spam`"),
+            ("Metadata-Version: 2.1\nName: spam\nVersion: one\n".to_string(), "invalid `Version` `one`"),
+            (format!("{}Requires-Python: 3\n", header), "invalid `Requires-Python` `3`"),
+            (format!("{}Provides-Extra: -x\n", header), "invalid `Provides-Extra` `-x`"),
+        ];
+        for (input, reason) in cases {
+            let err = Metadata::parse(input).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid metadata: {}", reason), "input={:?}", input);
+        }
+        let err = Metadata::parse(&format!("{}Requires-Dist: a>\n", header)).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid metadata: `Requires-Dist`: "), "{}", err);
+    }
+
+    #[test]
+    fn test_wheel_metadata() {
+        let wheel = WheelMetadata::parse(
+            "Wheel-Version: 2.0\nRoot-Is-Purelib: False\nTag: cp312-cp312-linux_x86_64\nTag: cp312-abi3-linux_x86_64\nBuild: 1\n",
+        )
+        .unwrap();
+        assert!(!wheel.is_supported());
+        assert!(!wheel.root_is_purelib);
+        assert_eq!(wheel.tags.len(), 2);
+        assert_eq!(wheel.build.as_deref(), Some("1"));
+
+        for (input, reason) in &[
+            ("Root-Is-Purelib: true\n", "missing `Wheel-Version`"),
+            ("Wheel-Version: one\nRoot-Is-Purelib: true\n", "invalid `Wheel-Version` `one`"),
+            ("Wheel-Version: 1.0\n", "missing `Root-Is-Purelib`"),
+            ("Wheel-Version: 1.0\nRoot-Is-Purelib: yes\n", "invalid `Root-Is-Purelib` `yes`"),
+        ] {
+            let err = WheelMetadata::parse(input).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid metadata: WHEEL: {}", reason));
+        }
+    }
+}
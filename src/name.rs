@@ -0,0 +1,106 @@
+//! Project (distribution) names, normalized as described in PEP503.
+
+use crate::error::Error;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    /// The name grammar from PEP508, which is also used for extra names.
+    static ref NAME_RE: Regex = Regex::new(
+        r"(?i)^([A-Z0-9]|[A-Z0-9][A-Z0-9._-]*[A-Z0-9])$").unwrap();
+
+    static ref SEPARATORS_RE: Regex = Regex::new(r"[-_.]+").unwrap();
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A validated and normalized project or extra name.
+///
+/// Names are compared in their normalized form, that is lowercased and with
+/// each run of `-`, `_` and `.` replaced by a single `-`. So `Foo.Bar`,
+/// `foo__bar` and `foo-bar` are all the same name.
+pub struct PackageName(String);
+
+impl PackageName {
+    /// Attempt to parse and normalize the given name.
+    ///
+    /// ```
+    /// # use pep440::name::PackageName;
+    /// let name = PackageName::parse("Typing_Extensions").unwrap();
+    /// assert_eq!(name.as_str(), "typing-extensions");
+    /// assert!(PackageName::parse("-foo").is_none());
+    /// ```
+    pub fn parse(input: &str) -> Option<PackageName> {
+        if !NAME_RE.is_match(input) {
+            return None;
+        }
+        Some(PackageName(normalize(input)))
+    }
+
+    /// Returns the normalized name.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the name escaped as required for wheel and sdist filenames and
+    /// `.dist-info` directories, which is the normalized name with `-`
+    /// replaced by `_`.
+    ///
+    /// ```
+    /// # use pep440::name::PackageName;
+    /// let name = PackageName::parse("zope.interface").unwrap();
+    /// assert_eq!(name.as_dist_info_name(), "zope_interface");
+    /// ```
+    pub fn as_dist_info_name(&self) -> String {
+        self.0.replace('-', "_")
+    }
+}
+
+/// Normalizes a name without validating it.
+fn normalize(name: &str) -> String {
+    SEPARATORS_RE.replace_all(name, "-").to_lowercase()
+}
+
+impl fmt::Display for PackageName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl AsRef<str> for PackageName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for PackageName {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PackageName::parse(s).ok_or_else(|| Error::name_parse_error(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::name::*;
+
+    #[test]
+    fn test_normalize() {
+        let cases: &[(&str, &str)] = &[
+            ("friendly-bard", "friendly-bard"), ("Friendly-Bard", "friendly-bard"),
+            ("FRIENDLY-BARD", "friendly-bard"), ("friendly.bard", "friendly-bard"),
+            ("friendly_bard", "friendly-bard"), ("friendly--bard", "friendly-bard"),
+            ("FrIeNdLy-._.-bArD", "friendly-bard"), ("a", "a"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(PackageName::parse(input).unwrap().as_str(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for input in &["", "-a", "a-", "a b", "a+b", "ä"] {
+            assert!(PackageName::parse(input).is_none(), "Parsed name: '{}'", input);
+        }
+    }
+}
@@ -0,0 +1,761 @@
+//! Reading and writing of `pylock.toml` lock files, as described in PEP751
+//! (now the "pylock.toml" specification).
+//!
+//! This module requires the `pylock` feature. Errors are reported as
+//! `Error::PyLockError`, with the dotted path to the offending key. Keys
+//! which are not modelled here, such as `[tool]` tables and attestation
+//! identities, are ignored and not written back.
+
+use crate::error::Error;
+use crate::filename::{SdistFilename, WheelFilename};
+use crate::marker::MarkerTree;
+use crate::name::PackageName;
+use crate::requires_python::RequiresPython;
+use crate::timestamp::Timestamp;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A parsed `pylock.toml`.
+pub struct PyLock {
+    /// The version of the file format. Only major version 1 is supported.
+    pub lock_version: Version,
+    /// The environments the lock file supports, or `None` for any.
+    pub environments: Option<Vec<MarkerTree>>,
+    pub requires_python: Option<RequiresPython>,
+    /// The extras the lock file supports.
+    pub extras: Vec<PackageName>,
+    /// The dependency groups the lock file supports.
+    pub dependency_groups: Vec<PackageName>,
+    /// The dependency groups installed when none are requested.
+    pub default_groups: Vec<PackageName>,
+    /// The name of the tool which wrote the lock file.
+    pub created_by: String,
+    pub packages: Vec<Package>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An entry of `[[packages]]`. Exactly one of `vcs`, `directory`, `archive`
+/// or `sdist`/`wheels` says where the package comes from.
+pub struct Package {
+    pub name: PackageName,
+    /// `None` for packages from a source tree, whose version isn't known
+    /// before building.
+    pub version: Option<Version>,
+    /// The environments the package is installed in, or `None` for every
+    /// environment.
+    pub marker: Option<MarkerTree>,
+    pub requires_python: Option<RequiresPython>,
+    pub dependencies: Vec<Dependency>,
+    /// The base URL of the index the package was locked from.
+    pub index: Option<String>,
+    pub vcs: Option<Vcs>,
+    pub directory: Option<Directory>,
+    pub archive: Option<Archive>,
+    pub sdist: Option<Artifact>,
+    pub wheels: Vec<Artifact>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A reference to another entry of `[[packages]]`, by as much of its name
+/// and version as is needed to tell it apart from the other entries.
+pub struct Dependency {
+    pub name: PackageName,
+    pub version: Option<Version>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A package built from a version control repository.
+pub struct Vcs {
+    /// The kind of repository, e.g. `git`.
+    pub kind: String,
+    pub url: Option<String>,
+    pub path: Option<String>,
+    /// The revision the user asked for, e.g. a branch name.
+    pub requested_revision: Option<String>,
+    /// The exact commit the revision resolved to.
+    pub commit_id: String,
+    pub subdirectory: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A package built from a local directory.
+pub struct Directory {
+    pub path: String,
+    pub editable: bool,
+    pub subdirectory: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A package built from a source archive which is not an sdist.
+pub struct Archive {
+    pub url: Option<String>,
+    pub path: Option<String>,
+    pub size: Option<u64>,
+    pub upload_time: Option<Timestamp>,
+    /// Keyed by algorithm, e.g. `sha256`.
+    pub hashes: BTreeMap<String, String>,
+    pub subdirectory: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An sdist or a wheel.
+pub struct Artifact {
+    /// The filename, if it can't be taken from the URL or path.
+    pub name: Option<String>,
+    pub upload_time: Option<Timestamp>,
+    pub url: Option<String>,
+    pub path: Option<String>,
+    pub size: Option<u64>,
+    /// Keyed by algorithm, e.g. `sha256`.
+    pub hashes: BTreeMap<String, String>,
+}
+
+impl Artifact {
+    /// Returns the filename: the `name`, or the last segment of the URL or
+    /// path.
+    ///
+    /// ```
+    /// # use pep440::pylock::Artifact;
+    /// let artifact = Artifact {
+    ///     name: None,
+    ///     upload_time: None,
+    ///     url: Some("https://files.example.com/attrs-25.1.0-py3-none-any.whl".to_string()),
+    ///     path: None,
+    ///     size: None,
+    ///     hashes: Default::default(),
+    /// };
+    /// assert_eq!(artifact.filename(), Some("attrs-25.1.0-py3-none-any.whl"));
+    /// ```
+    pub fn filename(&self) -> Option<&str> {
+        if let Some(name) = &self.name {
+            return Some(name);
+        }
+        let location = self.url.as_deref().or(self.path.as_deref())?;
+        let location = location.split(['?', '#']).next().unwrap_or_default();
+        location.rsplit(['/', '\\']).next().filter(|filename| !filename.is_empty())
+    }
+}
+
+impl PyLock {
+    /// Parse and validate the given `pylock.toml` contents.
+    ///
+    /// ```
+    /// # use pep440::pylock::PyLock;
+    /// let lock = PyLock::parse(r#"
+    /// lock-version = "1.0"
+    /// requires-python = ">=3.9"
+    /// created-by = "mousebender"
+    ///
+    /// [[packages]]
+    /// name = "attrs"
+    /// version = "25.1.0"
+    /// wheels = [{url = "https://files.example.com/attrs-25.1.0-py3-none-any.whl", hashes = {sha256 = "c75a69e2"}}]
+    /// "#).unwrap();
+    /// assert_eq!(lock.packages[0].wheels[0].filename(), Some("attrs-25.1.0-py3-none-any.whl"));
+    ///
+    /// let err = PyLock::parse(&lock.to_string().replace("25.1.0\"", "25.2.0\"")).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Invalid pylock.toml: `packages[0].wheels[0]`: \
+    ///      `attrs-25.1.0-py3-none-any.whl` is not of version 25.2.0");
+    /// ```
+    pub fn parse(input: &str) -> Result<PyLock, Error> {
+        let document: Table = input
+            .parse()
+            .map_err(|err: toml::de::Error| Error::pylock_error(String::new(), err.to_string()))?;
+
+        let lock_version = parsed(&document, "lock-version", "lock-version", Version::parse)?
+            .ok_or_else(|| error("lock-version", "must be given"))?;
+        if lock_version.release.first() != Some(&1) {
+            return Err(error("lock-version", &format!("unsupported version {}", lock_version)));
+        }
+        let environments = match string_array(&document, "environments", "environments")? {
+            None => None,
+            Some(environments) => Some(
+                environments
+                    .iter()
+                    .enumerate()
+                    .map(|(index, marker)| {
+                        MarkerTree::parse(marker)
+                            .map_err(|err| error(&format!("environments[{}]", index), &err.to_string()))
+                    })
+                    .collect::<Result<Vec<MarkerTree>, Error>>()?,
+            ),
+        };
+        let packages = tables(&document, "packages", "packages")?
+            .iter()
+            .enumerate()
+            .map(|(index, package)| Package::from_table(package, &format!("packages[{}]", index)))
+            .collect::<Result<Vec<Package>, Error>>()?;
+
+        let lock = PyLock {
+            lock_version,
+            environments,
+            requires_python: parsed(&document, "requires-python", "requires-python", RequiresPython::parse)?,
+            extras: names(&document, "extras", "extras")?,
+            dependency_groups: names(&document, "dependency-groups", "dependency-groups")?,
+            default_groups: names(&document, "default-groups", "default-groups")?,
+            created_by: string(&document, "created-by", "created-by")?
+                .ok_or_else(|| error("created-by", "must be given"))?
+                .to_string(),
+            packages,
+        };
+        lock.validate()?;
+        Ok(lock)
+    }
+
+    /// Checks the rules which the types can't express: every package comes
+    /// from exactly one kind of source, every file can be located and has a
+    /// hash, and the filenames of sdists and wheels match the name and the
+    /// version of their package.
+    pub fn validate(&self) -> Result<(), Error> {
+        for (index, package) in self.packages.iter().enumerate() {
+            package.validate(&format!("packages[{}]", index))?;
+        }
+        Ok(())
+    }
+}
+
+impl Package {
+    fn from_table(table: &Table, path: &str) -> Result<Package, Error> {
+        let key = |key: &str| format!("{}.{}", path, key);
+        let dependencies = tables(table, "dependencies", &key("dependencies"))?
+            .iter()
+            .enumerate()
+            .map(|(index, dependency)| {
+                let path = format!("{}[{}]", key("dependencies"), index);
+                Ok(Dependency {
+                    name: parsed(dependency, "name", &format!("{}.name", path), PackageName::parse)?
+                        .ok_or_else(|| error(&format!("{}.name", path), "must be given"))?,
+                    version: parsed(dependency, "version", &format!("{}.version", path), Version::parse)?,
+                })
+            })
+            .collect::<Result<Vec<Dependency>, Error>>()?;
+        let vcs = match sub_table(table, "vcs", &key("vcs"))? {
+            None => None,
+            Some(vcs) => {
+                let path = key("vcs");
+                let key = |key: &str| format!("{}.{}", path, key);
+                Some(Vcs {
+                    kind: required_string(vcs, "type", &key("type"))?,
+                    url: optional_string(vcs, "url", &key("url"))?,
+                    path: optional_string(vcs, "path", &key("path"))?,
+                    requested_revision: optional_string(vcs, "requested-revision", &key("requested-revision"))?,
+                    commit_id: required_string(vcs, "commit-id", &key("commit-id"))?,
+                    subdirectory: optional_string(vcs, "subdirectory", &key("subdirectory"))?,
+                })
+            }
+        };
+        let directory = match sub_table(table, "directory", &key("directory"))? {
+            None => None,
+            Some(directory) => {
+                let path = key("directory");
+                let key = |key: &str| format!("{}.{}", path, key);
+                let editable = match directory.get("editable") {
+                    None => false,
+                    Some(Value::Boolean(editable)) => *editable,
+                    Some(_) => return Err(error(&key("editable"), "expected a boolean")),
+                };
+                Some(Directory {
+                    path: required_string(directory, "path", &key("path"))?,
+                    editable,
+                    subdirectory: optional_string(directory, "subdirectory", &key("subdirectory"))?,
+                })
+            }
+        };
+        let archive = match sub_table(table, "archive", &key("archive"))? {
+            None => None,
+            Some(archive) => {
+                let path = key("archive");
+                let key = |key: &str| format!("{}.{}", path, key);
+                Some(Archive {
+                    url: optional_string(archive, "url", &key("url"))?,
+                    path: optional_string(archive, "path", &key("path"))?,
+                    size: size(archive, &key("size"))?,
+                    upload_time: upload_time(archive, &key("upload-time"))?,
+                    hashes: hashes(archive, &key("hashes"))?,
+                    subdirectory: optional_string(archive, "subdirectory", &key("subdirectory"))?,
+                })
+            }
+        };
+        let sdist = match sub_table(table, "sdist", &key("sdist"))? {
+            None => None,
+            Some(sdist) => Some(Artifact::from_table(sdist, &key("sdist"))?),
+        };
+        let wheels = tables(table, "wheels", &key("wheels"))?
+            .iter()
+            .enumerate()
+            .map(|(index, wheel)| Artifact::from_table(wheel, &format!("{}[{}]", key("wheels"), index)))
+            .collect::<Result<Vec<Artifact>, Error>>()?;
+
+        Ok(Package {
+            name: parsed(table, "name", &key("name"), PackageName::parse)?
+                .ok_or_else(|| error(&key("name"), "must be given"))?,
+            version: parsed(table, "version", &key("version"), Version::parse)?,
+            marker: match string(table, "marker", &key("marker"))? {
+                None => None,
+                Some(marker) => Some(MarkerTree::parse(marker).map_err(|err| error(&key("marker"), &err.to_string()))?),
+            },
+            requires_python: parsed(table, "requires-python", &key("requires-python"), RequiresPython::parse)?,
+            dependencies,
+            index: optional_string(table, "index", &key("index"))?,
+            vcs,
+            directory,
+            archive,
+            sdist,
+            wheels,
+        })
+    }
+
+    fn validate(&self, path: &str) -> Result<(), Error> {
+        let sources = [
+            self.vcs.is_some(),
+            self.directory.is_some(),
+            self.archive.is_some(),
+            self.sdist.is_some() || !self.wheels.is_empty(),
+        ];
+        match sources.iter().filter(|source| **source).count() {
+            0 => return Err(error(path, "must have one of `vcs`, `directory`, `archive`, `sdist` or `wheels`")),
+            1 => {}
+            _ => return Err(error(path, "must only have one of `vcs`, `directory`, `archive`, or `sdist` and `wheels`")),
+        }
+        if let Some(vcs) = &self.vcs {
+            if vcs.url.is_none() && vcs.path.is_none() {
+                return Err(error(&format!("{}.vcs", path), "must have a `url` or a `path`"));
+            }
+        }
+        if let Some(archive) = &self.archive {
+            if archive.url.is_none() && archive.path.is_none() {
+                return Err(error(&format!("{}.archive", path), "must have a `url` or a `path`"));
+            }
+        }
+        if let Some(sdist) = &self.sdist {
+            let filename = self.validate_artifact(sdist, &format!("{}.sdist", path))?;
+            let sdist = SdistFilename::parse(filename)
+                .ok_or_else(|| error(&format!("{}.sdist", path), &format!("invalid sdist filename `{}`", filename)))?;
+            self.validate_filename(filename, &sdist.name, &sdist.version, &format!("{}.sdist", path))?;
+        }
+        for (index, wheel) in self.wheels.iter().enumerate() {
+            let path = format!("{}.wheels[{}]", path, index);
+            let filename = self.validate_artifact(wheel, &path)?;
+            let wheel = WheelFilename::parse(filename)
+                .ok_or_else(|| error(&path, &format!("invalid wheel filename `{}`", filename)))?;
+            self.validate_filename(filename, &wheel.name, &wheel.version, &path)?;
+        }
+        Ok(())
+    }
+
+    fn validate_artifact<'a>(&self, artifact: &'a Artifact, path: &str) -> Result<&'a str, Error> {
+        if artifact.url.is_none() && artifact.path.is_none() {
+            return Err(error(path, "must have a `url` or a `path`"));
+        }
+        artifact.filename().ok_or_else(|| error(path, "has no filename"))
+    }
+
+    fn validate_filename(&self, filename: &str, name: &PackageName, version: &Version, path: &str) -> Result<(), Error> {
+        if *name != self.name {
+            return Err(error(path, &format!("`{}` is not a file of {}", filename, self.name)));
+        }
+        match &self.version {
+            Some(locked) if locked != version => {
+                Err(error(path, &format!("`{}` is not of version {}", filename, locked)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Artifact {
+    fn from_table(table: &Table, path: &str) -> Result<Artifact, Error> {
+        let key = |key: &str| format!("{}.{}", path, key);
+        Ok(Artifact {
+            name: optional_string(table, "name", &key("name"))?,
+            upload_time: upload_time(table, &key("upload-time"))?,
+            url: optional_string(table, "url", &key("url"))?,
+            path: optional_string(table, "path", &key("path"))?,
+            size: size(table, &key("size"))?,
+            hashes: hashes(table, &key("hashes"))?,
+        })
+    }
+
+    fn to_inline(&self) -> String {
+        let mut entries = vec![];
+        push(&mut entries, "name", self.name.as_deref().map(quote));
+        push(&mut entries, "upload-time", self.upload_time.map(|upload_time| upload_time.to_string()));
+        push(&mut entries, "url", self.url.as_deref().map(quote));
+        push(&mut entries, "path", self.path.as_deref().map(quote));
+        push(&mut entries, "size", self.size.map(|size| size.to_string()));
+        push(&mut entries, "hashes", Some(inline_hashes(&self.hashes)));
+        inline(&entries)
+    }
+}
+
+impl fmt::Display for PyLock {
+    /// Writes the lock file as TOML, with one table per package and the
+    /// files of a package as inline tables.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lock-version = {}", quote(&self.lock_version.to_string()))?;
+        if let Some(environments) = &self.environments {
+            let environments: Vec<String> = environments.iter().map(|marker| quote(&marker.to_string())).collect();
+            writeln!(f, "environments = [{}]", environments.join(", "))?;
+        }
+        if let Some(requires_python) = &self.requires_python {
+            writeln!(f, "requires-python = {}", quote(&requires_python.to_string()))?;
+        }
+        for (key, names) in &[
+            ("extras", &self.extras),
+            ("dependency-groups", &self.dependency_groups),
+            ("default-groups", &self.default_groups),
+        ] {
+            if !names.is_empty() {
+                let names: Vec<String> = names.iter().map(|name| quote(name.as_str())).collect();
+                writeln!(f, "{} = [{}]", key, names.join(", "))?;
+            }
+        }
+        writeln!(f, "created-by = {}", quote(&self.created_by))?;
+
+        for package in &self.packages {
+            writeln!(f, "\n[[packages]]")?;
+            writeln!(f, "name = {}", quote(package.name.as_str()))?;
+            if let Some(version) = &package.version {
+                writeln!(f, "version = {}", quote(&version.to_string()))?;
+            }
+            if let Some(marker) = &package.marker {
+                writeln!(f, "marker = {}", quote(&marker.to_string()))?;
+            }
+            if let Some(requires_python) = &package.requires_python {
+                writeln!(f, "requires-python = {}", quote(&requires_python.to_string()))?;
+            }
+            if !package.dependencies.is_empty() {
+                let dependencies: Vec<String> = package
+                    .dependencies
+                    .iter()
+                    .map(|dependency| {
+                        let mut entries = vec![];
+                        push(&mut entries, "name", Some(quote(dependency.name.as_str())));
+                        push(&mut entries, "version", dependency.version.as_ref().map(|version| quote(&version.to_string())));
+                        inline(&entries)
+                    })
+                    .collect();
+                writeln!(f, "dependencies = [{}]", dependencies.join(", "))?;
+            }
+            if let Some(index) = &package.index {
+                writeln!(f, "index = {}", quote(index))?;
+            }
+            if let Some(vcs) = &package.vcs {
+                let mut entries = vec![];
+                push(&mut entries, "type", Some(quote(&vcs.kind)));
+                push(&mut entries, "url", vcs.url.as_deref().map(quote));
+                push(&mut entries, "path", vcs.path.as_deref().map(quote));
+                push(&mut entries, "requested-revision", vcs.requested_revision.as_deref().map(quote));
+                push(&mut entries, "commit-id", Some(quote(&vcs.commit_id)));
+                push(&mut entries, "subdirectory", vcs.subdirectory.as_deref().map(quote));
+                writeln!(f, "vcs = {}", inline(&entries))?;
+            }
+            if let Some(directory) = &package.directory {
+                let mut entries = vec![];
+                push(&mut entries, "path", Some(quote(&directory.path)));
+                push(&mut entries, "editable", Some(directory.editable.to_string()));
+                push(&mut entries, "subdirectory", directory.subdirectory.as_deref().map(quote));
+                writeln!(f, "directory = {}", inline(&entries))?;
+            }
+            if let Some(archive) = &package.archive {
+                let mut entries = vec![];
+                push(&mut entries, "url", archive.url.as_deref().map(quote));
+                push(&mut entries, "path", archive.path.as_deref().map(quote));
+                push(&mut entries, "size", archive.size.map(|size| size.to_string()));
+                push(&mut entries, "upload-time", archive.upload_time.map(|upload_time| upload_time.to_string()));
+                push(&mut entries, "hashes", Some(inline_hashes(&archive.hashes)));
+                push(&mut entries, "subdirectory", archive.subdirectory.as_deref().map(quote));
+                writeln!(f, "archive = {}", inline(&entries))?;
+            }
+            if let Some(sdist) = &package.sdist {
+                writeln!(f, "sdist = {}", sdist.to_inline())?;
+            }
+            if !package.wheels.is_empty() {
+                writeln!(f, "wheels = [")?;
+                for wheel in &package.wheels {
+                    writeln!(f, "    {},", wheel.to_inline())?;
+                }
+                writeln!(f, "]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PyLock {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PyLock::parse(s)
+    }
+}
+
+fn error(key: &str, reason: &str) -> Error {
+    Error::pylock_error(key.to_string(), reason.to_string())
+}
+
+/// Returns the string at `key`, if present.
+fn string<'a>(table: &'a Table, key: &str, path: &str) -> Result<Option<&'a str>, Error> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(error(path, "expected a string")),
+    }
+}
+
+fn optional_string(table: &Table, key: &str, path: &str) -> Result<Option<String>, Error> {
+    Ok(string(table, key, path)?.map(str::to_string))
+}
+
+fn required_string(table: &Table, key: &str, path: &str) -> Result<String, Error> {
+    optional_string(table, key, path)?.ok_or_else(|| error(path, "must be given"))
+}
+
+/// Returns the string at `key` converted with `parse`, if present.
+fn parsed<T, F: Fn(&str) -> Option<T>>(table: &Table, key: &str, path: &str, parse: F) -> Result<Option<T>, Error> {
+    match string(table, key, path)? {
+        None => Ok(None),
+        Some(value) => parse(value).map(Some).ok_or_else(|| error(path, &format!("invalid value `{}`", value))),
+    }
+}
+
+/// Returns the array of strings at `key`, if present.
+fn string_array(table: &Table, key: &str, path: &str) -> Result<Option<Vec<String>>, Error> {
+    let array = match table.get(key) {
+        None => return Ok(None),
+        Some(Value::Array(array)) => array,
+        Some(_) => return Err(error(path, "expected an array of strings")),
+    };
+    array
+        .iter()
+        .enumerate()
+        .map(|(index, value)| match value {
+            Value::String(value) => Ok(value.to_string()),
+            _ => Err(error(&format!("{}[{}]", path, index), "expected a string")),
+        })
+        .collect::<Result<Vec<String>, Error>>()
+        .map(Some)
+}
+
+/// Returns the array of names at `key`, or an empty list if the key is not
+/// present.
+fn names(table: &Table, key: &str, path: &str) -> Result<Vec<PackageName>, Error> {
+    string_array(table, key, path)?
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            PackageName::parse(name)
+                .ok_or_else(|| error(&format!("{}[{}]", path, index), &format!("invalid name `{}`", name)))
+        })
+        .collect()
+}
+
+fn sub_table<'a>(table: &'a Table, key: &str, path: &str) -> Result<Option<&'a Table>, Error> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::Table(table)) => Ok(Some(table)),
+        Some(_) => Err(error(path, "expected a table")),
+    }
+}
+
+/// Returns the array of tables at `key`, or an empty list if the key is not
+/// present.
+fn tables<'a>(table: &'a Table, key: &str, path: &str) -> Result<Vec<&'a Table>, Error> {
+    let array = match table.get(key) {
+        None => return Ok(vec![]),
+        Some(Value::Array(array)) => array,
+        Some(_) => return Err(error(path, "expected an array of tables")),
+    };
+    array
+        .iter()
+        .enumerate()
+        .map(|(index, value)| match value {
+            Value::Table(table) => Ok(table),
+            _ => Err(error(&format!("{}[{}]", path, index), "expected a table")),
+        })
+        .collect()
+}
+
+fn size(table: &Table, path: &str) -> Result<Option<u64>, Error> {
+    match table.get("size") {
+        None => Ok(None),
+        Some(Value::Integer(size)) if *size >= 0 => Ok(Some(*size as u64)),
+        Some(_) => Err(error(path, "expected a non-negative integer")),
+    }
+}
+
+fn upload_time(table: &Table, path: &str) -> Result<Option<Timestamp>, Error> {
+    match table.get("upload-time") {
+        None => Ok(None),
+        Some(Value::Datetime(datetime)) => Timestamp::parse(&datetime.to_string())
+            .map(Some)
+            .ok_or_else(|| error(path, "expected a date and time with an offset")),
+        Some(_) => Err(error(path, "expected a datetime")),
+    }
+}
+
+/// Returns the hashes at `key`, of which there has to be at least one.
+fn hashes(table: &Table, path: &str) -> Result<BTreeMap<String, String>, Error> {
+    let hashes = sub_table(table, "hashes", path)?.ok_or_else(|| error(path, "must be given"))?;
+    if hashes.is_empty() {
+        return Err(error(path, "must have at least one hash"));
+    }
+    hashes
+        .keys()
+        .map(|algorithm| {
+            let hash = string(hashes, algorithm, &format!("{}.{}", path, algorithm))?;
+            Ok((algorithm.to_string(), hash.unwrap_or_default().to_string()))
+        })
+        .collect()
+}
+
+/// Writes a string as a TOML string, with the escaping it needs.
+fn quote(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+fn push(entries: &mut Vec<(&'static str, String)>, key: &'static str, value: Option<String>) {
+    if let Some(value) = value {
+        entries.push((key, value));
+    }
+}
+
+fn inline(entries: &[(&str, String)]) -> String {
+    let entries: Vec<String> = entries.iter().map(|(key, value)| format!("{} = {}", key, value)).collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+fn inline_hashes(hashes: &BTreeMap<String, String>) -> String {
+    let entries: Vec<(&str, String)> = hashes.iter().map(|(algorithm, hash)| (algorithm.as_str(), quote(hash))).collect();
+    inline(&entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pylock::*;
+
+    /// The example of the specification, shortened.
+    const EXAMPLE: &str = r#"
+lock-version = '1.0'
+environments = ["sys_platform == 'win32'", "sys_platform == 'linux'"]
+requires-python = '>=3.9'
+extras = []
+created-by = 'mousebender'
+
+[[packages]]
+name = 'attrs'
+version = '25.1.0'
+requires-python = '>=3.8'
+wheels = [
+  {name = 'attrs-25.1.0-py3-none-any.whl', upload-time = 2025-01-25T11:30:10.164985+00:00, url = 'https://files.pythonhosted.org/packages/fc/30/d4986a882011f9df997a55e6becd864812ccfcd821d64aac8570ee39f719/attrs-25.1.0-py3-none-any.whl', size = 63152, hashes = {sha256 = 'c75a69e28a550a7e93789579c22aa26b0f5b83b75dc4e08fe092980051e1090a'}},
+]
+[[packages.attestation-identities]]
+environment = 'release-pypi'
+kind = 'GitHub'
+repository = 'python-attrs/attrs'
+workflow = 'pypi-package.yml'
+
+[[packages]]
+name = 'cattrs'
+version = '24.1.2'
+requires-python = '>=3.8'
+dependencies = [
+    {name = 'attrs'},
+]
+index = 'https://pypi.org/simple'
+sdist = {url = 'https://files.pythonhosted.org/packages/cattrs-24.1.2.tar.gz', hashes = {sha256 = 'abc'}}
+
+[[packages]]
+name = 'numpy'
+marker = "sys_platform == 'linux'"
+vcs = {type = 'git', url = 'https://github.com/numpy/numpy.git', requested-revision = 'main', commit-id = 'deadbeef'}
+
+[[packages]]
+name = 'local'
+directory = {path = './local', editable = true}
+
+[tool.mousebender]
+command = ['.', 'lock', '--platform', 'cpython3.12-windows-x64']
+"#;
+
+    #[test]
+    fn test_parse() {
+        let lock = PyLock::parse(EXAMPLE).unwrap();
+        assert_eq!(lock.lock_version, Version::parse("1.0").unwrap());
+        assert_eq!(lock.environments.as_ref().unwrap()[1].to_string(), "sys_platform == \"linux\"");
+        assert_eq!(lock.packages.len(), 4);
+
+        let attrs = &lock.packages[0];
+        assert_eq!(attrs.version, Version::parse("25.1.0"));
+        let wheel = &attrs.wheels[0];
+        assert_eq!(wheel.size, Some(63152));
+        assert_eq!(wheel.upload_time.unwrap().to_string(), "2025-01-25T11:30:10.164985000Z");
+        assert!(wheel.hashes["sha256"].starts_with("c75a69e2"));
+
+        let cattrs = &lock.packages[1];
+        assert_eq!(cattrs.dependencies, vec![Dependency { name: PackageName::parse("attrs").unwrap(), version: None }]);
+        assert_eq!(cattrs.sdist.as_ref().unwrap().filename(), Some("cattrs-24.1.2.tar.gz"));
+        assert_eq!(lock.packages[2].vcs.as_ref().unwrap().commit_id, "deadbeef");
+        assert!(lock.packages[3].directory.as_ref().unwrap().editable);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lock = PyLock::parse(EXAMPLE).unwrap();
+        let written = lock.to_string();
+        assert_eq!(PyLock::parse(&written).unwrap(), lock);
+        assert!(written.contains("[[packages]]\nname = \"cattrs\"\nversion = \"24.1.2\"\n"), "{}", written);
+        assert!(written.contains("upload-time = 2025-01-25T11:30:10.164985000Z"), "{}", written);
+    }
+
+    fn parse_error(input: &str) -> String {
+        PyLock::parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_errors() {
+        let header = "lock-version = '1.0'\ncreated-by = 'test'\n";
+        let cases = &[
+            ("created-by = 'test'\n", "`lock-version`: must be given"),
+            ("lock-version = '2.0'\ncreated-by = 'test'\n", "`lock-version`: unsupported version 2.0"),
+            ("lock-version = '1.0'\n", "`created-by`: must be given"),
+            ("[[packages]]\nname = 'a'\n", "`packages[0]`: must have one of `vcs`, `directory`, `archive`, `sdist` or `wheels`"),
+            (
+                "[[packages]]\nname = 'a'\ndirectory = {path = '.'}\narchive = {path = 'a.tar.gz', hashes = {sha256 = 'x'}}\n",
+                "`packages[0]`: must only have one of `vcs`, `directory`, `archive`, or `sdist` and `wheels`",
+            ),
+            ("[[packages]]\nname = 'a'\nsdist = {path = 'a-1.0.tar.gz'}\n", "`packages[0].sdist.hashes`: must be given"),
+            ("[[packages]]\nname = 'a'\nsdist = {path = 'a-1.0.tar.gz', hashes = {}}\n", "`packages[0].sdist.hashes`: must have at least one hash"),
+            ("[[packages]]\nname = 'a'\nsdist = {name = 'a-1.0.tar.gz', hashes = {sha256 = 'x'}}\n", "`packages[0].sdist`: must have a `url` or a `path`"),
+            (
+                "[[packages]]\nname = 'a'\nversion = '1.0'\nsdist = {path = 'a-1.1.tar.gz', hashes = {sha256 = 'x'}}\n",
+                "`packages[0].sdist`: `a-1.1.tar.gz` is not of version 1.0",
+            ),
+            (
+                "[[packages]]\nname = 'a'\nwheels = [{path = 'b-1.0-py3-none-any.whl', hashes = {sha256 = 'x'}}]\n",
+                "`packages[0].wheels[0]`: `b-1.0-py3-none-any.whl` is not a file of a",
+            ),
+            ("[[packages]]\nname = 'a'\nwheels = [{path = 'a.whl', hashes = {sha256 = 'x'}}]\n", "`packages[0].wheels[0]`: invalid wheel filename `a.whl`"),
+            (
+                "lock-version = '1.0'\ncreated-by = 'test'\nenvironments = ['os_name ==']\n",
+                "`environments[0]`: Failed to parse marker: os_name == (expected a marker variable or a quoted string)",
+            ),
+            (
+                "[[packages]]\nname = 'a'\nmarker = 'os_name =='\ndirectory = {path = '.'}\n",
+                "`packages[0].marker`: Failed to parse marker: os_name == (expected a marker variable or a quoted string)",
+            ),
+            ("[[packages]]\nname = 'a'\nsdist = {path = 'a-1.0.tar.gz', upload-time = '2025-01-01', hashes = {sha256 = 'x'}}\n", "`packages[0].sdist.upload-time`: expected a datetime"),
+        ];
+        for (input, expected) in cases {
+            let input = if input.starts_with("[[") { format!("{}{}", header, input) } else { input.to_string() };
+            assert_eq!(parse_error(&input), format!("Invalid pylock.toml: {}", expected), "input={}", input);
+        }
+        assert!(parse_error("lock-version = ").starts_with("Invalid pylock.toml: TOML parse error"));
+    }
+}
@@ -0,0 +1,543 @@
+//! Parsing of the `[project]` table of `pyproject.toml`, as described in PEP621
+//! (now the "Declaring project metadata" specification), and of the
+//! `[build-system]` table, as described in PEP517 and PEP518, and of the
+//! `[dependency-groups]` table, as described in PEP735.
+//!
+//! This module requires the `pyproject` feature. Errors are reported as
+//! `Error::PyProjectError`, with the dotted path to the offending key.
+
+use crate::error::Error;
+use crate::name::PackageName;
+use crate::requirement::Requirement;
+use crate::requires_python::RequiresPython;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// The keys which may appear in the `[project]` table, and therefore in
+/// `project.dynamic`.
+const PROJECT_KEYS: &[&str] = &[
+    "name", "version", "description", "readme", "requires-python", "license",
+    "license-files", "authors", "maintainers", "keywords", "classifiers", "urls",
+    "scripts", "gui-scripts", "entry-points", "dependencies",
+    "optional-dependencies", "import-names", "import-namespaces",
+];
+
+/// The build requirement assumed by PEP517 when there is no `[build-system]`.
+const DEFAULT_BUILD_REQUIRES: &str = "setuptools>=40.8.0";
+
+/// The backend assumed by PEP517 when none is declared: setuptools, emulating
+/// a direct `setup.py` invocation.
+pub const LEGACY_BUILD_BACKEND: &str = "setuptools.build_meta:__legacy__";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// A parsed `pyproject.toml`. Tables other than those modelled here are
+/// ignored.
+pub struct PyProject {
+    /// The `[project]` table, if any.
+    pub project: Option<Project>,
+    /// The `[build-system]` table, or the PEP517 default if there is none.
+    pub build_system: BuildSystem,
+    /// The `[dependency-groups]` table, keyed by normalized group name. Use
+    /// `dependency_group` to expand the includes.
+    pub dependency_groups: BTreeMap<PackageName, Vec<DependencyGroupEntry>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An entry of a dependency group.
+pub enum DependencyGroupEntry {
+    Requirement(Requirement),
+    /// `{include-group = "..."}`: all the requirements of another group.
+    IncludeGroup(PackageName),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The `[build-system]` table.
+///
+/// The `Default` is what PEP517 prescribes for projects without a
+/// `[build-system]` table: `setuptools>=40.8.0` with the legacy backend.
+pub struct BuildSystem {
+    pub requires: Vec<Requirement>,
+    /// The declared backend, or `LEGACY_BUILD_BACKEND` if none is declared.
+    pub build_backend: String,
+    /// Directories, relative to the project root, to add to `sys.path` when
+    /// importing the backend (for in-tree backends).
+    pub backend_path: Vec<String>,
+}
+
+impl Default for BuildSystem {
+    /// ```
+    /// # use pep440::pyproject::{BuildSystem, LEGACY_BUILD_BACKEND};
+    /// let build_system = BuildSystem::default();
+    /// assert_eq!(build_system.requires[0].to_string(), "setuptools>=40.8.0");
+    /// assert_eq!(build_system.build_backend, LEGACY_BUILD_BACKEND);
+    /// ```
+    fn default() -> BuildSystem {
+        BuildSystem {
+            requires: vec![Requirement::parse(DEFAULT_BUILD_REQUIRES).unwrap()],
+            build_backend: LEGACY_BUILD_BACKEND.to_string(),
+            backend_path: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The `[project]` table.
+pub struct Project {
+    pub name: PackageName,
+    /// `None` if, and only if, `version` is listed in `dynamic`.
+    pub version: Option<Version>,
+    pub requires_python: Option<RequiresPython>,
+    pub dependencies: Vec<Requirement>,
+    /// Keyed by normalized extra name.
+    pub optional_dependencies: BTreeMap<PackageName, Vec<Requirement>>,
+    /// The keys whose values are provided by the build backend.
+    pub dynamic: Vec<String>,
+}
+
+impl PyProject {
+    /// Parse and validate the given `pyproject.toml` contents.
+    ///
+    /// ```
+    /// # use pep440::pyproject::PyProject;
+    /// let pyproject = PyProject::parse(r#"
+    /// [project]
+    /// name = "Spam_Eggs"
+    /// version = "2020.0.0"
+    /// requires-python = ">=3.8"
+    /// dependencies = ["httpx", "gidgethub[httpx]>4.0.0"]
+    ///
+    /// [project.optional-dependencies]
+    /// cli = ["rich", "click"]
+    /// "#).unwrap();
+    /// let project = pyproject.project.unwrap();
+    /// assert_eq!(project.name.as_str(), "spam-eggs");
+    /// assert_eq!(project.dependencies[1].to_string(), "gidgethub[httpx]>4.0.0");
+    ///
+    /// let err = PyProject::parse("[project]\nname = 'spam'\n").unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Invalid pyproject.toml: `project.version`: \
+    ///      must be given, or listed in `project.dynamic`");
+    /// ```
+    pub fn parse(input: &str) -> Result<PyProject, Error> {
+        let document: Table = input
+            .parse()
+            .map_err(|err: toml::de::Error| Error::pyproject_error(String::new(), err.to_string()))?;
+        let project = match document.get("project") {
+            None => None,
+            Some(Value::Table(project)) => Some(Project::from_table(project)?),
+            Some(_) => return Err(error("project", "expected a table")),
+        };
+        let build_system = match document.get("build-system") {
+            None => BuildSystem::default(),
+            Some(Value::Table(build_system)) => BuildSystem::from_table(build_system)?,
+            Some(_) => return Err(error("build-system", "expected a table")),
+        };
+        let dependency_groups = match document.get("dependency-groups") {
+            None => BTreeMap::new(),
+            Some(Value::Table(groups)) => dependency_groups(groups)?,
+            Some(_) => return Err(error("dependency-groups", "expected a table")),
+        };
+        Ok(PyProject { project, build_system, dependency_groups })
+    }
+
+    /// Returns the requirements of the named dependency group, with included
+    /// groups expanded recursively in place.
+    ///
+    /// ```
+    /// # use pep440::name::PackageName;
+    /// # use pep440::pyproject::PyProject;
+    /// let pyproject = PyProject::parse(r#"
+    /// [dependency-groups]
+    /// test = ["pytest>7", "coverage"]
+    /// docs = ["sphinx"]
+    /// CI = [{include-group = "test"}, {include-group = "docs"}, "tox"]
+    /// "#).unwrap();
+    /// let ci = pyproject.dependency_group(&PackageName::parse("ci").unwrap()).unwrap();
+    /// let ci: Vec<String> = ci.iter().map(|requirement| requirement.to_string()).collect();
+    /// assert_eq!(ci, vec!["pytest>7", "coverage", "sphinx", "tox"]);
+    /// ```
+    pub fn dependency_group(&self, name: &PackageName) -> Result<Vec<Requirement>, Error> {
+        let mut requirements = vec![];
+        self.expand_group(name, &mut vec![], &mut requirements)?;
+        Ok(requirements)
+    }
+
+    /// Appends the requirements of `name` to `requirements`; `including` is
+    /// the chain of groups whose includes led here.
+    fn expand_group(
+        &self,
+        name: &PackageName,
+        including: &mut Vec<PackageName>,
+        requirements: &mut Vec<Requirement>,
+    ) -> Result<(), Error> {
+        let path = format!("dependency-groups.{}", name);
+        let entries = match self.dependency_groups.get(name) {
+            Some(entries) => entries,
+            None => return Err(error(&path, "no such group")),
+        };
+        including.push(name.clone());
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                DependencyGroupEntry::Requirement(requirement) => requirements.push(requirement.clone()),
+                DependencyGroupEntry::IncludeGroup(included) => {
+                    let path = format!("{}[{}]", path, index);
+                    if let Some(start) = including.iter().position(|group| group == included) {
+                        let cycle: Vec<String> = including[start..]
+                            .iter()
+                            .chain(std::iter::once(included))
+                            .map(|group| group.to_string())
+                            .collect();
+                        return Err(error(&path, &format!("cyclic include: {}", cycle.join(" -> "))));
+                    }
+                    if !self.dependency_groups.contains_key(included) {
+                        return Err(error(&path, &format!("unknown group `{}`", included)));
+                    }
+                    self.expand_group(included, including, requirements)?;
+                }
+            }
+        }
+        including.pop();
+        Ok(())
+    }
+}
+
+/// Parses the `[dependency-groups]` table, without expanding includes.
+fn dependency_groups(table: &Table) -> Result<BTreeMap<PackageName, Vec<DependencyGroupEntry>>, Error> {
+    let mut groups = BTreeMap::new();
+    for (group, entries) in table {
+        let path = format!("dependency-groups.{}", group);
+        let name = PackageName::parse(group)
+            .ok_or_else(|| error(&path, &format!("invalid group name `{}`", group)))?;
+        let entries = match entries {
+            Value::Array(entries) => entries,
+            _ => return Err(error(&path, "expected an array")),
+        };
+        let entries = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let path = format!("{}[{}]", path, index);
+                match entry {
+                    Value::String(requirement) => Requirement::parse(requirement)
+                        .map(DependencyGroupEntry::Requirement)
+                        .map_err(|err| error(&path, &err.to_string())),
+                    Value::Table(include) if include.len() == 1 => {
+                        let included = string(include, "include-group", &format!("{}.include-group", path))?
+                            .ok_or_else(|| error(&path, "expected `include-group`"))?;
+                        PackageName::parse(included)
+                            .map(DependencyGroupEntry::IncludeGroup)
+                            .ok_or_else(|| error(&path, &format!("invalid group name `{}`", included)))
+                    }
+                    _ => Err(error(&path, "expected a requirement or an `include-group` table")),
+                }
+            })
+            .collect::<Result<Vec<DependencyGroupEntry>, Error>>()?;
+        if groups.insert(name.clone(), entries).is_some() {
+            return Err(error(&path, &format!("duplicate group `{}`", name)));
+        }
+    }
+    Ok(groups)
+}
+
+impl Project {
+    fn from_table(table: &Table) -> Result<Project, Error> {
+        let dynamic = string_array(table, "dynamic", "project.dynamic")?.unwrap_or_default();
+        for (index, key) in dynamic.iter().enumerate() {
+            let path = format!("project.dynamic[{}]", index);
+            if key == "name" {
+                return Err(error(&path, "`name` cannot be dynamic"));
+            } else if !PROJECT_KEYS.contains(&key.as_str()) {
+                return Err(error(&path, &format!("unknown key `{}`", key)));
+            } else if table.contains_key(key) {
+                return Err(error(
+                    &format!("project.{}", key),
+                    "must not be given, since it is listed in `project.dynamic`"));
+            }
+        }
+
+        let name = string(table, "name", "project.name")?
+            .ok_or_else(|| error("project.name", "missing required key"))?;
+        let name = PackageName::parse(name)
+            .ok_or_else(|| error("project.name", &format!("invalid name `{}`", name)))?;
+
+        let version = match string(table, "version", "project.version")? {
+            Some(version) => Some(
+                Version::parse(version)
+                    .ok_or_else(|| error("project.version", &format!("invalid version `{}`", version)))?),
+            None if dynamic.iter().any(|key| key == "version") => None,
+            None => return Err(error("project.version", "must be given, or listed in `project.dynamic`")),
+        };
+
+        let requires_python = match string(table, "requires-python", "project.requires-python")? {
+            Some(specifiers) => Some(
+                RequiresPython::parse(specifiers)
+                    .ok_or_else(|| error(
+                        "project.requires-python",
+                        &format!("invalid version specifier `{}`", specifiers)))?),
+            None => None,
+        };
+
+        let dependencies = requirements(table, "dependencies", "project.dependencies")?;
+
+        let mut optional_dependencies = BTreeMap::new();
+        match table.get("optional-dependencies") {
+            None => {}
+            Some(Value::Table(extras)) => {
+                for extra in extras.keys() {
+                    let path = format!("project.optional-dependencies.{}", extra);
+                    let name = PackageName::parse(extra)
+                        .ok_or_else(|| error(&path, &format!("invalid extra name `{}`", extra)))?;
+                    let requirements = requirements(extras, extra, &path)?;
+                    if optional_dependencies.insert(name.clone(), requirements).is_some() {
+                        return Err(error(&path, &format!("duplicate extra `{}`", name)));
+                    }
+                }
+            }
+            Some(_) => return Err(error("project.optional-dependencies", "expected a table")),
+        }
+
+        Ok(Project {
+            name,
+            version,
+            requires_python,
+            dependencies,
+            optional_dependencies,
+            dynamic,
+        })
+    }
+}
+
+impl BuildSystem {
+    fn from_table(table: &Table) -> Result<BuildSystem, Error> {
+        // PEP518 makes `requires` mandatory once the table exists.
+        if !table.contains_key("requires") {
+            return Err(error("build-system.requires", "missing required key"));
+        }
+        let requires = requirements(table, "requires", "build-system.requires")?;
+
+        let build_backend = string(table, "build-backend", "build-system.build-backend")?
+            .unwrap_or(LEGACY_BUILD_BACKEND)
+            .to_string();
+
+        let backend_path = string_array(table, "backend-path", "build-system.backend-path")?
+            .unwrap_or_default();
+        for (index, path) in backend_path.iter().enumerate() {
+            if !is_within_project(path) {
+                return Err(error(
+                    &format!("build-system.backend-path[{}]", index),
+                    &format!("`{}` is not a relative path inside the project", path)));
+            }
+        }
+
+        Ok(BuildSystem { requires, build_backend, backend_path })
+    }
+}
+
+/// Returns `true` if the relative path does not escape the project root.
+fn is_within_project(path: &str) -> bool {
+    let mut depth: usize = 0;
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => return false,
+        }
+    }
+    true
+}
+
+impl FromStr for PyProject {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PyProject::parse(s)
+    }
+}
+
+pub(crate) fn error(key: &str, reason: &str) -> Error {
+    Error::pyproject_error(key.to_string(), reason.to_string())
+}
+
+/// Returns the string at `key`, if present.
+pub(crate) fn string<'a>(table: &'a Table, key: &str, path: &str) -> Result<Option<&'a str>, Error> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(error(path, "expected a string")),
+    }
+}
+
+/// Returns the array of strings at `key`, if present.
+pub(crate) fn string_array(table: &Table, key: &str, path: &str) -> Result<Option<Vec<String>>, Error> {
+    let array = match table.get(key) {
+        None => return Ok(None),
+        Some(Value::Array(array)) => array,
+        Some(_) => return Err(error(path, "expected an array of strings")),
+    };
+    array
+        .iter()
+        .enumerate()
+        .map(|(index, value)| match value {
+            Value::String(value) => Ok(value.to_string()),
+            _ => Err(error(&format!("{}[{}]", path, index), "expected a string")),
+        })
+        .collect::<Result<Vec<String>, Error>>()
+        .map(Some)
+}
+
+/// Returns the array of PEP508 requirements at `key`, or an empty list if the
+/// key is not present.
+pub(crate) fn requirements(table: &Table, key: &str, path: &str) -> Result<Vec<Requirement>, Error> {
+    string_array(table, key, path)?
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, requirement)| {
+            Requirement::parse(requirement)
+                .map_err(|err| error(&format!("{}[{}]", path, index), &err.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pyproject::*;
+
+    fn parse_error(input: &str) -> (String, String) {
+        match PyProject::parse(input) {
+            Err(Error::PyProjectError { key, reason }) => (key, reason),
+            other => panic!("Expected a pyproject error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let pyproject = PyProject::parse(r#"
+            [project]
+            name = "spam"
+            dynamic = ["version", "readme"]
+            dependencies = ["attrs; python_version < '3.10'"]
+
+            [project.optional-dependencies]
+            Test_Suite = ["pytest>=7", "coverage[toml]"]
+
+            [tool.black]
+            line-length = 88
+        "#).unwrap();
+        let project = pyproject.project.unwrap();
+        assert_eq!(project.version, None);
+        assert_eq!(project.requires_python, None);
+        assert_eq!(project.dynamic, vec!["version", "readme"]);
+        assert!(project.dependencies[0].marker.is_some());
+        let test = PackageName::parse("test-suite").unwrap();
+        assert_eq!(project.optional_dependencies[&test].len(), 2);
+
+        assert_eq!(PyProject::parse("[tool.black]\n").unwrap(), PyProject::default());
+    }
+
+    #[test]
+    fn test_build_system() {
+        let pyproject = PyProject::parse(r#"
+            [build-system]
+            requires = ["hatchling>=1.18", "hatch-vcs; python_version >= '3.8'"]
+            build-backend = "backend"
+            backend-path = ["_build", "./tools/../_build"]
+        "#).unwrap();
+        let build_system = pyproject.build_system;
+        assert_eq!(build_system.requires.len(), 2);
+        assert_eq!(build_system.requires[0].specifiers().to_string(), ">=1.18");
+        assert_eq!(build_system.build_backend, "backend");
+        assert_eq!(build_system.backend_path, vec!["_build", "./tools/../_build"]);
+
+        // A table without a backend falls back to the legacy backend, but
+        // keeps its own requirements.
+        let pyproject = PyProject::parse("[build-system]\nrequires = []\n").unwrap();
+        assert!(pyproject.build_system.requires.is_empty());
+        assert_eq!(pyproject.build_system.build_backend, LEGACY_BUILD_BACKEND);
+    }
+
+    #[test]
+    fn test_dependency_groups() {
+        let pyproject = PyProject::parse(r#"
+            [dependency-groups]
+            Typing = ["mypy", "types-requests"]
+            test = ["pytest", {include-group = "typing"}]
+            all = [{include-group = "Test"}, {include-group = "typing"}]
+            loop-a = [{include-group = "loop_b"}]
+            loop-b = ["x", {include-group = "Loop.A"}]
+            broken = [{include-group = "missing"}]
+        "#).unwrap();
+        let group = |name: &str| {
+            pyproject
+                .dependency_group(&PackageName::parse(name).unwrap())
+                .map(|requirements| requirements.iter().map(|requirement| requirement.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(group("test").unwrap(), vec!["pytest", "mypy", "types-requests"]);
+        // Including a group twice is not a cycle.
+        assert_eq!(group("all").unwrap(), vec!["pytest", "mypy", "types-requests", "mypy", "types-requests"]);
+
+        let errors = [
+            ("loop-a", "dependency-groups.loop-b[1]", "cyclic include: loop-a -> loop-b -> loop-a"),
+            ("broken", "dependency-groups.broken[0]", "unknown group `missing`"),
+            ("docs", "dependency-groups.docs", "no such group"),
+        ];
+        for (name, expected_key, expected_reason) in &errors {
+            match group(name) {
+                Err(Error::PyProjectError { key, reason }) => {
+                    assert_eq!((key.as_str(), reason.as_str()), (*expected_key, *expected_reason));
+                }
+                other => panic!("Expected a pyproject error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases: &[(&str, &str)] = &[
+            ("project = 1", "project"),
+            ("[project]\nversion = '1.0'", "project.name"),
+            ("[project]\nname = '-spam'\nversion = '1.0'", "project.name"),
+            ("[project]\nname = 'spam'\nversion = 'one'", "project.version"),
+            ("[project]\nname = 'spam'\ndynamic = ['version']\nversion = '1'", "project.version"),
+            ("[project]\nname = 'spam'\ndynamic = ['name']", "project.dynamic[0]"),
+            ("[project]\nname = 'spam'\ndynamic = ['version', 'colour']", "project.dynamic[1]"),
+            ("[project]\nname = 'spam'\nversion = '1'\nrequires-python = '3.8'",
+             "project.requires-python"),
+            ("[project]\nname = 'spam'\nversion = '1'\ndependencies = ['a', 'b>']",
+             "project.dependencies[1]"),
+            ("[project]\nname = 'spam'\nversion = '1'\ndependencies = 'a'",
+             "project.dependencies"),
+            ("[project]\nname = 'spam'\nversion = '1'\n[project.optional-dependencies]\nx = ['a', 1]",
+             "project.optional-dependencies.x[1]"),
+            ("[project]\nname = 'spam'\nversion = '1'\n[project.optional-dependencies]\nA_B = []\na-b = []",
+             "project.optional-dependencies.a-b"),
+            ("[project", ""),
+            ("build-system = []", "build-system"),
+            ("[build-system]\nbuild-backend = 'flit_core.buildapi'", "build-system.requires"),
+            ("[build-system]\nrequires = ['flit_core >=3.2,<4 ']\nbuild-backend = 3",
+             "build-system.build-backend"),
+            ("[build-system]\nrequires = ['setuptools', 'wheel>']", "build-system.requires[1]"),
+            ("[build-system]\nrequires = []\nbackend-path = ['../outside']",
+             "build-system.backend-path[0]"),
+            ("[build-system]\nrequires = []\nbackend-path = ['/abs']",
+             "build-system.backend-path[0]"),
+            ("dependency-groups = 1", "dependency-groups"),
+            ("[dependency-groups]\ntest = 'pytest'", "dependency-groups.test"),
+            ("[dependency-groups]\n'-test' = []", "dependency-groups.-test"),
+            ("[dependency-groups]\nTest = []\ntest = []", "dependency-groups.test"),
+            ("[dependency-groups]\ntest = ['pytest', 'a>']", "dependency-groups.test[1]"),
+            ("[dependency-groups]\ntest = [{include = 'a'}]", "dependency-groups.test[0]"),
+            ("[dependency-groups]\ntest = [{include-group = 1}]", "dependency-groups.test[0].include-group"),
+            ("[dependency-groups]\ntest = [{include-group = 'a', extra = 1}]", "dependency-groups.test[0]"),
+            ("[dependency-groups]\ntest = [1]", "dependency-groups.test[0]"),
+        ];
+        for (input, expected) in cases {
+            let (key, reason) = parse_error(input);
+            assert_eq!(key, *expected, "input={:?}, reason={}", input, reason);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Sets of versions, represented as unions of disjoint intervals over the
+//! ordering of `Version`. These are what the resolver reasons about: unlike
+//! specifiers, they are closed under union, intersection and complement.
+
+use crate::specifier::{Operator, Specifier, SpecifierSet};
+use crate::Version;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A set of versions.
+///
+/// The segments are kept sorted, non-empty, and separated by a non-empty gap,
+/// so that equal sets have equal representations.
+pub struct Ranges {
+    segments: Vec<(Bound<Version>, Bound<Version>)>,
+}
+
+impl Ranges {
+    /// The set containing no version.
+    pub fn empty() -> Ranges {
+        Ranges { segments: vec![] }
+    }
+
+    /// The set containing every version.
+    pub fn full() -> Ranges {
+        Ranges { segments: vec![(Unbounded, Unbounded)] }
+    }
+
+    /// The set containing `version` only.
+    pub fn singleton(version: Version) -> Ranges {
+        Ranges { segments: vec![(Included(version.clone()), Included(version))] }
+    }
+
+    /// The versions `>= version`.
+    pub fn higher_than(version: Version) -> Ranges {
+        Ranges { segments: vec![(Included(version), Unbounded)] }
+    }
+
+    /// The versions `< version`.
+    pub fn strictly_lower_than(version: Version) -> Ranges {
+        Ranges { segments: vec![(Unbounded, Excluded(version))] }
+    }
+
+    /// The versions `>= lower` and `< upper`.
+    pub fn between(lower: Version, upper: Version) -> Ranges {
+        Ranges::from_segment(Included(lower), Excluded(upper))
+    }
+
+    /// The versions between two bounds, or the empty set if the bounds are
+    /// the wrong way around.
+    pub fn from_segment(lower: Bound<Version>, upper: Bound<Version>) -> Ranges {
+        if valid_segment(&lower, &upper) {
+            Ranges { segments: vec![(lower, upper)] }
+        } else {
+            Ranges::empty()
+        }
+    }
+
+    /// Converts specifiers into the set of versions they match.
+    ///
+    /// Pre-releases are included; excluding them is a matter of policy which
+    /// is left to the caller. Otherwise the conversion is exact, except for
+    /// corner cases which intervals can't express, where `<V` and `>V` also
+    /// exclude pre-, post- or local releases of the base version of `V` which
+    /// are not adjacent to `V` (e.g. `>1.0rc1` excludes `1.0.post1`, but the
+    /// range includes it).
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::ranges::Ranges;
+    /// # use pep440::specifier::SpecifierSet;
+    /// let ranges = Ranges::from_specifiers(&SpecifierSet::parse("~=1.4.2, !=1.4.5").unwrap());
+    /// assert_eq!(ranges.to_string(), ">=1.4.2, <1.4.5 | >=1.4.5.post0.dev0, <1.5.dev0");
+    /// assert!(ranges.contains(&Version::parse("1.4.9").unwrap()));
+    /// assert!(!ranges.contains(&Version::parse("1.4.5+local").unwrap()));
+    /// ```
+    pub fn from_specifiers(specifiers: &SpecifierSet) -> Ranges {
+        specifiers
+            .specifiers
+            .iter()
+            .fold(Ranges::full(), |ranges, specifier| ranges.intersection(&Ranges::from_specifier(specifier)))
+    }
+
+    /// Converts a single specifier, see [`Ranges::from_specifiers`].
+    pub fn from_specifier(specifier: &Specifier) -> Ranges {
+        let version = specifier.version.clone();
+        match specifier.operator {
+            Operator::Equal if specifier.wildcard => prefix_ranges(&version.release, version.epoch),
+            Operator::Equal => equal_ranges(version),
+            Operator::NotEqual => {
+                let equal = Specifier { operator: Operator::Equal, ..specifier.clone() };
+                Ranges::from_specifier(&equal).complement()
+            }
+            Operator::ArbitraryEqual => Ranges::singleton(version),
+            Operator::GreaterThanEqual => Ranges::higher_than(version),
+            Operator::LessThanEqual => match after_locals(&version) {
+                Some(next) => Ranges::strictly_lower_than(next),
+                None => Ranges::from_segment(Unbounded, Included(version)),
+            },
+            // Local versions of `V` don't match `>V`...
+            Operator::GreaterThan if version.is_postrelease() => match after_locals(&version) {
+                Some(next) => Ranges::higher_than(next),
+                None => Ranges::from_segment(Excluded(version), Unbounded),
+            },
+            // ...and neither do its post-releases, unless `V` is one.
+            Operator::GreaterThan => {
+                let last_post = Version { post: Some(u32::MAX), dev: None, local: vec![], ..version };
+                Ranges::from_segment(Excluded(last_post), Unbounded)
+            }
+            Operator::LessThan if version.is_prerelease() || version.is_postrelease() => {
+                Ranges::strictly_lower_than(version)
+            }
+            // Pre-releases of `V` don't match `<V`; `V.dev0` is the first one.
+            Operator::LessThan => Ranges::strictly_lower_than(Version { dev: Some(0), ..version }),
+            Operator::Compatible => {
+                let prefix = &version.release[..version.release.len() - 1];
+                Ranges::higher_than(version.clone()).intersection(&prefix_ranges(prefix, version.epoch))
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the disjoint intervals of the set, in ascending order.
+    pub fn segments(&self) -> impl Iterator<Item = (&Bound<Version>, &Bound<Version>)> {
+        self.segments.iter().map(|(lower, upper)| (lower, upper))
+    }
+
+    /// Returns the only version of the set, if it has exactly one.
+    pub fn as_singleton(&self) -> Option<&Version> {
+        match self.segments.as_slice() {
+            [(Included(lower), Included(upper))] if lower == upper => Some(lower),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        self.segments.iter().any(|(lower, upper)| {
+            let above = match lower {
+                Unbounded => true,
+                Included(lower) => version >= lower,
+                Excluded(lower) => version > lower,
+            };
+            let below = match upper {
+                Unbounded => true,
+                Included(upper) => version <= upper,
+                Excluded(upper) => version < upper,
+            };
+            above && below
+        })
+    }
+
+    /// The versions not in this set.
+    pub fn complement(&self) -> Ranges {
+        let mut segments = vec![];
+        let mut start = Unbounded;
+        for (lower, upper) in &self.segments {
+            match lower {
+                Unbounded => {}
+                Included(version) => segments.push((start, Excluded(version.clone()))),
+                Excluded(version) => segments.push((start, Included(version.clone()))),
+            }
+            start = match upper {
+                Unbounded => return Ranges { segments },
+                Included(version) => Excluded(version.clone()),
+                Excluded(version) => Included(version.clone()),
+            };
+        }
+        segments.push((start, Unbounded));
+        Ranges { segments }
+    }
+
+    pub fn intersection(&self, other: &Ranges) -> Ranges {
+        let mut segments = vec![];
+        let (mut left, mut right) = (self.segments.iter().peekable(), other.segments.iter().peekable());
+        while let (Some((left_lower, left_upper)), Some((right_lower, right_upper))) = (left.peek(), right.peek()) {
+            let lower = if cmp_lower(left_lower, right_lower) == Ordering::Greater { left_lower } else { right_lower };
+            let left_first = cmp_upper(left_upper, right_upper) == Ordering::Less;
+            let upper = if left_first { left_upper } else { right_upper };
+            if valid_segment(lower, upper) {
+                segments.push((lower.clone(), upper.clone()));
+            }
+            if left_first {
+                left.next();
+            } else {
+                right.next();
+            }
+        }
+        Ranges { segments }
+    }
+
+    pub fn union(&self, other: &Ranges) -> Ranges {
+        self.complement().intersection(&other.complement()).complement()
+    }
+
+    /// Returns `true` if every version of this set is also in `other`.
+    pub fn subset_of(&self, other: &Ranges) -> bool {
+        self.intersection(other) == *self
+    }
+
+    pub fn is_disjoint(&self, other: &Ranges) -> bool {
+        self.intersection(other).is_empty()
+    }
+}
+
+/// Orders lower bounds by the smallest version they admit.
+fn cmp_lower(a: &Bound<Version>, b: &Bound<Version>) -> Ordering {
+    match (a, b) {
+        (Unbounded, Unbounded) => Ordering::Equal,
+        (Unbounded, _) => Ordering::Less,
+        (_, Unbounded) => Ordering::Greater,
+        (Included(a), Included(b)) | (Excluded(a), Excluded(b)) => a.cmp(b),
+        (Included(a), Excluded(b)) => a.cmp(b).then(Ordering::Less),
+        (Excluded(a), Included(b)) => a.cmp(b).then(Ordering::Greater),
+    }
+}
+
+/// Orders upper bounds by the largest version they admit.
+fn cmp_upper(a: &Bound<Version>, b: &Bound<Version>) -> Ordering {
+    match (a, b) {
+        (Unbounded, Unbounded) => Ordering::Equal,
+        (Unbounded, _) => Ordering::Greater,
+        (_, Unbounded) => Ordering::Less,
+        (Included(a), Included(b)) | (Excluded(a), Excluded(b)) => a.cmp(b),
+        (Included(a), Excluded(b)) => a.cmp(b).then(Ordering::Greater),
+        (Excluded(a), Included(b)) => a.cmp(b).then(Ordering::Less),
+    }
+}
+
+fn valid_segment(lower: &Bound<Version>, upper: &Bound<Version>) -> bool {
+    match (lower, upper) {
+        (Unbounded, _) | (_, Unbounded) => true,
+        (Included(lower), Included(upper)) => lower <= upper,
+        (Included(lower), Excluded(upper))
+        | (Excluded(lower), Included(upper))
+        | (Excluded(lower), Excluded(upper)) => lower < upper,
+    }
+}
+
+/// The smallest version which is greater than `version` and all of its local
+/// versions, or `None` if there is no such version because a segment would
+/// overflow.
+fn after_locals(version: &Version) -> Option<Version> {
+    let version = version.public();
+    match version.dev {
+        Some(dev) => Some(Version { dev: Some(dev.checked_add(1)?), ..version }),
+        None => {
+            let post = match version.post {
+                Some(post) => post.checked_add(1)?,
+                None => 0,
+            };
+            Some(Version { post: Some(post), dev: Some(0), ..version })
+        }
+    }
+}
+
+/// `==V` matches `V` and every local version of it, unless `V` is itself a
+/// local version.
+fn equal_ranges(version: Version) -> Ranges {
+    if !version.local.is_empty() {
+        return Ranges::singleton(version);
+    }
+    match after_locals(&version) {
+        Some(next) => Ranges::between(version, next),
+        None => Ranges::higher_than(version),
+    }
+}
+
+/// The versions whose release starts with `prefix`, e.g. `==1.4.*`:
+/// `>=1.4.dev0, <1.5.dev0`.
+fn prefix_ranges(prefix: &[u32], epoch: u32) -> Ranges {
+    let version = |release: Vec<u32>| Version { epoch, dev: Some(0), ..Version::from_release(release) };
+    let mut next = prefix.to_vec();
+    match next.last_mut().and_then(|last| last.checked_add(1)) {
+        Some(bumped) => {
+            *next.last_mut().unwrap() = bumped;
+            Ranges::between(version(prefix.to_vec()), version(next))
+        }
+        None => Ranges::higher_than(version(prefix.to_vec())),
+    }
+}
+
+impl fmt::Display for Ranges {
+    /// Formats the set as `|`-separated intervals in specifier syntax, e.g.
+    /// `>=1.0, <2.0 | ==3.0`. The full set is `*` and the empty set `∅`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "∅");
+        }
+        let segments: Vec<String> = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                (Unbounded, Unbounded) => "*".to_string(),
+                (Included(lower), Included(upper)) if lower == upper => format!("=={}", lower),
+                (Included(lower), Excluded(upper)) if after_locals(lower).as_ref() == Some(upper) => {
+                    format!("=={}", lower)
+                }
+                (lower, upper) => {
+                    let mut bounds = vec![];
+                    match lower {
+                        Unbounded => {}
+                        Included(version) => bounds.push(format!(">={}", version)),
+                        Excluded(version) => bounds.push(format!(">{}", version)),
+                    }
+                    match upper {
+                        Unbounded => {}
+                        Included(version) => bounds.push(format!("<={}", version)),
+                        Excluded(version) => bounds.push(format!("<{}", version)),
+                    }
+                    bounds.join(", ")
+                }
+            })
+            .collect();
+        write!(f, "{}", segments.join(" | "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ranges::*;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    /// Versions around the interesting boundaries of the specifiers below.
+    const VERSIONS: &[&str] = &[
+        "0.9", "1.0.dev0", "1.0a1", "1.0rc1", "1.0", "1.0+local", "1.0.post1.dev1", "1.0.post1",
+        "1.0.post1+local", "1.0.1", "1.1.dev0", "1.1", "1.4", "1.4.2", "1.4.5", "1.4.5+local",
+        "1.5.dev0", "1.5", "2.0", "1!1.0",
+    ];
+
+    /// Specifiers whose ranges agree with `Specifier::contains` on `VERSIONS`.
+    const SPECIFIERS: &[&str] = &[
+        ">=1.0", ">1.0", "<1.0", "<=1.0", "==1.0", "!=1.0", "==1.0+local", "==1.*", "!=1.0.*",
+        "~=1.4.2", "~=1.0", ">1.0.post1", "<1.0rc1", "===1.0", "==1!1.0", ">=1.0.post1",
+    ];
+
+    #[test]
+    fn test_from_specifier() {
+        for specifier in SPECIFIERS {
+            let parsed = Specifier::parse(specifier).unwrap();
+            let ranges = Ranges::from_specifier(&parsed);
+            for version in VERSIONS {
+                assert_eq!(
+                    ranges.contains(&v(version)),
+                    parsed.contains(&v(version)),
+                    "specifier={}, ranges={}, version={}", specifier, ranges, version);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = Ranges::between(v("1.0"), v("2.0"));
+        let b = Ranges::higher_than(v("1.5")).union(&Ranges::singleton(v("0.5")));
+        assert_eq!(a.intersection(&b).to_string(), ">=1.5, <2.0");
+        assert_eq!(a.union(&b).to_string(), "==0.5 | >=1.0");
+        assert_eq!(b.complement().to_string(), "<0.5 | >0.5, <1.5");
+        assert_eq!(b.complement().complement(), b);
+        assert_eq!(Ranges::full().complement(), Ranges::empty());
+        assert_eq!(Ranges::empty().complement().to_string(), "*");
+        assert!(Ranges::singleton(v("1.2")).subset_of(&a));
+        assert!(!b.subset_of(&a));
+        assert!(a.is_disjoint(&Ranges::strictly_lower_than(v("1.0"))));
+        assert_eq!(Ranges::singleton(v("1.2")).as_singleton(), Some(&v("1.2")));
+        assert_eq!(Ranges::from_specifiers(&SpecifierSet::parse("==1.2").unwrap()).to_string(), "==1.2");
+
+        // Touching intervals are merged.
+        let touching = Ranges::strictly_lower_than(v("1.0")).union(&Ranges::higher_than(v("1.0")));
+        assert_eq!(touching, Ranges::full());
+    }
+}
@@ -0,0 +1,581 @@
+//! Parsing of the `RECORD` file of a wheel or installed distribution, as
+//! described in the "Recording installed projects" specification, and
+//! verification of the installed files against it.
+//!
+//! `RECORD` is a CSV file with a row per file: its path relative to
+//! `site-packages`, its hash as `{algorithm}={urlsafe-base64 digest}` and its
+//! size. Verification requires the `record` feature.
+
+use crate::error::Error;
+use crate::installed::InstalledDistribution;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// The hash of a file, e.g. `sha256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU`.
+pub struct RecordHash {
+    pub algorithm: String,
+    /// The digest, in urlsafe base64 without padding.
+    pub digest: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A row of a `RECORD`.
+pub struct RecordEntry {
+    /// The path relative to `site-packages`, with `/` separators. Files
+    /// installed elsewhere, such as scripts, start with `..`.
+    pub path: String,
+    /// `None` for the `RECORD` itself, and for `.pyc` files in some records.
+    pub hash: Option<RecordHash>,
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// A parsed `RECORD`.
+pub struct Record {
+    pub entries: Vec<RecordEntry>,
+}
+
+impl RecordHash {
+    /// Parses `{algorithm}={digest}`.
+    pub fn parse(input: &str) -> Option<RecordHash> {
+        let (algorithm, digest) = input.split_once('=')?;
+        if algorithm.is_empty() || digest.is_empty() {
+            return None;
+        }
+        Some(RecordHash { algorithm: algorithm.to_string(), digest: digest.to_string() })
+    }
+}
+
+impl fmt::Display for RecordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.algorithm, self.digest)
+    }
+}
+
+impl Record {
+    /// Parse the given `RECORD` contents.
+    ///
+    /// ```
+    /// # use pep440::record::Record;
+    /// let record = Record::parse("\
+    /// six.py,sha256=TOOfQi7nFGjMnk_XDasOsjwUfGiB29r6Wt1T8nQb4So,34549
+    /// six-1.16.0.dist-info/RECORD,,
+    /// ").unwrap();
+    /// assert_eq!(record.entries[0].size, Some(34549));
+    /// assert_eq!(record.entries[0].hash.as_ref().unwrap().algorithm, "sha256");
+    /// assert_eq!(record.entries[1].hash, None);
+    ///
+    /// let err = Record::parse("six.py,sha256=abc,many\n").unwrap_err();
+    /// assert_eq!(err.to_string(), "Invalid RECORD: line 1: invalid size `many`");
+    /// ```
+    pub fn parse(input: &str) -> Result<Record, Error> {
+        let mut entries = vec![];
+        for (index, line) in input.lines().enumerate() {
+            let error = |reason: String| Error::record_error(index + 1, reason);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split_csv(line).map_err(|reason| error(reason.to_string()))?;
+            let (path, hash, size) = match fields.as_slice() {
+                [path, hash, size] => (path, hash, size),
+                _ => return Err(error(format!("expected 3 fields, found {}", fields.len()))),
+            };
+            if path.is_empty() {
+                return Err(error("empty path".to_string()));
+            }
+            let hash = match hash.as_str() {
+                "" => None,
+                hash => Some(RecordHash::parse(hash).ok_or_else(|| error(format!("invalid hash `{}`", hash)))?),
+            };
+            let size = match size.as_str() {
+                "" => None,
+                size => Some(size.parse().map_err(|_| error(format!("invalid size `{}`", size)))?),
+            };
+            entries.push(RecordEntry { path: path.to_string(), hash, size });
+        }
+        Ok(Record { entries })
+    }
+
+    /// Reads the `RECORD` of an installed distribution. Only `.dist-info`
+    /// directories have one.
+    pub fn read(distribution: &InstalledDistribution) -> Result<Record, Error> {
+        let path = distribution.path.join("RECORD");
+        let contents = fs::read_to_string(&path).map_err(|err| Error::io_error(&path, err))?;
+        Record::parse(&contents)
+    }
+
+    /// Returns the entry for a path.
+    pub fn get(&self, path: &str) -> Option<&RecordEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+}
+
+impl fmt::Display for Record {
+    /// Writes the record as CSV, quoting paths only where needed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let path = if entry.path.contains([',', '"']) {
+                format!("\"{}\"", entry.path.replace('"', "\"\""))
+            } else {
+                entry.path.clone()
+            };
+            let hash = entry.hash.as_ref().map(RecordHash::to_string).unwrap_or_default();
+            let size = entry.size.map(|size| size.to_string()).unwrap_or_default();
+            writeln!(f, "{},{},{}", path, hash, size)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Record {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Record::parse(s)
+    }
+}
+
+/// Splits a CSV line, where fields may be quoted with `"` and quotes in a
+/// quoted field are doubled.
+fn split_csv(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field"),
+                }
+            }
+            match chars.next() {
+                Some(',') => fields.push(field),
+                None => {
+                    fields.push(field);
+                    return Ok(fields);
+                }
+                Some(_) => return Err("unexpected character after quoted field"),
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    Some(',') => break,
+                    Some(c) => field.push(c),
+                    None => {
+                        fields.push(field);
+                        return Ok(fields);
+                    }
+                }
+            }
+            fields.push(field);
+        }
+    }
+}
+
+#[cfg(feature = "record")]
+mod verify {
+    use crate::installed::{DistributionKind, SitePackages};
+    use crate::name::PackageName;
+    use crate::record::*;
+    use crate::Version;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+    use std::collections::{HashMap, HashSet};
+    use std::path::{Path, PathBuf};
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    /// What is wrong with a file.
+    pub enum FileProblemKind {
+        /// The contents don't match the recorded hash.
+        Modified { expected: RecordHash, actual: RecordHash },
+        /// The size doesn't match the recorded size.
+        SizeMismatch { expected: u64, actual: u64 },
+        /// The recorded hash uses an algorithm which can't be checked, so the
+        /// contents weren't verified.
+        UnsupportedHash { algorithm: String },
+        /// A recorded file doesn't exist.
+        Missing,
+        /// A file in one of the distribution's directories isn't recorded by
+        /// any distribution.
+        Unlisted,
+        /// The `RECORD` itself can't be read, so nothing was verified.
+        InvalidRecord { reason: String },
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    /// A file which fails verification.
+    pub struct FileProblem {
+        /// The path relative to `site-packages`, with `/` separators.
+        pub path: String,
+        pub kind: FileProblemKind,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    /// The result of verifying one distribution.
+    pub struct Verification {
+        pub name: PackageName,
+        pub version: Version,
+        pub problems: Vec<FileProblem>,
+    }
+
+    impl RecordHash {
+        /// Hashes `contents` with the given algorithm, or returns `None` if
+        /// the algorithm isn't supported. SHA-2 algorithms are supported.
+        pub fn compute(algorithm: &str, contents: &[u8]) -> Option<RecordHash> {
+            let digest = match algorithm {
+                "sha224" => Sha224::digest(contents).to_vec(),
+                "sha256" => Sha256::digest(contents).to_vec(),
+                "sha384" => Sha384::digest(contents).to_vec(),
+                "sha512" => Sha512::digest(contents).to_vec(),
+                _ => return None,
+            };
+            Some(RecordHash { algorithm: algorithm.to_string(), digest: URL_SAFE_NO_PAD.encode(digest) })
+        }
+
+        /// Hashes `contents` with SHA-256, the algorithm installers write.
+        ///
+        /// ```
+        /// # use pep440::record::RecordHash;
+        /// assert_eq!(RecordHash::sha256(b"").to_string(), "sha256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU");
+        /// ```
+        pub fn sha256(contents: &[u8]) -> RecordHash {
+            RecordHash::compute("sha256", contents).unwrap()
+        }
+    }
+
+    impl Verification {
+        /// Verifies the files of one distribution: that every recorded file
+        /// exists with the recorded hash and size, and that the directories
+        /// it installed hold no unrecorded files. Compiled `.pyc` files in
+        /// `__pycache__` directories are not required to be recorded.
+        ///
+        /// Hashes with unsupported algorithms, such as `md5`, are reported as
+        /// problems rather than trusted.
+        pub fn verify(distribution: &InstalledDistribution) -> Verification {
+            let mut ownership = Ownership::default();
+            ownership.add(distribution);
+            verify_with(distribution, &mut ownership)
+        }
+
+        /// Verifies every `.dist-info` distribution in `site_packages`. A
+        /// file is only unlisted if no distribution records it, so that
+        /// namespace packages shared by several distributions are handled,
+        /// and it is reported for the distribution which records files
+        /// closest to it.
+        ///
+        /// ```no_run
+        /// # use pep440::installed::SitePackages;
+        /// # use pep440::record::Verification;
+        /// let site_packages = SitePackages::scan("/usr/lib/python3/dist-packages").unwrap();
+        /// for verification in Verification::verify_all(&site_packages) {
+        ///     for problem in &verification.problems {
+        ///         println!("{} {}: {}", verification.name, verification.version, problem);
+        ///     }
+        /// }
+        /// ```
+        pub fn verify_all(site_packages: &SitePackages) -> Vec<Verification> {
+            let distributions: Vec<&InstalledDistribution> = site_packages
+                .iter()
+                .filter(|distribution| distribution.kind == DistributionKind::DistInfo)
+                .collect();
+            let mut ownership = Ownership::default();
+            for distribution in &distributions {
+                ownership.add(distribution);
+            }
+            distributions
+                .into_iter()
+                .map(|distribution| verify_with(distribution, &mut ownership))
+                .collect()
+        }
+
+        /// Returns `true` if no problems were found.
+        pub fn is_ok(&self) -> bool {
+            self.problems.is_empty()
+        }
+    }
+
+    #[derive(Default)]
+    /// Which distributions the files and directories of `site-packages`
+    /// belong to.
+    struct Ownership {
+        /// Every recorded file.
+        files: HashSet<PathBuf>,
+        /// The directories holding recorded files, other than `site-packages`
+        /// itself, with the `.dist-info` path of the first distribution
+        /// recording files there.
+        directories: HashMap<PathBuf, PathBuf>,
+        /// The unlisted files without an owning directory which have already
+        /// been reported.
+        reported: HashSet<PathBuf>,
+    }
+
+    impl Ownership {
+        fn add(&mut self, distribution: &InstalledDistribution) {
+            let record = match Record::read(distribution) {
+                Ok(record) => record,
+                Err(_) => return,
+            };
+            let root = site_packages(distribution);
+            for entry in &record.entries {
+                let path = root.join(&entry.path);
+                if let Some(parent) = path.parent().filter(|parent| *parent != root) {
+                    self.directories.entry(parent.to_path_buf()).or_insert_with(|| distribution.path.clone());
+                }
+                self.files.insert(path);
+            }
+        }
+
+        /// Returns `true` if an unlisted file is to be reported for the
+        /// distribution.
+        fn report(&mut self, distribution: &InstalledDistribution, file: &Path) -> bool {
+            let root = site_packages(distribution);
+            let owner = file
+                .ancestors()
+                .skip(1)
+                .take_while(|directory| *directory != root)
+                .find_map(|directory| self.directories.get(directory));
+            match owner {
+                Some(owner) => *owner == distribution.path,
+                None => self.reported.insert(file.to_path_buf()),
+            }
+        }
+    }
+
+    fn site_packages(distribution: &InstalledDistribution) -> &Path {
+        distribution.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    fn verify_with(distribution: &InstalledDistribution, ownership: &mut Ownership) -> Verification {
+        let mut verification = Verification {
+            name: distribution.name.clone(),
+            version: distribution.version.clone(),
+            problems: vec![],
+        };
+        let root = site_packages(distribution);
+        let record = match Record::read(distribution) {
+            Ok(record) => record,
+            Err(err) => {
+                let path = distribution.path.join("RECORD");
+                verification.problems.push(FileProblem {
+                    path: relative(root, &path),
+                    kind: FileProblemKind::InvalidRecord { reason: err.to_string() },
+                });
+                return verification;
+            }
+        };
+
+        let mut directories = vec![];
+        for entry in &record.entries {
+            if let Some(kind) = check_entry(&root.join(&entry.path), entry) {
+                verification.problems.push(FileProblem { path: entry.path.clone(), kind });
+            }
+            if let Some((top, _)) = entry.path.split_once('/') {
+                if top != ".." && top != "." && !directories.contains(&top) {
+                    directories.push(top);
+                }
+            }
+        }
+
+        let mut files = vec![];
+        for directory in directories {
+            walk(&root.join(directory), &mut files);
+        }
+        files.sort();
+        for file in files {
+            let compiled = file.extension().is_some_and(|extension| extension == "pyc")
+                && file.parent().and_then(Path::file_name).is_some_and(|parent| parent == "__pycache__");
+            if !compiled && !ownership.files.contains(&file) && ownership.report(distribution, &file) {
+                verification.problems.push(FileProblem { path: relative(root, &file), kind: FileProblemKind::Unlisted });
+            }
+        }
+        verification
+    }
+
+    fn check_entry(path: &Path, entry: &RecordEntry) -> Option<FileProblemKind> {
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(_) => return Some(FileProblemKind::Missing),
+        };
+        if let Some(expected) = &entry.hash {
+            match RecordHash::compute(&expected.algorithm, &contents) {
+                Some(actual) if actual != *expected => {
+                    return Some(FileProblemKind::Modified { expected: expected.clone(), actual });
+                }
+                Some(_) => {}
+                None => {
+                    return Some(FileProblemKind::UnsupportedHash { algorithm: expected.algorithm.clone() });
+                }
+            }
+        }
+        match entry.size {
+            Some(expected) if expected != contents.len() as u64 => {
+                Some(FileProblemKind::SizeMismatch { expected, actual: contents.len() as u64 })
+            }
+            _ => None,
+        }
+    }
+
+    /// Collects the files under `directory`, recursively. Unreadable
+    /// directories are skipped.
+    fn walk(directory: &Path, files: &mut Vec<PathBuf>) {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => walk(&path, files),
+                Ok(_) => files.push(path),
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn relative(root: &Path, path: &Path) -> String {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    impl fmt::Display for FileProblem {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.kind {
+                FileProblemKind::Modified { expected, actual } => {
+                    write!(f, "{}: hash is {}, but {} is recorded", self.path, actual, expected)
+                }
+                FileProblemKind::SizeMismatch { expected, actual } => {
+                    write!(f, "{}: size is {}, but {} is recorded", self.path, actual, expected)
+                }
+                FileProblemKind::UnsupportedHash { algorithm } => {
+                    write!(f, "{}: unsupported hash algorithm `{}`", self.path, algorithm)
+                }
+                FileProblemKind::Missing => write!(f, "{}: missing", self.path),
+                FileProblemKind::Unlisted => write!(f, "{}: not recorded", self.path),
+                FileProblemKind::InvalidRecord { reason } => write!(f, "{}: {}", self.path, reason),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "record")]
+pub use verify::{FileProblem, FileProblemKind, Verification};
+
+#[cfg(test)]
+mod tests {
+    use crate::record::*;
+
+    #[test]
+    fn test_parse() {
+        let record = Record::parse(
+            "pkg/__init__.py,sha256=abc,3\n\
+             \"pkg/odd, name.py\",sha256=def,0\n\
+             ../../../bin/tool,sha512=ghi,10\n\
+             \n\
+             pkg-1.0.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+        assert_eq!(record.entries.len(), 4);
+        assert_eq!(record.entries[1].path, "pkg/odd, name.py");
+        assert_eq!(record.get("../../../bin/tool").unwrap().hash.as_ref().unwrap().algorithm, "sha512");
+        assert_eq!(Record::parse(&record.to_string()).unwrap(), record);
+
+        let cases: &[(&str, &str)] = &[
+            ("a,b\n", "line 1: expected 3 fields, found 2"),
+            ("a,,\n,,\n", "line 2: empty path"),
+            ("a,sha256,1\n", "line 1: invalid hash `sha256`"),
+            ("a,,-1\n", "line 1: invalid size `-1`"),
+            ("\"a,,\n", "line 1: unterminated quoted field"),
+            ("\"a\"b,,\n", "line 1: unexpected character after quoted field"),
+        ];
+        for (input, reason) in cases {
+            let err = Record::parse(input).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid RECORD: {}", reason), "input={:?}", input);
+        }
+    }
+
+    #[cfg(feature = "record")]
+    #[test]
+    fn test_verify() {
+        use crate::installed::SitePackages;
+        use crate::scratch::ScratchDir;
+        use std::fs;
+
+        let dir = ScratchDir::new("record");
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        let entry = |path: &str, contents: &str| {
+            format!("{},{},{}\n", path, RecordHash::sha256(contents.as_bytes()), contents.len())
+        };
+        write("pkg-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: pkg\nVersion: 1.0\n");
+        write("ns/pkg/__init__.py", "original");
+        write("ns/pkg/data.txt", "data");
+        write("ns/pkg/__pycache__/__init__.cpython-312.pyc", "compiled");
+        write("ns/pkg/extra.py", "dropped in");
+        write("ns/pkg/legacy.py", "legacy");
+        write("pkg-1.0.dist-info/RECORD", &[
+            entry("pkg-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: pkg\nVersion: 1.0\n"),
+            entry("ns/pkg/__init__.py", "changed"),
+            "ns/pkg/data.txt,,7\n".to_string(),
+            entry("ns/pkg/gone.py", ""),
+            "ns/pkg/legacy.py,md5=Z8Qp6V8jFi9QNyInqJwQkA,6\n".to_string(),
+            "pkg-1.0.dist-info/RECORD,,\n".to_string(),
+        ].concat());
+        // Another distribution in the same namespace package.
+        write("other-2.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: other\nVersion: 2.0\n");
+        write("ns/other.py", "other");
+        write("other-2.0.dist-info/RECORD", &[
+            entry("ns/other.py", "other"),
+            entry("other-2.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: other\nVersion: 2.0\n"),
+            "other-2.0.dist-info/RECORD,,\n".to_string(),
+        ].concat());
+        write("broken-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: broken\nVersion: 1.0\n");
+
+        let site_packages = SitePackages::scan(&dir).unwrap();
+        let verifications = Verification::verify_all(&site_packages);
+        let summary: Vec<(String, Vec<String>)> = verifications
+            .iter()
+            .map(|verification| {
+                let problems = verification.problems.iter().map(|problem| problem.to_string()).collect();
+                (format!("{} {}", verification.name, verification.version), problems)
+            })
+            .collect();
+        let modified = format!(
+            "ns/pkg/__init__.py: hash is {}, but {} is recorded",
+            RecordHash::sha256(b"original"),
+            RecordHash::sha256(b"changed"));
+        assert_eq!(summary[1], ("other 2.0".to_string(), vec![]));
+        assert_eq!(summary[2], ("pkg 1.0".to_string(), vec![
+            modified,
+            "ns/pkg/data.txt: size is 4, but 7 is recorded".to_string(),
+            "ns/pkg/gone.py: missing".to_string(),
+            "ns/pkg/legacy.py: unsupported hash algorithm `md5`".to_string(),
+            "ns/pkg/extra.py: not recorded".to_string(),
+        ]));
+        assert_eq!(summary[0].0, "broken 1.0");
+        assert!(matches!(
+            verifications[0].problems[0].kind,
+            FileProblemKind::InvalidRecord { .. }));
+        assert!(verifications[1].is_ok());
+
+        // On its own, `pkg` doesn't know that `ns/other.py` is recorded.
+        let pkg = &site_packages.get(&crate::name::PackageName::parse("pkg").unwrap())[0];
+        let alone = Verification::verify(pkg);
+        assert!(alone.problems.iter().any(|problem| problem.path == "ns/other.py"));
+    }
+}
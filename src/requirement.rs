@@ -0,0 +1,240 @@
+//! Dependency specifications, as described in PEP508, e.g.
+//! `requests[socks] >= 2.8.1, == 2.8.* ; python_version < "3.12"`.
+
+use crate::direct_url::DirectUrl;
+use crate::error::Error;
+use crate::marker::{MarkerEnvironment, MarkerTree};
+use crate::name::PackageName;
+use crate::specifier::SpecifierSet;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    /// Matches the (not yet validated) name at the start of a requirement.
+    static ref NAME_PREFIX_RE: Regex = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]*").unwrap();
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// What a requirement asks for: a set of versions, or a direct URL.
+pub enum VersionOrUrl {
+    Specifiers(SpecifierSet),
+    Url(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A parsed PEP508 requirement.
+pub struct Requirement {
+    pub name: PackageName,
+    pub extras: Vec<PackageName>,
+    pub version_or_url: Option<VersionOrUrl>,
+    pub marker: Option<MarkerTree>,
+}
+
+impl Requirement {
+    /// Attempt to parse the given requirement string.
+    ///
+    /// ```
+    /// # use pep440::requirement::{Requirement, VersionOrUrl};
+    /// let req = Requirement::parse("Requests[socks]>=2.8.1,<3 ; python_version<'3.12'").unwrap();
+    /// assert_eq!(req.name.as_str(), "requests");
+    /// assert_eq!(req.extras[0].as_str(), "socks");
+    /// assert_eq!(req.to_string(), "requests[socks]>=2.8.1, <3; python_version < \"3.12\"");
+    ///
+    /// let req = Requirement::parse("pip @ https://example.com/pip-24.0.tar.gz").unwrap();
+    /// assert_eq!(
+    ///     req.version_or_url,
+    ///     Some(VersionOrUrl::Url("https://example.com/pip-24.0.tar.gz".to_string())));
+    /// ```
+    pub fn parse(input: &str) -> Result<Requirement, Error> {
+        let error = |reason: &str| Error::requirement_parse_error(input.to_string(), reason.to_string());
+        let rest = input.trim();
+
+        let name = NAME_PREFIX_RE
+            .find(rest)
+            .ok_or_else(|| error("expected a package name"))?
+            .as_str();
+        let parsed_name = PackageName::parse(name)
+            .ok_or_else(|| error(&format!("invalid package name `{}`", name)))?;
+        let mut rest = rest[name.len()..].trim_start();
+
+        let mut extras = vec![];
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(|| error("expected `]`"))?;
+            for extra in after[..end].split(',').map(str::trim) {
+                if extra.is_empty() && end != 0 {
+                    return Err(error("empty extra name"));
+                } else if extra.is_empty() {
+                    continue;
+                }
+                let extra = PackageName::parse(extra)
+                    .ok_or_else(|| error(&format!("invalid extra name `{}`", extra)))?;
+                extras.push(extra);
+            }
+            rest = after[end + 1..].trim_start();
+        }
+
+        let (version_or_url, rest) = if let Some(after) = rest.strip_prefix('@') {
+            let after = after.trim_start();
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            let url = &after[..end];
+            if url.is_empty() {
+                return Err(error("expected a URL after `@`"));
+            }
+            (Some(VersionOrUrl::Url(url.to_string())), &after[end..])
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            let mut specifiers = rest[..end].trim();
+            if let Some(inner) = specifiers.strip_prefix('(') {
+                specifiers = inner
+                    .strip_suffix(')')
+                    .ok_or_else(|| error("expected `)`"))?;
+            }
+            let version_or_url = if specifiers.trim().is_empty() {
+                None
+            } else {
+                let parsed = SpecifierSet::parse(specifiers)
+                    .ok_or_else(|| error(&format!("invalid version specifier `{}`", specifiers.trim())))?;
+                Some(VersionOrUrl::Specifiers(parsed))
+            };
+            (version_or_url, &rest[end..])
+        };
+
+        let rest = rest.trim();
+        let marker = if rest.is_empty() {
+            None
+        } else if let Some(marker) = rest.strip_prefix(';') {
+            Some(MarkerTree::parse(marker.trim())?)
+        } else {
+            return Err(error(&format!("unexpected trailing input `{}`", rest)));
+        };
+
+        Ok(Requirement { name: parsed_name, extras, version_or_url, marker })
+    }
+
+    /// Returns the version specifiers of the requirement; a requirement
+    /// without any gives an empty set, which allows any version.
+    pub fn specifiers(&self) -> SpecifierSet {
+        match &self.version_or_url {
+            Some(VersionOrUrl::Specifiers(specifiers)) => specifiers.clone(),
+            _ => SpecifierSet::default(),
+        }
+    }
+
+    /// Parses the URL of a direct reference, or returns `None` for a
+    /// requirement by version.
+    ///
+    /// ```
+    /// # use pep440::requirement::Requirement;
+    /// let req = Requirement::parse("pkg @ git+https://host/repo.git@v1.2#subdirectory=x").unwrap();
+    /// let direct = req.direct_url().unwrap().unwrap();
+    /// assert_eq!(direct.url, "https://host/repo.git");
+    /// assert_eq!(direct.revision_version().unwrap().to_string(), "1.2");
+    /// ```
+    pub fn direct_url(&self) -> Result<Option<DirectUrl>, Error> {
+        match &self.version_or_url {
+            Some(VersionOrUrl::Url(url)) => DirectUrl::parse(url).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns `true` if the requirement applies to the given environment
+    /// with the given extras requested. A requirement without a marker always
+    /// applies.
+    pub fn evaluate_markers(&self, env: &MarkerEnvironment, extras: &[PackageName]) -> bool {
+        self.marker
+            .as_ref()
+            .map(|marker| marker.evaluate(env, extras))
+            .unwrap_or(true)
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.extras.is_empty() {
+            let extras = self.extras
+                .iter()
+                .map(|extra| extra.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            write!(f, "[{}]", extras)?;
+        }
+        match &self.version_or_url {
+            Some(VersionOrUrl::Specifiers(specifiers)) => write!(f, "{}", specifiers)?,
+            Some(VersionOrUrl::Url(url)) => {
+                write!(f, " @ {}", url)?;
+                if self.marker.is_some() {
+                    // The URL has to be separated from the `;` by whitespace.
+                    write!(f, " ")?;
+                }
+            }
+            None => {}
+        }
+        if let Some(marker) = &self.marker {
+            write!(f, "; {}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Requirement {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Requirement::parse(s)
+    }
+}
+
+#[cfg(test)]
+/// Some of the test data here is pulled from the python-packaging tests,
+/// https://github.com/pypa/packaging/blob/main/tests/test_requirements.py
+mod tests {
+    use crate::requirement::*;
+
+    const VALID_REQUIREMENTS: &[(&str, &str)] = &[
+        ("name", "name"),
+        ("Name_With.Dots", "name-with-dots"),
+        ("name>=3", "name>=3"),
+        ("name >= 3, < 4", "name>=3, <4"),
+        ("name (>=3)", "name>=3"),
+        ("name[]", "name"),
+        ("name[ bar , baz ]>=1", "name[bar,baz]>=1"),
+        ("name==1.0.*; os_name=='a'", "name==1.0.*; os_name == \"a\""),
+        ("name; os_name=='a' or os_name=='b'", "name; os_name == \"a\" or os_name == \"b\""),
+        ("name@ http://foo.com", "name @ http://foo.com"),
+        ("name [fred,bar] @ http://foo.com ; python_version=='2.7'",
+         "name[fred,bar] @ http://foo.com ; python_version == \"2.7\""),
+        ("name @ file:///absolute/path", "name @ file:///absolute/path"),
+    ];
+
+    const INVALID_REQUIREMENTS: &[&str] = &[
+        "", "_name", "name[", "name[bar", "name[bar,]", "name[-bar]", "name>=",
+        "name>=1.0 foo", "name @", "name @ http://foo.com; os_name=='a'extra",
+        "name; foo == 'a'", "name >= 1.0.*", "name (>=1",
+    ];
+
+    #[test]
+    fn test_valid() {
+        for (input, expected) in VALID_REQUIREMENTS {
+            let req = Requirement::parse(input);
+            assert!(req.is_ok(), "Failed to parse requirement '{}': {:?}", input, req);
+            assert_eq!(req.unwrap().to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for input in INVALID_REQUIREMENTS {
+            assert!(Requirement::parse(input).is_err(), "Parsed requirement: '{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_markers() {
+        let req = Requirement::parse("pytest; extra == 'test'").unwrap();
+        let env = MarkerEnvironment::default();
+        assert!(!req.evaluate_markers(&env, &[]));
+        assert!(req.evaluate_markers(&env, &[PackageName::parse("Test").unwrap()]));
+        assert!(Requirement::parse("pytest").unwrap().evaluate_markers(&env, &[]));
+    }
+}
@@ -0,0 +1,161 @@
+//! Evaluation of `Requires-Python` metadata (e.g. `>=3.8, !=3.9.*`) against a
+//! catalogue of interpreter versions.
+//!
+//! Interpreters are matched the way pip matches them: only the
+//! `major.minor.micro` release of the interpreter is considered. This means
+//! that a `3.13.0rc1` interpreter is treated as `3.13.0`, so `>=3.13` allows
+//! it even though `3.13.0rc1 < 3.13` under PEP440 ordering.
+
+use crate::error::Error;
+use crate::specifier::SpecifierSet;
+use crate::Version;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+/// A parsed `Requires-Python` specifier set.
+pub struct RequiresPython {
+    pub specifiers: SpecifierSet,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The result of evaluating a `RequiresPython` against a catalogue of
+/// interpreter versions.
+pub struct PythonSupport {
+    /// Every `major.minor` with at least one allowed interpreter, ascending.
+    pub minors: Vec<Version>,
+    /// The lowest allowed interpreter from the catalogue.
+    pub lowest: Option<Version>,
+    /// The highest allowed interpreter from the catalogue.
+    pub highest: Option<Version>,
+}
+
+impl RequiresPython {
+    /// Attempt to parse the given `Requires-Python` string.
+    pub fn parse(input: &str) -> Option<RequiresPython> {
+        let specifiers = SpecifierSet::parse(input)?;
+        Some(RequiresPython { specifiers })
+    }
+
+    /// Returns `true` if an interpreter of the given version is allowed.
+    ///
+    /// The interpreter version is truncated to `major.minor.micro` first, so
+    /// that pre-release interpreters are treated like their final release.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::requires_python::RequiresPython;
+    /// let requires = RequiresPython::parse(">=3.8, !=3.9.*").unwrap();
+    /// assert!(requires.contains(&Version::parse("3.8").unwrap()));
+    /// assert!(!requires.contains(&Version::parse("3.9.18").unwrap()));
+    /// assert!(requires.contains(&Version::parse("3.13.0rc1").unwrap()));
+    /// ```
+    pub fn contains(&self, python: &Version) -> bool {
+        self.specifiers.contains_with(&python.truncate_release(3), true)
+    }
+
+    /// Evaluates the specifiers against every interpreter in the catalogue.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::requires_python::RequiresPython;
+    /// let catalogue: Vec<Version> = (6..=13)
+    ///     .map(|minor| Version::from_release(vec![3, minor]))
+    ///     .collect();
+    /// let requires = RequiresPython::parse(">=3.8, !=3.9.*, <3.13").unwrap();
+    /// let support = requires.evaluate(&catalogue);
+    /// let minors: Vec<String> = support.minors.iter().map(|v| v.to_string()).collect();
+    /// assert_eq!(minors, vec!["3.8", "3.10", "3.11", "3.12"]);
+    /// assert_eq!(support.lowest, Version::parse("3.8"));
+    /// assert_eq!(support.highest, Version::parse("3.12"));
+    /// ```
+    pub fn evaluate(&self, catalogue: &[Version]) -> PythonSupport {
+        let mut allowed: Vec<&Version> = catalogue
+            .iter()
+            .filter(|python| self.contains(python))
+            .collect();
+        allowed.sort();
+
+        let mut minors: Vec<Version> = allowed
+            .iter()
+            .map(|python| python.truncate_release(2))
+            .collect();
+        minors.dedup();
+
+        PythonSupport {
+            minors,
+            lowest: allowed.first().map(|python| (*python).clone()),
+            highest: allowed.last().map(|python| (*python).clone()),
+        }
+    }
+}
+
+impl fmt::Display for RequiresPython {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.specifiers)
+    }
+}
+
+impl FromStr for RequiresPython {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RequiresPython::parse(s).ok_or_else(|| Error::specifier_parse_error(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::requires_python::*;
+
+    fn versions(input: &[&str]) -> Vec<Version> {
+        input.iter().map(|v| Version::parse(v).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_contains() {
+        // (requires-python, interpreter, expected)
+        let cases: &[(&str, &str, bool)] = &[
+            (">=3.8", "3.8.0", true),
+            (">=3.8", "3.8", true),
+            (">=3.8.1", "3.8.0", false),
+            (">3.8", "3.8.0", false),
+            (">3.8", "3.8.1", true),
+            ("!=3.9.*", "3.9", false),
+            ("!=3.9.*", "3.9.0rc2", false),
+            ("!=3.9.*", "3.10", true),
+            ("==3.13.*", "3.13.0rc1", true),
+            (">=3.13", "3.13.0rc1", true),
+            ("<3.13", "3.13.0rc1", false),
+            ("~=3.8", "3.12.1", true),
+            ("~=3.8.0", "3.9", false),
+            ("", "2.7", true),
+        ];
+        for (requires, python, expected) in cases {
+            let parsed = RequiresPython::parse(requires).unwrap();
+            let python = Version::parse(python).unwrap();
+            assert_eq!(
+                parsed.contains(&python),
+                *expected,
+                "requires-python={}, python={}", requires, python);
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let catalogue = versions(&["3.13.0rc1", "3.7.17", "3.9.1", "3.9.0", "3.8.0"]);
+        let parsed = RequiresPython::parse(">=3.8.0").unwrap();
+        let support = parsed.evaluate(&catalogue);
+        assert_eq!(support.minors, versions(&["3.8", "3.9", "3.13"]));
+        assert_eq!(support.lowest, Version::parse("3.8"));
+        assert_eq!(support.highest, Version::parse("3.13.0rc1"));
+
+        let parsed = RequiresPython::parse(">=4").unwrap();
+        assert_eq!(parsed.evaluate(&catalogue), PythonSupport::default());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(RequiresPython::parse(">=3.8,>=3.*").is_none());
+        assert!(RequiresPython::from_str("3.8").is_err());
+    }
+}
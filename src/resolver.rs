@@ -0,0 +1,1354 @@
+//! Dependency resolution with the PubGrub algorithm, as described in
+//! <https://nex3.medium.com/pubgrub-2fb6470504f> and the documentation of the
+//! `pubgrub` crate.
+//!
+//! Versions and their requirements come from a [`DependencyProvider`]. The
+//! resolver reasons about sets of versions as [`Ranges`], learns from every
+//! conflict, and either returns one version per package or explains why no
+//! such set of versions exists.
+//!
+//! By default the highest possible versions are selected. The lowest versions
+//! can be selected instead, for everything or only for the direct
+//! requirements, to check that declared lower bounds actually work.
+//!
+//! A universal resolution is valid for every environment allowed by the
+//! project's `Requires-Python` instead of a single one. Markers which are
+//! only true in some environments don't split the environments: they are
+//! kept as the markers of the packages they lead to. Only once the
+//! requirements on a package diverge, e.g. `numpy>=2; python_version >= "3.10"` and
+//! `numpy<2; python_version < "3.10"`, are the environments split in two
+//! forks along the marker, and each fork resolved on its own.
+//!
+//! Extras are handled by treating `foo[bar]` as a package of its own, which
+//! depends on `foo` at the same version and on the requirements `foo` only has
+//! when `bar` is requested.
+
+use crate::candidate::PrereleasePolicy;
+use crate::error::Error;
+use crate::marker::{MarkerEnvironment, MarkerTree};
+use crate::marker_set::MarkerSet;
+use crate::name::PackageName;
+use crate::ranges::Ranges;
+use crate::requirement::{Requirement, VersionOrUrl};
+use crate::requires_python::RequiresPython;
+use crate::specifier::Operator;
+use crate::Version;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Bound;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The requirements of a version of a package.
+pub enum Dependencies {
+    /// The requirements, unfiltered: the resolver evaluates their markers.
+    Known(Vec<Requirement>),
+    /// The version can't be used, e.g. because its metadata couldn't be
+    /// fetched; the string says why.
+    Unavailable(String),
+}
+
+/// Supplies the versions of packages and their requirements.
+pub trait DependencyProvider {
+    /// Returns the available versions of a package, in any order. Unknown
+    /// packages have no versions.
+    fn versions(&self, package: &PackageName) -> Result<Vec<Version>, Error>;
+
+    /// Returns the requirements of one of the versions returned by
+    /// [`versions`](DependencyProvider::versions).
+    fn dependencies(&self, package: &PackageName, version: &Version) -> Result<Dependencies, Error>;
+}
+
+#[derive(Clone, Debug, Default)]
+/// A [`DependencyProvider`] backed by a map, for tests and offline use.
+pub struct InMemoryProvider {
+    packages: BTreeMap<PackageName, BTreeMap<Version, Dependencies>>,
+}
+
+impl InMemoryProvider {
+    pub fn new() -> InMemoryProvider {
+        InMemoryProvider::default()
+    }
+
+    /// Adds a version of a package with the given PEP508 requirements.
+    ///
+    /// ```
+    /// # use pep440::resolver::InMemoryProvider;
+    /// let mut provider = InMemoryProvider::new();
+    /// provider.add("foo", "1.0", &["bar>=2; python_version >= '3.8'"]).unwrap();
+    /// assert!(provider.add("foo", "one", &[]).is_err());
+    /// ```
+    pub fn add(&mut self, package: &str, version: &str, requirements: &[&str]) -> Result<(), Error> {
+        let requirements = requirements
+            .iter()
+            .map(|requirement| requirement.parse())
+            .collect::<Result<Vec<Requirement>, Error>>()?;
+        self.insert(package.parse()?, version.parse()?, Dependencies::Known(requirements));
+        Ok(())
+    }
+
+    /// Adds a version of a package whose requirements are unavailable.
+    pub fn add_unavailable(&mut self, package: &str, version: &str, reason: &str) -> Result<(), Error> {
+        self.insert(package.parse()?, version.parse()?, Dependencies::Unavailable(reason.to_string()));
+        Ok(())
+    }
+
+    fn insert(&mut self, package: PackageName, version: Version, dependencies: Dependencies) {
+        self.packages.entry(package).or_default().insert(version, dependencies);
+    }
+}
+
+impl DependencyProvider for InMemoryProvider {
+    fn versions(&self, package: &PackageName) -> Result<Vec<Version>, Error> {
+        Ok(self.packages.get(package).map(|versions| versions.keys().cloned().collect()).unwrap_or_default())
+    }
+
+    fn dependencies(&self, package: &PackageName, version: &Version) -> Result<Dependencies, Error> {
+        Ok(self
+            .packages
+            .get(package)
+            .and_then(|versions| versions.get(version))
+            .cloned()
+            .unwrap_or_else(|| Dependencies::Unavailable("unknown version".to_string())))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// The settings of a resolution.
+pub struct Resolver {
+    /// The environment the markers of requirements are evaluated in. It is
+    /// not used by [`Resolver::resolve_universal`].
+    pub environment: MarkerEnvironment,
+    /// Pre-releases are also selected if any requirement on the package
+    /// explicitly asks for one.
+    pub prereleases: PrereleasePolicy,
+    pub mode: ResolutionMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+/// Which versions the resolver prefers.
+pub enum ResolutionMode {
+    /// The highest version of every package.
+    #[default]
+    Highest,
+    /// The lowest version of every package.
+    Lowest,
+    /// The lowest version of the packages named by the requirements passed to
+    /// [`Resolver::resolve`], and the highest version of the others.
+    LowestDirect,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The result of a successful resolution.
+pub struct Resolution {
+    /// One version per package, including packages only needed indirectly.
+    pub packages: BTreeMap<PackageName, Version>,
+    /// In a universal resolution, the environments each package is needed
+    /// in: the union of the markers along every path of requirements which
+    /// leads to it, within the environments of the fork. Empty otherwise.
+    pub markers: BTreeMap<PackageName, MarkerSet>,
+    /// In the lowest modes, the direct requirements whose lower bound could
+    /// not be resolved to.
+    pub lower_bounds: Vec<LowerBound>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A direct requirement whose lower bound is not honest: either no version
+/// at the bound exists, or it can't be used together with the other
+/// requirements.
+pub struct LowerBound {
+    pub requirement: Requirement,
+    /// The lowest available version matching the requirement, if any.
+    pub lowest_available: Option<Version>,
+    /// The version the package was resolved to.
+    pub resolved: Version,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The result of a successful universal resolution.
+pub struct UniversalResolution {
+    /// The forks, whose markers are disjoint and together cover every
+    /// environment allowed by `Requires-Python`.
+    pub forks: Vec<Fork>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The resolution for part of the environments.
+pub struct Fork {
+    /// The environments the versions apply to, or `None` for every
+    /// environment allowed by `Requires-Python`.
+    pub marker: Option<MarkerTree>,
+    pub resolution: Resolution,
+}
+
+impl fmt::Display for LowerBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.lowest_available {
+            Some(lowest) if *lowest == self.resolved => write!(
+                f, "{}: the lowest available version is {}", self.requirement, lowest),
+            Some(lowest) => write!(
+                f, "{}: the lowest available version is {}, but it resolved to {}",
+                self.requirement, lowest, self.resolved),
+            None => write!(f, "{}: resolved to {}", self.requirement, self.resolved),
+        }
+    }
+}
+
+impl Resolver {
+    /// Resolves `requirements` using the versions offered by `provider`.
+    ///
+    /// If there is no solution, the error is a
+    /// [`NoSolution`](Error::NoSolution) whose text derives the conflict step
+    /// by step from the requirements.
+    ///
+    /// ```
+    /// # use pep440::requirement::Requirement;
+    /// # use pep440::resolver::{InMemoryProvider, Resolver};
+    /// let mut provider = InMemoryProvider::new();
+    /// provider.add("app", "1.0", &["lib>=1.0"]).unwrap();
+    /// provider.add("app", "2.0", &["lib>=2.0"]).unwrap();
+    /// provider.add("lib", "1.5", &[]).unwrap();
+    ///
+    /// let requirements = vec![Requirement::parse("app").unwrap()];
+    /// let resolution = Resolver::default().resolve(&requirements, &provider).unwrap();
+    /// let pins: Vec<String> = resolution.packages.iter().map(|(name, version)| format!("{}=={}", name, version)).collect();
+    /// assert_eq!(pins, vec!["app==1.0", "lib==1.5"]);
+    /// ```
+    pub fn resolve<P: DependencyProvider>(&self, requirements: &[Requirement], provider: &P) -> Result<Resolution, Error> {
+        match State::new(self, requirements, provider, None).solve()? {
+            Outcome::Resolved(resolution) => Ok(resolution),
+            Outcome::Split(_) => unreachable!("only universal resolutions are split"),
+        }
+    }
+
+    /// Resolves `requirements` for every environment allowed by
+    /// `requires_python` at once, splitting the environments into forks
+    /// where the requirements diverge.
+    ///
+    /// Forks which end up with the same versions are merged again. A fork
+    /// without a solution fails the whole resolution. The markers of the
+    /// packages say which environments of the fork need them.
+    ///
+    /// ```
+    /// # use pep440::requirement::Requirement;
+    /// # use pep440::requires_python::RequiresPython;
+    /// # use pep440::resolver::{InMemoryProvider, Resolver};
+    /// let mut provider = InMemoryProvider::new();
+    /// provider.add("numpy", "1.26.4", &[]).unwrap();
+    /// provider.add("numpy", "2.2.0", &[]).unwrap();
+    ///
+    /// let requirements: Vec<Requirement> = [
+    ///     "numpy>=2; python_version >= '3.10'",
+    ///     "numpy<2; python_version < '3.10'",
+    ///     "pywin32; python_version < '3.9'",
+    /// ].iter().map(|requirement| requirement.parse().unwrap()).collect();
+    /// let requires_python = RequiresPython::parse(">=3.9").unwrap();
+    /// let resolution = Resolver::default().resolve_universal(&requirements, &requires_python, &provider).unwrap();
+    /// let forks: Vec<String> = resolution
+    ///     .forks
+    ///     .iter()
+    ///     .map(|fork| format!("{}: numpy=={}", fork.marker.as_ref().unwrap(), fork.resolution.packages.values().next().unwrap()))
+    ///     .collect();
+    /// assert_eq!(forks, vec![
+    ///     "python_version >= \"3.10\": numpy==2.2.0",
+    ///     "python_version < \"3.10\": numpy==1.26.4",
+    /// ]);
+    /// ```
+    pub fn resolve_universal<P: DependencyProvider>(
+        &self,
+        requirements: &[Requirement],
+        requires_python: &RequiresPython,
+        provider: &P,
+    ) -> Result<UniversalResolution, Error> {
+        let python = MarkerSet::from_requires_python(requires_python);
+        let mut forks: Vec<(MarkerSet, Resolution)> = vec![];
+        let mut pending = vec![MarkerSet::full()];
+        while let Some(fork) = pending.pop() {
+            let environments = fork.intersection(&python);
+            let state = State { fork: fork.clone(), ..State::new(self, requirements, provider, Some(environments)) };
+            match state.solve() {
+                Ok(Outcome::Resolved(resolution)) => {
+                    let existing = forks.iter_mut().find(|(_, existing)| {
+                        existing.packages == resolution.packages && existing.lower_bounds == resolution.lower_bounds
+                    });
+                    match existing {
+                        Some((marker, existing)) => {
+                            *marker = marker.union(&fork);
+                            for (name, marker) in resolution.markers {
+                                let merged = match existing.markers.remove(&name) {
+                                    Some(existing) => existing.union(&marker),
+                                    None => marker,
+                                };
+                                existing.markers.insert(name, merged);
+                            }
+                        }
+                        None => forks.push((fork, resolution)),
+                    }
+                }
+                Ok(Outcome::Split(marker)) => {
+                    // Forks outside of `Requires-Python` are pruned.
+                    for part in [fork.intersection(&marker.complement()), fork.intersection(&marker)] {
+                        if !part.is_disjoint(&python) {
+                            pending.push(part);
+                        }
+                    }
+                }
+                Err(Error::NoSolution(explanation)) if !fork.is_full() => {
+                    return Err(Error::no_solution(format!("In environments where {}:\n{}", fork, explanation)));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let forks = forks
+            .into_iter()
+            .map(|(marker, resolution)| Fork { marker: marker.to_marker(), resolution })
+            .collect();
+        Ok(UniversalResolution { forks })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A node of the resolution: the root requirements, a package, or a package
+/// with one of its extras.
+enum Package {
+    Root,
+    Name(PackageName),
+    Extra(PackageName, PackageName),
+}
+
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Package::Root => write!(f, "root"),
+            Package::Name(name) => write!(f, "{}", name),
+            Package::Extra(name, extra) => write!(f, "{}[{}]", name, extra),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A statement about the version of a package: a positive term says that a
+/// version in the set is selected, a negative one that no version in the set
+/// is selected (which is also true if the package isn't selected at all).
+enum Term {
+    Positive(Ranges),
+    Negative(Ranges),
+}
+
+impl Term {
+    /// The term which is always true.
+    fn any() -> Term {
+        Term::Negative(Ranges::empty())
+    }
+
+    /// The term which is never true.
+    fn empty() -> Term {
+        Term::Positive(Ranges::empty())
+    }
+
+    fn negate(&self) -> Term {
+        match self {
+            Term::Positive(ranges) => Term::Negative(ranges.clone()),
+            Term::Negative(ranges) => Term::Positive(ranges.clone()),
+        }
+    }
+
+    fn intersection(&self, other: &Term) -> Term {
+        match (self, other) {
+            (Term::Positive(a), Term::Positive(b)) => Term::Positive(a.intersection(b)),
+            (Term::Positive(a), Term::Negative(b)) | (Term::Negative(b), Term::Positive(a)) => {
+                Term::Positive(a.intersection(&b.complement()))
+            }
+            (Term::Negative(a), Term::Negative(b)) => Term::Negative(a.union(b)),
+        }
+    }
+
+    fn union(&self, other: &Term) -> Term {
+        self.negate().intersection(&other.negate()).negate()
+    }
+
+    /// Returns `true` if this term holds whenever `other` does.
+    fn satisfied_by(&self, other: &Term) -> bool {
+        self.intersection(other) == *other
+    }
+
+    /// Returns `true` if this term can't hold when `other` does.
+    fn contradicted_by(&self, other: &Term) -> bool {
+        self.intersection(other) == Term::empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Cause {
+    /// The root package has to be selected.
+    Root,
+    /// No version of the package is in the range.
+    NoVersions,
+    /// The version can't be used, for the given reason.
+    Unavailable(String),
+    /// A version of the first package depends on the second package.
+    Dependency,
+    /// Derived from two other incompatibilities during conflict resolution.
+    Derived(usize, usize),
+}
+
+#[derive(Clone, Debug)]
+/// A set of terms which must not all be true at the same time.
+struct Incompatibility {
+    terms: BTreeMap<Package, Term>,
+    cause: Cause,
+}
+
+#[derive(Clone, Debug)]
+struct Assignment {
+    package: Package,
+    term: Term,
+    decision_level: usize,
+    /// The incompatibility the assignment was derived from, or `None` for a
+    /// decision.
+    cause: Option<usize>,
+}
+
+enum Outcome {
+    Resolved(Resolution),
+    /// The requirements diverge along the marker, so the environments have
+    /// to be split.
+    Split(MarkerSet),
+}
+
+enum Relation {
+    Satisfied,
+    Contradicted,
+    /// Every term is satisfied except for the term of the given package,
+    /// which is inconclusive.
+    AlmostSatisfied(Package),
+    Inconclusive,
+}
+
+struct State<'a, P> {
+    resolver: &'a Resolver,
+    requirements: &'a [Requirement],
+    provider: &'a P,
+    incompatibilities: Vec<Incompatibility>,
+    /// The incompatibilities used for propagation, by package.
+    by_package: HashMap<Package, Vec<usize>>,
+    assignments: Vec<Assignment>,
+    decision_level: usize,
+    /// Packages which a requirement explicitly asked a pre-release of, with
+    /// the decision level of the version the requirement belongs to, so that
+    /// the request is forgotten when that version is backtracked.
+    explicit_prereleases: BTreeMap<PackageName, usize>,
+    versions: RefCell<HashMap<PackageName, Vec<Version>>>,
+    /// For a universal resolution, the environments of the fork.
+    environments: Option<MarkerSet>,
+    /// The marker of the fork, without `Requires-Python`, which the markers
+    /// of the resolved packages start from.
+    fork: MarkerSet,
+    /// For a universal resolution, the dependencies of every version whose
+    /// dependencies were added, with the markers of the requirements.
+    edges: HashMap<(Package, Version), Vec<(Package, MarkerSet)>>,
+    split: Option<MarkerSet>,
+}
+
+impl<'a, P: DependencyProvider> State<'a, P> {
+    fn new(
+        resolver: &'a Resolver,
+        requirements: &'a [Requirement],
+        provider: &'a P,
+        environments: Option<MarkerSet>,
+    ) -> State<'a, P> {
+        State {
+            resolver,
+            requirements,
+            provider,
+            incompatibilities: vec![],
+            by_package: HashMap::new(),
+            assignments: vec![],
+            decision_level: 0,
+            explicit_prereleases: BTreeMap::new(),
+            versions: RefCell::new(HashMap::new()),
+            environments,
+            fork: MarkerSet::full(),
+            edges: HashMap::new(),
+            split: None,
+        }
+    }
+
+    fn root_version() -> Version {
+        Version::from_release(vec![0])
+    }
+
+    fn solve(mut self) -> Result<Outcome, Error> {
+        let root = Term::Negative(Ranges::singleton(State::<P>::root_version()));
+        self.add_incompatibility(vec![(Package::Root, root)], Cause::Root);
+
+        let mut next = Package::Root;
+        loop {
+            if let Err(root_cause) = self.propagate(next) {
+                return Err(Error::no_solution(self.explain(root_cause)));
+            }
+            match self.decide()? {
+                Some(package) => next = package,
+                None => break,
+            }
+        }
+        if let Some(split) = self.split.take() {
+            return Ok(Outcome::Split(split));
+        }
+
+        let mut decided = BTreeMap::new();
+        for assignment in &self.assignments {
+            if let (None, Term::Positive(ranges)) = (assignment.cause, &assignment.term) {
+                decided.insert(assignment.package.clone(), ranges.as_singleton().unwrap().clone());
+            }
+        }
+        let packages: BTreeMap<PackageName, Version> = decided
+            .iter()
+            .filter_map(|(package, version)| match package {
+                Package::Name(name) => Some((name.clone(), version.clone())),
+                _ => None,
+            })
+            .collect();
+        let markers = match self.environments {
+            Some(_) => self.markers(&decided),
+            None => BTreeMap::new(),
+        };
+        let lower_bounds = match self.resolver.mode {
+            ResolutionMode::Highest => vec![],
+            ResolutionMode::Lowest | ResolutionMode::LowestDirect => self.lower_bounds(&packages)?,
+        };
+        Ok(Outcome::Resolved(Resolution { packages, markers, lower_bounds }))
+    }
+
+    /// Propagates the marker of the fork along the requirements of the
+    /// decided versions, until every package has the union of the markers
+    /// of the paths which lead to it.
+    fn markers(&self, decided: &BTreeMap<Package, Version>) -> BTreeMap<PackageName, MarkerSet> {
+        let mut markers: HashMap<&Package, MarkerSet> = HashMap::new();
+        markers.insert(&Package::Root, self.fork.clone());
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (package, version) in decided {
+                let from = match markers.get(package) {
+                    Some(from) => from.clone(),
+                    None => continue,
+                };
+                for (dependency, marker) in self.edges.get(&(package.clone(), version.clone())).into_iter().flatten() {
+                    let reached = from.intersection(marker);
+                    let existing = markers.entry(dependency).or_insert_with(MarkerSet::empty);
+                    if !reached.subset_of(existing) {
+                        *existing = existing.union(&reached);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        // A package needed in every environment of the fork which
+        // `Requires-Python` allows is needed wherever the fork applies.
+        let environments = self.environments.as_ref().unwrap_or(&self.fork);
+        markers
+            .into_iter()
+            .filter_map(|(package, marker)| match package {
+                Package::Name(name) if environments.subset_of(&marker) => Some((name.clone(), self.fork.clone())),
+                Package::Name(name) => Some((name.clone(), marker)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks that each direct requirement with a lower bound resolved to the
+    /// version at that bound.
+    fn lower_bounds(&self, packages: &BTreeMap<PackageName, Version>) -> Result<Vec<LowerBound>, Error> {
+        let mut lower_bounds = vec![];
+        for requirement in self.requirements.iter().filter(|requirement| self.applies(requirement, &[])) {
+            let specifiers = match &requirement.version_or_url {
+                Some(VersionOrUrl::Specifiers(specifiers)) => specifiers,
+                _ => continue,
+            };
+            let resolved = match packages.get(&requirement.name) {
+                Some(resolved) => resolved,
+                None => continue,
+            };
+            // The highest bound wins; `>V` is higher than `>=V`.
+            let bound = specifiers
+                .specifiers
+                .iter()
+                .filter_map(|specifier| match specifier.operator {
+                    Operator::GreaterThan => Some((&specifier.version, Bound::Excluded(()))),
+                    Operator::GreaterThanEqual | Operator::Compatible | Operator::Equal | Operator::ArbitraryEqual => {
+                        Some((&specifier.version, Bound::Included(())))
+                    }
+                    _ => None,
+                })
+                .max_by(|(a, a_bound), (b, b_bound)| {
+                    a.cmp(b).then_with(|| matches!(a_bound, Bound::Excluded(_)).cmp(&matches!(b_bound, Bound::Excluded(_))))
+                });
+            let ranges = Ranges::from_specifiers(specifiers);
+            let lowest_available = self.choose_version(&Package::Name(requirement.name.clone()), &ranges)?;
+            let honest = match bound {
+                None => continue,
+                Some((version, Bound::Included(_))) => resolved.public() == *version,
+                // An exclusive bound has no version of its own; the first
+                // version above it has to be usable.
+                Some(_) => lowest_available.as_ref() == Some(resolved),
+            };
+            if !honest {
+                lower_bounds.push(LowerBound {
+                    requirement: requirement.clone(),
+                    lowest_available,
+                    resolved: resolved.clone(),
+                });
+            }
+        }
+        Ok(lower_bounds)
+    }
+
+    fn add_incompatibility(&mut self, terms: Vec<(Package, Term)>, cause: Cause) -> usize {
+        let id = self.incompatibilities.len();
+        self.incompatibilities.push(Incompatibility { terms: terms.into_iter().collect(), cause });
+        self.register(id);
+        id
+    }
+
+    fn register(&mut self, id: usize) {
+        for package in self.incompatibilities[id].terms.keys() {
+            self.by_package.entry(package.clone()).or_default().push(id);
+        }
+    }
+
+    /// The intersection of the assignments of a package so far.
+    fn term(&self, package: &Package) -> Term {
+        self.assignments
+            .iter()
+            .filter(|assignment| assignment.package == *package)
+            .fold(Term::any(), |term, assignment| term.intersection(&assignment.term))
+    }
+
+    fn is_decided(&self, package: &Package) -> bool {
+        self.assignments.iter().any(|assignment| assignment.package == *package && assignment.cause.is_none())
+    }
+
+    fn relation(&self, id: usize) -> Relation {
+        let mut unsatisfied = vec![];
+        for (package, term) in &self.incompatibilities[id].terms {
+            let assigned = self.term(package);
+            if term.contradicted_by(&assigned) {
+                return Relation::Contradicted;
+            }
+            if !term.satisfied_by(&assigned) {
+                unsatisfied.push(package);
+            }
+        }
+        match unsatisfied.as_slice() {
+            [] => Relation::Satisfied,
+            [package] => Relation::AlmostSatisfied((*package).clone()),
+            _ => Relation::Inconclusive,
+        }
+    }
+
+    fn derive(&mut self, package: Package, term: Term, cause: usize) {
+        let decision_level = self.decision_level;
+        self.assignments.push(Assignment { package, term, decision_level, cause: Some(cause) });
+    }
+
+    /// Unit propagation: derives every assignment which follows from the
+    /// incompatibilities, starting with those mentioning `package`. Returns
+    /// the terminal incompatibility if a conflict can't be resolved.
+    fn propagate(&mut self, package: Package) -> Result<(), usize> {
+        let mut changed = vec![package];
+        while let Some(package) = changed.pop() {
+            let ids = self.by_package.get(&package).cloned().unwrap_or_default();
+            // Newer incompatibilities are more specific, so try them first.
+            for id in ids.into_iter().rev() {
+                match self.relation(id) {
+                    Relation::Satisfied => {
+                        let root_cause = self.resolve_conflict(id)?;
+                        let package = match self.relation(root_cause) {
+                            Relation::AlmostSatisfied(package) => package,
+                            _ => unreachable!("a learned incompatibility is almost satisfied after backtracking"),
+                        };
+                        let term = self.incompatibilities[root_cause].terms[&package].negate();
+                        self.derive(package.clone(), term, root_cause);
+                        changed = vec![package];
+                        break;
+                    }
+                    Relation::AlmostSatisfied(package) => {
+                        let term = self.incompatibilities[id].terms[&package].negate();
+                        self.derive(package.clone(), term, id);
+                        if !changed.contains(&package) {
+                            changed.push(package);
+                        }
+                    }
+                    Relation::Contradicted | Relation::Inconclusive => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_terminal(&self, id: usize) -> bool {
+        let terms = &self.incompatibilities[id].terms;
+        terms.is_empty()
+            || (terms.len() == 1 && matches!(terms.get(&Package::Root), Some(Term::Positive(_))))
+    }
+
+    /// Learns from a satisfied incompatibility, backtracks, and returns the
+    /// learned incompatibility, or returns the terminal incompatibility if
+    /// the conflict can't be resolved.
+    fn resolve_conflict(&mut self, mut id: usize) -> Result<usize, usize> {
+        let mut learned = false;
+        loop {
+            if self.is_terminal(id) {
+                return Err(id);
+            }
+            let (satisfier, package, previous_level) = self.satisfier(id);
+            let Assignment { decision_level, cause, .. } = self.assignments[satisfier].clone();
+            let cause = match cause {
+                Some(cause) if previous_level >= decision_level => cause,
+                _ => {
+                    self.assignments.retain(|assignment| assignment.decision_level <= previous_level);
+                    self.explicit_prereleases.retain(|_, level| *level <= previous_level);
+                    self.decision_level = previous_level;
+                    if learned {
+                        self.register(id);
+                    }
+                    return Ok(id);
+                }
+            };
+
+            // Resolve the incompatibility with the cause of its satisfier.
+            let mut terms = self.incompatibilities[id].terms.clone();
+            let mut other = self.incompatibilities[cause].terms.clone();
+            let pivot = terms.remove(&package).unwrap().union(&other.remove(&package).unwrap());
+            for (other_package, term) in other {
+                let term = match terms.remove(&other_package) {
+                    Some(existing) => existing.intersection(&term),
+                    None => term,
+                };
+                terms.insert(other_package, term);
+            }
+            terms.insert(package, pivot);
+            terms.retain(|_, term| *term != Term::any());
+            self.incompatibilities.push(Incompatibility { terms, cause: Cause::Derived(id, cause) });
+            id = self.incompatibilities.len() - 1;
+            learned = true;
+        }
+    }
+
+    /// Finds the earliest assignment which, together with the ones before it,
+    /// satisfies the incompatibility. Returns its index, the package of the
+    /// term it satisfies, and the decision level of the previous satisfier.
+    fn satisfier(&self, id: usize) -> (usize, Package, usize) {
+        let terms = &self.incompatibilities[id].terms;
+        let mut satisfiers = vec![];
+        // A dependency on an empty range gives a term which always holds.
+        for (package, term) in terms.iter().filter(|(_, term)| **term != Term::any()) {
+            let mut assigned = Term::any();
+            for (index, assignment) in self.assignments.iter().enumerate() {
+                if assignment.package == *package {
+                    assigned = assigned.intersection(&assignment.term);
+                    if term.satisfied_by(&assigned) {
+                        satisfiers.push((index, package));
+                        break;
+                    }
+                }
+            }
+        }
+        let (satisfier, package) = *satisfiers.iter().max().unwrap();
+
+        // The satisfier's term may be satisfied by an earlier assignment
+        // together with the satisfier.
+        let term = &terms[package];
+        let mut assigned = self.assignments[satisfier].term.clone();
+        let mut previous = satisfiers.iter().filter(|(index, _)| *index != satisfier).map(|(index, _)| *index).max();
+        if !term.satisfied_by(&assigned) {
+            for (index, assignment) in self.assignments[..satisfier].iter().enumerate() {
+                if assignment.package == *package {
+                    assigned = assigned.intersection(&assignment.term);
+                    if term.satisfied_by(&assigned) {
+                        previous = previous.max(Some(index));
+                        break;
+                    }
+                }
+            }
+        }
+        let previous_level = previous.map_or(1, |index| self.assignments[index].decision_level);
+        (satisfier, package.clone(), previous_level)
+    }
+
+    /// Decision making: picks a version for the next undecided package and
+    /// adds its dependencies. Returns `None` once every package is decided.
+    fn decide(&mut self) -> Result<Option<Package>, Error> {
+        let mut undecided = None;
+        for assignment in &self.assignments {
+            if let Term::Positive(ranges) = self.term(&assignment.package) {
+                if !self.is_decided(&assignment.package) {
+                    undecided = Some((assignment.package.clone(), ranges));
+                    break;
+                }
+            }
+        }
+        let (package, ranges) = match undecided {
+            Some(undecided) => undecided,
+            None => return Ok(None),
+        };
+
+        // Forget the pre-releases asked for by a version which was tried but
+        // not decided.
+        let decision_level = self.decision_level;
+        self.explicit_prereleases.retain(|_, level| *level <= decision_level);
+
+        let version = match self.choose_version(&package, &ranges)? {
+            Some(version) => version,
+            None => {
+                self.add_incompatibility(vec![(package.clone(), Term::Positive(ranges))], Cause::NoVersions);
+                return Ok(Some(package));
+            }
+        };
+
+        let decided = Term::Positive(Ranges::singleton(version.clone()));
+        let dependencies = match self.dependencies(&package, &version)? {
+            Ok(dependencies) => dependencies,
+            Err(reason) => {
+                self.add_incompatibility(vec![(package.clone(), decided)], Cause::Unavailable(reason));
+                return Ok(Some(package));
+            }
+        };
+        if self.split.is_some() {
+            return Ok(None);
+        }
+
+        let mut conflict = false;
+        for (dependency, ranges) in dependencies {
+            let term = Term::Negative(ranges);
+            conflict |= term.satisfied_by(&self.term(&dependency));
+            self.add_incompatibility(vec![(package.clone(), decided.clone()), (dependency, term)], Cause::Dependency);
+        }
+        // If a dependency is already ruled out, propagation derives that this
+        // version can't be used instead.
+        if !conflict {
+            self.decision_level += 1;
+            let decision_level = self.decision_level;
+            self.assignments.push(Assignment { package: package.clone(), term: decided, decision_level, cause: None });
+        }
+        Ok(Some(package))
+    }
+
+    fn choose_version(&self, package: &Package, ranges: &Ranges) -> Result<Option<Version>, Error> {
+        let name = match package {
+            Package::Root => return Ok(Some(State::<P>::root_version())),
+            Package::Name(name) | Package::Extra(name, _) => name,
+        };
+        let mut versions = self.versions.borrow_mut();
+        if !versions.contains_key(name) {
+            let mut available = self.provider.versions(name)?;
+            available.sort();
+            versions.insert(name.clone(), available);
+        }
+        let lowest = match self.resolver.mode {
+            ResolutionMode::Highest => false,
+            ResolutionMode::Lowest => true,
+            ResolutionMode::LowestDirect => self.requirements.iter().any(|requirement| requirement.name == *name),
+        };
+        let mut matching: Vec<&Version> = versions[name].iter().filter(|version| ranges.contains(version)).collect();
+        if !lowest {
+            matching.reverse();
+        }
+
+        let allow_prereleases = match self.resolver.prereleases {
+            PrereleasePolicy::Allow => true,
+            _ if self.explicit_prereleases.contains_key(name) => true,
+            PrereleasePolicy::Disallow => false,
+            PrereleasePolicy::IfNecessary => matching.iter().all(|version| version.is_prerelease()),
+        };
+        Ok(matching
+            .into_iter()
+            .find(|version| allow_prereleases || !version.is_prerelease())
+            .cloned())
+    }
+
+    /// Returns the dependencies of a version with their merged ranges, or
+    /// the reason the version is unavailable.
+    #[allow(clippy::type_complexity)]
+    fn dependencies(&mut self, package: &Package, version: &Version) -> Result<Result<BTreeMap<Package, Ranges>, String>, Error> {
+        let (requirements, extras) = match package {
+            Package::Root => (self.requirements.to_vec(), vec![]),
+            Package::Name(name) | Package::Extra(name, _) => match self.provider.dependencies(name, version)? {
+                Dependencies::Known(requirements) => {
+                    let extras = match package {
+                        Package::Extra(_, extra) => vec![extra.clone()],
+                        _ => vec![],
+                    };
+                    (requirements, extras)
+                }
+                Dependencies::Unavailable(reason) => return Ok(Err(reason)),
+            },
+        };
+
+        let mut dependencies: BTreeMap<Package, Ranges> = BTreeMap::new();
+        let mut edges = vec![];
+        if let Package::Extra(name, _) = package {
+            dependencies.insert(Package::Name(name.clone()), Ranges::singleton(version.clone()));
+            edges.push((Package::Name(name.clone()), MarkerSet::full()));
+        }
+        let requirements: Vec<Requirement> = requirements
+            .into_iter()
+            .filter(|requirement| self.applies(requirement, &extras))
+            .collect();
+        if let Some(split) = self.divergence(&requirements, &extras) {
+            self.split = Some(split);
+            return Ok(Ok(dependencies));
+        }
+        for requirement in requirements {
+            let ranges = match &requirement.version_or_url {
+                None => Ranges::full(),
+                Some(VersionOrUrl::Specifiers(specifiers)) => {
+                    if specifiers.prereleases() {
+                        // The level the version gets once it is decided.
+                        let decision_level = self.decision_level + 1;
+                        self.explicit_prereleases.entry(requirement.name.clone()).or_insert(decision_level);
+                    }
+                    Ranges::from_specifiers(specifiers)
+                }
+                Some(VersionOrUrl::Url(url)) => {
+                    return Ok(Err(format!("depends on {} by URL ({}), which is not supported", requirement.name, url)));
+                }
+            };
+            let mut packages = vec![Package::Name(requirement.name.clone())];
+            packages.extend(requirement.extras.iter().map(|extra| Package::Extra(requirement.name.clone(), extra.clone())));
+            let marker = match &requirement.marker {
+                Some(marker) if self.environments.is_some() => MarkerSet::from_marker(marker, &extras),
+                _ => MarkerSet::full(),
+            };
+            edges.extend(packages.iter().map(|dependency| (dependency.clone(), marker.clone())));
+            for dependency in packages {
+                let merged = match dependencies.remove(&dependency) {
+                    Some(existing) => existing.intersection(&ranges),
+                    None => ranges.clone(),
+                };
+                dependencies.insert(dependency, merged);
+            }
+        }
+
+        // A package depending on itself is fine if it accepts this version.
+        if let Some(ranges) = dependencies.remove(package) {
+            if !ranges.contains(version) {
+                return Ok(Err(format!("depends on itself with {}", ranges)));
+            }
+        }
+        if self.environments.is_some() {
+            self.edges.insert((package.clone(), version.clone()), edges);
+        }
+        Ok(Ok(dependencies))
+    }
+
+    /// Returns `false` if the markers of the requirement rule it out: in the
+    /// environment, or in every environment of the fork.
+    fn applies(&self, requirement: &Requirement, extras: &[PackageName]) -> bool {
+        match (&self.environments, &requirement.marker) {
+            (None, _) => requirement.evaluate_markers(&self.resolver.environment, extras),
+            (Some(environments), Some(marker)) => !MarkerSet::from_marker(marker, extras).is_disjoint(environments),
+            (Some(_), None) => true,
+        }
+    }
+
+    /// In a universal resolution, returns the marker to split the fork along
+    /// if the requirements on a package differ, and at least one of them only
+    /// applies to some of the environments of the fork.
+    fn divergence(&self, requirements: &[Requirement], extras: &[PackageName]) -> Option<MarkerSet> {
+        let environments = self.environments.as_ref()?;
+        for requirement in requirements {
+            let marker = match &requirement.marker {
+                Some(marker) => MarkerSet::from_marker(marker, extras),
+                None => continue,
+            };
+            if environments.subset_of(&marker) {
+                continue;
+            }
+            let diverges = requirements.iter().any(|other| {
+                other.name == requirement.name && other.version_or_url != requirement.version_or_url
+            });
+            if diverges {
+                return Some(marker);
+            }
+        }
+        None
+    }
+
+    /// Writes out the derivation of a terminal incompatibility, one numbered
+    /// line per derived incompatibility.
+    fn explain(&self, root_cause: usize) -> String {
+        let mut lines = vec![];
+        let mut numbers = HashMap::new();
+        self.explain_derived(root_cause, &mut lines, &mut numbers);
+        lines.join("\n")
+    }
+
+    fn explain_derived(&self, id: usize, lines: &mut Vec<String>, numbers: &mut HashMap<usize, usize>) -> usize {
+        if let Some(number) = numbers.get(&id) {
+            return *number;
+        }
+        let (left, right) = match self.incompatibilities[id].cause {
+            Cause::Derived(left, right) => (left, right),
+            _ => unreachable!("only derived incompatibilities are explained"),
+        };
+        let mut reference = |cause: usize, lines: &mut Vec<String>| match self.incompatibilities[cause].cause {
+            Cause::Derived(..) => {
+                let number = self.explain_derived(cause, lines, numbers);
+                format!("{} ({})", self.describe(cause), number)
+            }
+            _ => self.describe(cause),
+        };
+        let left = reference(left, lines);
+        let right = reference(right, lines);
+        lines.push(format!("({}) Because {} and {}, {}.", lines.len() + 1, left, right, self.describe(id)));
+        numbers.insert(id, lines.len());
+        lines.len()
+    }
+
+    fn describe(&self, id: usize) -> String {
+        let incompatibility = &self.incompatibilities[id];
+        let mut terms: Vec<(&Package, &Term)> = incompatibility.terms.iter().collect();
+        // The root is always selected, so it goes without saying.
+        if terms.len() > 1 && terms.iter().all(|(_, term)| matches!(term, Term::Positive(_))) {
+            terms.retain(|(package, _)| **package != Package::Root);
+        }
+        match (&incompatibility.cause, terms.as_slice()) {
+            (Cause::Root, _) => "root is required".to_string(),
+            (Cause::NoVersions, [(package, Term::Positive(ranges))]) if *ranges == Ranges::full() => {
+                format!("there is no version of {}", package)
+            }
+            (Cause::NoVersions, [(package, Term::Positive(ranges))]) => {
+                format!("there is no version of {} in {}", package, ranges)
+            }
+            (Cause::Unavailable(reason), [(package, term)]) => {
+                format!("{} is unavailable: {}", describe_term(package, term), reason)
+            }
+            _ => match terms.as_slice() {
+                [] => "version solving failed".to_string(),
+                [(Package::Root, Term::Positive(_))] => "version solving failed".to_string(),
+                [(package, Term::Positive(ranges))] => format!("{} is forbidden", describe_ranges(package, ranges)),
+                [(package, Term::Negative(ranges))] => format!("{} is required", describe_ranges(package, ranges)),
+                [(depender, Term::Positive(depender_ranges)), (dependency, Term::Negative(ranges))]
+                | [(dependency, Term::Negative(ranges)), (depender, Term::Positive(depender_ranges))] => format!(
+                    "{} depends on {}",
+                    describe_ranges(depender, depender_ranges),
+                    describe_ranges(dependency, ranges)),
+                _ => {
+                    let terms: Vec<String> = terms.iter().map(|(package, term)| describe_term(package, term)).collect();
+                    format!("{} are incompatible", terms.join(" and "))
+                }
+            },
+        }
+    }
+}
+
+fn describe_ranges(package: &Package, ranges: &Ranges) -> String {
+    match (package, ranges.as_singleton()) {
+        (Package::Root, _) => "root".to_string(),
+        (_, Some(version)) => format!("{} {}", package, version),
+        _ if *ranges == Ranges::full() => package.to_string(),
+        _ => format!("{} {}", package, ranges),
+    }
+}
+
+fn describe_term(package: &Package, term: &Term) -> String {
+    match term {
+        Term::Positive(ranges) => describe_ranges(package, ranges),
+        Term::Negative(ranges) => format!("not {}", describe_ranges(package, ranges)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resolver::*;
+
+    fn provider(packages: &[(&str, &str, &[&str])]) -> InMemoryProvider {
+        let mut provider = InMemoryProvider::new();
+        for (package, version, requirements) in packages {
+            provider.add(package, version, requirements).unwrap();
+        }
+        provider
+    }
+
+    fn resolve(resolver: &Resolver, provider: &InMemoryProvider, requirements: &[&str]) -> Result<Vec<String>, Error> {
+        let requirements: Vec<Requirement> = requirements.iter().map(|requirement| requirement.parse().unwrap()).collect();
+        let resolution = resolver.resolve(&requirements, provider)?;
+        Ok(resolution.packages.iter().map(|(name, version)| format!("{}=={}", name, version)).collect())
+    }
+
+    /// The examples from the PubGrub documentation.
+    #[test]
+    fn test_solutions() {
+        let resolver = Resolver::default();
+
+        let no_conflict = provider(&[
+            ("foo", "1.0.0", &["bar>=1.0,<2.0"]),
+            ("bar", "1.0.0", &[]),
+            ("bar", "2.0.0", &[]),
+        ]);
+        assert_eq!(resolve(&resolver, &no_conflict, &["foo>=1.0,<2.0"]).unwrap(), vec!["bar==1.0.0", "foo==1.0.0"]);
+
+        let avoiding_conflict = provider(&[
+            ("foo", "1.0.0", &[]),
+            ("foo", "1.1.0", &["bar>=2.0,<3.0"]),
+            ("bar", "1.0.0", &[]),
+            ("bar", "1.1.0", &[]),
+            ("bar", "2.0.0", &[]),
+        ]);
+        assert_eq!(
+            resolve(&resolver, &avoiding_conflict, &["foo>=1.0,<2.0", "bar>=1.0,<2.0"]).unwrap(),
+            vec!["bar==1.1.0", "foo==1.0.0"]);
+
+        let conflict_resolution = provider(&[
+            ("foo", "1.0.0", &[]),
+            ("foo", "2.0.0", &["bar>=1.0,<2.0"]),
+            ("bar", "1.0.0", &["foo>=1.0,<2.0"]),
+        ]);
+        assert_eq!(resolve(&resolver, &conflict_resolution, &["foo>=1.0"]).unwrap(), vec!["foo==1.0.0"]);
+
+        let partial_satisfier = provider(&[
+            ("foo", "1.0.0", &[]),
+            ("foo", "1.1.0", &["left>=1.0,<2.0", "right>=1.0,<2.0"]),
+            ("left", "1.0.0", &["shared>=1.0"]),
+            ("right", "1.0.0", &["shared<2.0"]),
+            ("shared", "2.0.0", &[]),
+            ("shared", "1.0.0", &["target>=1.0,<2.0"]),
+            ("target", "2.0.0", &[]),
+            ("target", "1.0.0", &[]),
+        ]);
+        assert_eq!(
+            resolve(&resolver, &partial_satisfier, &["foo>=1.0,<2.0", "target>=2.0,<3.0"]).unwrap(),
+            vec!["foo==1.0.0", "target==2.0.0"]);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let linear = provider(&[
+            ("foo", "1.0", &["bar>=2.0"]),
+            ("bar", "2.0", &["baz>=3.0"]),
+            ("baz", "1.0", &[]),
+            ("baz", "3.0", &[]),
+        ]);
+        let err = resolve(&Resolver::default(), &linear, &["foo", "baz==1.0"]).unwrap_err();
+        assert_eq!(err.to_string(), "\
+No solution found:
+(1) Because there is no version of foo in <1.0 | >1.0 and foo 1.0 depends on bar >=2.0, foo depends on bar >=2.0.
+(2) Because bar 2.0 depends on baz >=3.0 and there is no version of bar in >2.0, bar >=2.0 depends on baz >=3.0.
+(3) Because foo depends on bar >=2.0 (1) and bar >=2.0 depends on baz >=3.0 (2), foo depends on baz >=3.0.
+(4) Because foo depends on baz >=3.0 (3) and root depends on baz ==1.0, foo is forbidden.
+(5) Because root depends on foo and foo is forbidden (4), version solving failed.");
+
+        let missing = provider(&[("foo", "1.0", &["bar"])]);
+        let err = resolve(&Resolver::default(), &missing, &["foo"]).unwrap_err();
+        assert!(err.to_string().contains("there is no version of bar"), "{}", err);
+    }
+
+    #[test]
+    fn test_unavailable() {
+        let mut provider = provider(&[("foo", "1.0", &[])]);
+        provider.add_unavailable("foo", "2.0", "no compatible wheel").unwrap();
+        assert_eq!(resolve(&Resolver::default(), &provider, &["foo"]).unwrap(), vec!["foo==1.0"]);
+        let err = resolve(&Resolver::default(), &provider, &["foo>=2"]).unwrap_err();
+        assert!(err.to_string().contains("foo 2.0 is unavailable: no compatible wheel"), "{}", err);
+    }
+
+    #[test]
+    fn test_markers_and_extras() {
+        let provider = provider(&[
+            ("app", "1.0", &["lib[fast]>=1.0", "legacy; python_version < '3.8'"]),
+            ("lib", "1.0", &["speedups>=2; extra == 'fast'", "speedups<3"]),
+            ("lib", "2.0", &["speedups>=3; extra == 'fast'"]),
+            ("speedups", "2.5", &[]),
+            ("speedups", "3.0", &[]),
+            ("legacy", "1.0", &[]),
+        ]);
+        let mut resolver = Resolver::default();
+        resolver.environment.python_version = "3.12".to_string();
+        assert_eq!(
+            resolve(&resolver, &provider, &["app"]).unwrap(),
+            vec!["app==1.0", "lib==2.0", "speedups==3.0"]);
+        assert_eq!(
+            resolve(&resolver, &provider, &["app", "speedups<3"]).unwrap(),
+            vec!["app==1.0", "lib==1.0", "speedups==2.5"]);
+
+        resolver.environment.python_version = "3.7".to_string();
+        assert!(resolve(&resolver, &provider, &["app"]).unwrap().contains(&"legacy==1.0".to_string()));
+    }
+
+    #[test]
+    fn test_prereleases() {
+        let provider = provider(&[
+            ("foo", "1.0", &[]),
+            ("foo", "2.0b1", &[]),
+            ("bar", "1.0rc1", &[]),
+        ]);
+        let resolver = Resolver::default();
+        assert_eq!(resolve(&resolver, &provider, &["foo"]).unwrap(), vec!["foo==1.0"]);
+        assert_eq!(resolve(&resolver, &provider, &["foo>=2.0b1"]).unwrap(), vec!["foo==2.0b1"]);
+        assert_eq!(resolve(&resolver, &provider, &["bar"]).unwrap(), vec!["bar==1.0rc1"]);
+
+        let allow = Resolver { prereleases: PrereleasePolicy::Allow, ..Resolver::default() };
+        assert_eq!(resolve(&allow, &provider, &["foo"]).unwrap(), vec!["foo==2.0b1"]);
+        let disallow = Resolver { prereleases: PrereleasePolicy::Disallow, ..Resolver::default() };
+        assert!(resolve(&disallow, &provider, &["bar"]).is_err());
+    }
+
+    #[test]
+    fn test_prereleases_after_backtracking() {
+        // Only `a==2.0` asks for a pre-release of `c`, and it is given up.
+        let backtracking = provider(&[
+            ("a", "1.0", &["c"]),
+            ("a", "2.0", &["c>=2.0b1", "d"]),
+            ("b", "1.0", &[]),
+            ("b", "2.0", &[]),
+            ("c", "1.0", &[]),
+            ("c", "2.0b1", &[]),
+            ("d", "1.0", &["b>=2"]),
+        ]);
+        let resolver = Resolver::default();
+        assert_eq!(resolve(&resolver, &backtracking, &["a", "b<2"]).unwrap(), vec!["a==1.0", "b==1.0", "c==1.0"]);
+
+        // Here its dependencies conflict as soon as it is tried.
+        let conflicting = provider(&[
+            ("a", "1.0", &["c"]),
+            ("a", "2.0", &["c>=2.0b1", "b>=2"]),
+            ("b", "1.0", &[]),
+            ("b", "2.0", &[]),
+            ("c", "1.0", &[]),
+            ("c", "2.0b1", &[]),
+        ]);
+        assert_eq!(resolve(&resolver, &conflicting, &["a", "b<2"]).unwrap(), vec!["a==1.0", "b==1.0", "c==1.0"]);
+    }
+
+    #[test]
+    fn test_modes() {
+        let provider = provider(&[
+            ("app", "1.0", &["lib>=1.0"]),
+            ("app", "2.0", &["lib>=2.0"]),
+            ("lib", "1.0", &[]),
+            ("lib", "2.0", &[]),
+            ("lib", "3.0", &[]),
+        ]);
+        let highest = Resolver::default();
+        assert_eq!(resolve(&highest, &provider, &["app"]).unwrap(), vec!["app==2.0", "lib==3.0"]);
+        let lowest = Resolver { mode: ResolutionMode::Lowest, ..Resolver::default() };
+        assert_eq!(resolve(&lowest, &provider, &["app"]).unwrap(), vec!["app==1.0", "lib==1.0"]);
+        assert_eq!(resolve(&lowest, &provider, &["app>=2"]).unwrap(), vec!["app==2.0", "lib==2.0"]);
+        let lowest_direct = Resolver { mode: ResolutionMode::LowestDirect, ..Resolver::default() };
+        assert_eq!(resolve(&lowest_direct, &provider, &["app"]).unwrap(), vec!["app==1.0", "lib==3.0"]);
+    }
+
+    #[test]
+    fn test_lower_bounds() {
+        let provider = provider(&[
+            ("app", "1.0", &["lib>=1.1"]),
+            ("lib", "1.0", &[]),
+            ("lib", "1.2", &[]),
+            ("tool", "0.5", &[]),
+            ("tool", "1.0", &[]),
+        ]);
+        let lowest = Resolver { mode: ResolutionMode::Lowest, ..Resolver::default() };
+        let requirements: Vec<Requirement> = ["app>=0.9", "lib>=1.0", "tool>0.5", "tool<2"]
+            .iter()
+            .map(|requirement| requirement.parse().unwrap())
+            .collect();
+        let resolution = lowest.resolve(&requirements, &provider).unwrap();
+        let report: Vec<String> = resolution.lower_bounds.iter().map(ToString::to_string).collect();
+        assert_eq!(report, vec![
+            "app>=0.9: the lowest available version is 1.0",
+            "lib>=1.0: the lowest available version is 1.0, but it resolved to 1.2",
+        ]);
+
+        let highest = Resolver::default().resolve(&requirements, &provider).unwrap();
+        assert!(highest.lower_bounds.is_empty());
+    }
+
+    fn resolve_universal(provider: &InMemoryProvider, requirements: &[&str], requires_python: &str) -> Result<Vec<String>, Error> {
+        let requirements: Vec<Requirement> = requirements.iter().map(|requirement| requirement.parse().unwrap()).collect();
+        let requires_python = RequiresPython::parse(requires_python).unwrap();
+        let resolution = Resolver::default().resolve_universal(&requirements, &requires_python, provider)?;
+        Ok(resolution
+            .forks
+            .iter()
+            .map(|fork| {
+                let fork_marker = fork.marker.as_ref().map_or_else(MarkerSet::full, |marker| MarkerSet::from_marker(marker, &[]));
+                let pins: Vec<String> = fork
+                    .resolution
+                    .packages
+                    .iter()
+                    .map(|(name, version)| match &fork.resolution.markers[name] {
+                        marker if fork_marker.subset_of(marker) => format!("{}=={}", name, version),
+                        marker => format!("{}=={} ({})", name, version, marker),
+                    })
+                    .collect();
+                match &fork.marker {
+                    Some(marker) => format!("{}: {}", marker, pins.join(" ")),
+                    None => pins.join(" "),
+                }
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_universal() {
+        let provider = provider(&[
+            ("app", "1.0", &[
+                "colorama; sys_platform == 'win32'",
+                "lib>=2; sys_platform == 'linux'",
+                "lib<2; sys_platform != 'linux'",
+                "legacy; python_version < '3.9'",
+            ]),
+            ("lib", "1.0", &[]),
+            ("lib", "2.0", &["shim; python_version < '3.11'", "shim>=2; python_version >= '3.11'"]),
+            ("shim", "1.0", &[]),
+            ("shim", "2.0", &[]),
+            ("colorama", "0.4", &[]),
+            ("legacy", "1.0", &[]),
+            ("gui", "1.0", &["widgets; python_version >= '3.10'"]),
+            ("cli", "1.0", &["widgets"]),
+            ("widgets", "1.0", &[]),
+        ]);
+        // A dependency which only some environments need doesn't fork but
+        // keeps its marker, while diverging requirements do fork, also on a
+        // dependency of a dependency.
+        assert_eq!(resolve_universal(&provider, &["app"], ">=3.9").unwrap(), vec![
+            "sys_platform == \"linux\": app==1.0 lib==2.0 shim==2.0",
+            "sys_platform != \"linux\": app==1.0 colorama==0.4 (sys_platform == \"win32\") lib==1.0",
+        ]);
+
+        // Without `Requires-Python`, the marker of `legacy` can be true.
+        let requirements = &["colorama", "legacy; python_version < '3.9'"];
+        assert_eq!(
+            resolve_universal(&provider, requirements, "").unwrap(),
+            vec!["colorama==0.4 legacy==1.0 (python_version < \"3.9\")"]);
+        assert_eq!(resolve_universal(&provider, requirements, ">=3.9").unwrap(), vec!["colorama==0.4"]);
+
+        // A package reached along several paths is needed wherever any of
+        // them applies.
+        let requirements = &["gui; sys_platform == 'win32'", "cli; os_name == 'posix'"];
+        assert_eq!(resolve_universal(&provider, requirements, ">=3.9").unwrap(), vec![
+            "cli==1.0 (os_name == \"posix\") gui==1.0 (sys_platform == \"win32\") \
+             widgets==1.0 (os_name == \"posix\" or python_version >= \"3.10\" and sys_platform == \"win32\")",
+        ]);
+
+        // There is no fork for environments which `Requires-Python` rules out.
+        let requirements = &["shim<2; python_version < '3.8'", "shim>=2; python_version >= '3.8'"];
+        assert_eq!(resolve_universal(&provider, requirements, ">=3.9").unwrap(), vec!["shim==2.0"]);
+        assert_eq!(resolve_universal(&provider, requirements, ">=3.7").unwrap(), vec![
+            "python_version < \"3.8\": shim==1.0",
+            "python_version >= \"3.8\": shim==2.0",
+        ]);
+    }
+
+    #[test]
+    fn test_universal_no_solution() {
+        let provider = provider(&[("lib", "1.0", &[])]);
+        let err = resolve_universal(&provider, &["lib>=2; os_name == 'nt'", "lib<2; os_name != 'nt'"], ">=3.9").unwrap_err();
+        assert_eq!(err.to_string(), "\
+No solution found:
+In environments where os_name == \"nt\":
+(1) Because there is no version of lib in >=2 and root depends on lib >=2, version solving failed.");
+    }
+}
@@ -0,0 +1,243 @@
+//! Extraction of inline script metadata, as described in PEP723 (now the
+//! "Inline script metadata" specification): the `# /// script` comment block
+//! of a single-file Python script.
+//!
+//! This module requires the `script` feature. Errors are reported as
+//! `Error::ScriptMetadataError`, with the 1-based line of the script.
+
+use crate::error::Error;
+use crate::requirement::Requirement;
+use crate::requires_python::RequiresPython;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The metadata of a `# /// script` block. The `[tool]` table is accepted
+/// but not modelled.
+pub struct ScriptMetadata {
+    pub requires_python: Option<RequiresPython>,
+    pub dependencies: Vec<Requirement>,
+}
+
+/// The comment lines of a block, without the opening and closing lines.
+struct Block<'a> {
+    /// The 0-based index of the opening line.
+    opening: usize,
+    lines: Vec<&'a str>,
+}
+
+impl ScriptMetadata {
+    /// Finds and parses the `script` block of a Python source file, or
+    /// returns `None` if there isn't one. Blocks of other types are skipped.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::script::ScriptMetadata;
+    /// let metadata = ScriptMetadata::parse(r#"#!/usr/bin/env python3
+    /// ## /// script
+    /// ## requires-python = ">=3.11"
+    /// ## dependencies = [
+    /// ##   "requests<3",
+    /// ##   "rich",
+    /// ## ]
+    /// ## ///
+    ///
+    /// import requests
+    /// "#).unwrap().unwrap();
+    /// assert!(metadata.requires_python.unwrap().contains(&Version::parse("3.12").unwrap()));
+    /// assert_eq!(metadata.dependencies[0].to_string(), "requests<3");
+    ///
+    /// let err = ScriptMetadata::parse("# /// script\n# dependencies = ['requests>>2']\n# ///\n").unwrap_err();
+    /// assert!(err.to_string().starts_with("Invalid script metadata: line 2: `dependencies[0]`: "));
+    /// ```
+    pub fn parse(source: &str) -> Result<Option<ScriptMetadata>, Error> {
+        match find_block(source)? {
+            Some(block) => block.parse().map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for ScriptMetadata {
+    type Err = Error;
+    /// Parses a script which must have a `script` block.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ScriptMetadata::parse(s)?.ok_or_else(|| Error::script_metadata_error(0, "no `script` block".to_string()))
+    }
+}
+
+/// Finds the single `script` block. A block closes at the last `# ///` line
+/// before the first line which is not a comment.
+fn find_block(source: &str) -> Result<Option<Block<'_>>, Error> {
+    let lines: Vec<&str> = source.lines().collect();
+    let is_comment = |line: &str| line == "#" || line.starts_with("# ");
+    let mut found: Option<Block> = None;
+    let mut index = 0;
+    while index < lines.len() {
+        let kind = match lines[index].strip_prefix("# /// ") {
+            Some(kind) if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => kind,
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let comments = lines[index + 1..].iter().take_while(|line| is_comment(line)).count();
+        let closing = lines[index + 1..index + 1 + comments]
+            .iter()
+            .rposition(|line| *line == "# ///")
+            .map(|offset| index + 1 + offset);
+        let closing = match closing {
+            Some(closing) => closing,
+            None if kind == "script" => {
+                return Err(Error::script_metadata_error(index + 1, "unclosed `script` block".to_string()))
+            }
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+        if kind == "script" {
+            if found.is_some() {
+                return Err(Error::script_metadata_error(index + 1, "duplicate `script` block".to_string()));
+            }
+            let content = lines[index + 1..closing]
+                .iter()
+                .map(|line| line.strip_prefix("# ").unwrap_or(&line[1..]))
+                .collect();
+            found = Some(Block { opening: index, lines: content });
+        }
+        index = closing + 1;
+    }
+    Ok(found)
+}
+
+impl Block<'_> {
+    fn parse(&self) -> Result<ScriptMetadata, Error> {
+        let content = self.lines.join("\n");
+        let table: Table = content.parse().map_err(|err: toml::de::Error| {
+            let line = match err.span() {
+                Some(span) => self.opening + 2 + content[..span.start].matches('\n').count(),
+                None => self.opening + 1,
+            };
+            Error::script_metadata_error(line, err.message().to_string())
+        })?;
+
+        let mut metadata = ScriptMetadata::default();
+        for (key, value) in &table {
+            let line = self.line_of(key);
+            match (key.as_str(), value) {
+                ("requires-python", Value::String(specifiers)) => {
+                    metadata.requires_python = Some(RequiresPython::parse(specifiers).ok_or_else(|| {
+                        self.error(
+                            line,
+                            &format!("`requires-python`: invalid version specifier `{}`", specifiers))
+                    })?);
+                }
+                ("requires-python", _) => return Err(self.error(line, "`requires-python`: expected a string")),
+                ("dependencies", Value::Array(array)) => {
+                    for (index, requirement) in array.iter().enumerate() {
+                        let requirement = match requirement {
+                            Value::String(requirement) => requirement,
+                            _ => {
+                                return Err(self.error(line, &format!("`dependencies[{}]`: expected a string", index)))
+                            }
+                        };
+                        let parsed = Requirement::parse(requirement).map_err(|err| {
+                            self.error(self.line_of(requirement), &format!("`dependencies[{}]`: {}", index, err))
+                        })?;
+                        metadata.dependencies.push(parsed);
+                    }
+                }
+                ("dependencies", _) => return Err(self.error(line, "`dependencies`: expected an array of strings")),
+                ("tool", Value::Table(_)) => {}
+                ("tool", _) => return Err(self.error(line, "`tool`: expected a table")),
+                (key, _) => return Err(self.error(line, &format!("unknown key `{}`", key))),
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// Returns the 1-based script line of the first line of the block
+    /// mentioning `needle`, or of the opening line.
+    fn line_of(&self, needle: &str) -> usize {
+        match self.lines.iter().position(|line| line.contains(needle)) {
+            Some(index) => self.opening + 2 + index,
+            None => self.opening + 1,
+        }
+    }
+
+    fn error(&self, line: usize, reason: &str) -> Error {
+        Error::script_metadata_error(line, reason.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::*;
+
+    fn parse_error(source: &str) -> (usize, String) {
+        match ScriptMetadata::parse(source) {
+            Err(Error::ScriptMetadataError { line, reason }) => (line, reason),
+            other => panic!("Expected a script metadata error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_find_block() {
+        assert_eq!(ScriptMetadata::parse("import sys\n# a comment\n").unwrap(), None);
+
+        // Other block types are skipped, and a block closes at its last
+        // `# ///` line.
+        let source = "\
+# /// other
+# /// script
+# ///
+
+# /// script
+# dependencies = ['one']
+#
+# # ///
+# ///
+import one
+";
+        let metadata = ScriptMetadata::parse(source).unwrap().unwrap();
+        assert_eq!(metadata.dependencies.len(), 1);
+        assert_eq!(metadata.requires_python, None);
+
+        assert_eq!(
+            ScriptMetadata::parse("# /// script\n# ///\n").unwrap(),
+            Some(ScriptMetadata::default()));
+        assert_eq!(
+            ScriptMetadata::parse("# /// script\n#dependencies = []\n# ///\n").unwrap_err().to_string(),
+            "Invalid script metadata: line 1: unclosed `script` block");
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases: &[(&str, usize, &str)] = &[
+            ("x = 1\n# /// script\n# dependencies = []\n", 2, "unclosed `script` block"),
+            ("# /// script\n# ///\n\n# /// script\n# ///\n", 4, "duplicate `script` block"),
+            ("# /// script\n# dependencies = [\n#   'a',\n#   'b'\n# ///\n", 4, "invalid array"),
+            ("# /// script\n#\n# requires-python = 3\n# ///\n", 3, "`requires-python`: expected a string"),
+            (
+                "# /// script\n# requires-python = '>=three'\n# ///\n",
+                2,
+                "`requires-python`: invalid version specifier `>=three`",
+            ),
+            (
+                "# /// script\n# dependencies = [\n#   'a',\n#   'b c',\n# ]\n# ///\n",
+                4,
+                "`dependencies[1]`: ",
+            ),
+            ("# /// script\n# dependencies = 'a'\n# ///\n", 2, "`dependencies`: expected an array of strings"),
+            ("# /// script\n# tool = 1\n# ///\n", 2, "`tool`: expected a table"),
+            ("# /// script\n# name = 'x'\n# ///\n", 2, "unknown key `name`"),
+            ("# /// script\n# a = 1\n# a = 2\n# ///\n", 3, "duplicate key"),
+        ];
+        for (source, line, reason) in cases {
+            let (actual_line, actual_reason) = parse_error(source);
+            assert_eq!(actual_line, *line, "source={:?} reason={}", source, actual_reason);
+            assert!(actual_reason.starts_with(reason), "source={:?} reason={}", source, actual_reason);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+//! Reading and validation of source distributions, as described in the
+//! "Source distribution format" specification: a `.tar.gz` (or a legacy
+//! `.zip`) archive holding a single `{name}-{version}` directory, with the
+//! core metadata in `PKG-INFO` and usually a `pyproject.toml`.
+//!
+//! This module requires the `sdist` feature. Only `PKG-INFO` and
+//! `pyproject.toml` are read into memory; nothing is extracted.
+
+use crate::error::Error;
+use crate::filename::{SdistExtension, SdistFilename};
+use crate::metadata::Metadata;
+use crate::Version;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The metadata read from an sdist.
+pub struct Sdist {
+    pub filename: SdistFilename,
+    /// The top-level directory, e.g. `spam-1.0`.
+    pub directory: String,
+    pub pkg_info: Metadata,
+    /// The contents of the top-level `pyproject.toml`, which older sdists
+    /// don't have.
+    pub pyproject_toml: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Something wrong with an sdist.
+pub enum SdistProblem {
+    /// The archive doesn't hold exactly one top-level directory, so nothing
+    /// else was checked. Lists every top-level entry.
+    TopLevel { entries: Vec<String> },
+    /// The top-level directory isn't `{name}-{version}`.
+    InvalidDirectory { directory: String },
+    /// `PKG-INFO` is missing from the top-level directory.
+    MissingFile { path: String },
+    /// `PKG-INFO` can't be parsed.
+    InvalidFile { path: String, reason: String },
+    /// The version of the filename isn't the `Version` in `PKG-INFO`.
+    VersionMismatch { filename: Version, metadata: Version },
+    /// The version of the top-level directory isn't the `Version` in
+    /// `PKG-INFO`.
+    DirectoryVersionMismatch { directory: Version, metadata: Version },
+    /// A version string, in the filename or in `PKG-INFO`, which isn't in
+    /// canonical form. `path` is the filename or the path of `PKG-INFO`.
+    NonCanonicalVersion { path: String, version: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The result of validating an sdist.
+pub struct SdistCheck {
+    pub filename: SdistFilename,
+    pub problems: Vec<SdistProblem>,
+}
+
+#[derive(Default)]
+/// The top-level entries of an sdist, and the files of interest in them.
+struct Contents {
+    top_level: BTreeSet<String>,
+    /// Whether any path is below the top level, i.e. whether a single
+    /// top-level entry is a directory.
+    nested: bool,
+    /// `PKG-INFO` and `pyproject.toml` of every top-level directory.
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl Sdist {
+    /// Reads `PKG-INFO` and `pyproject.toml` from the sdist at `sdist`.
+    /// Fails if the archive doesn't hold a single top-level directory with
+    /// a valid `PKG-INFO`; use [`SdistCheck`] to find out more.
+    ///
+    /// ```no_run
+    /// # use pep440::sdist::Sdist;
+    /// let sdist = Sdist::read("dist/spam-1.0.tar.gz").unwrap();
+    /// println!("{} {}", sdist.pkg_info.name, sdist.pkg_info.version);
+    /// ```
+    pub fn read<P: AsRef<Path>>(sdist: P) -> Result<Sdist, Error> {
+        let sdist = sdist.as_ref();
+        let filename = parse_filename(sdist)?;
+        let mut contents = read_contents(sdist, filename.extension)?;
+        let mut problems = vec![];
+        let found = contents
+            .directory(&mut problems)
+            .and_then(|directory| Some((contents.pkg_info(&directory, &mut problems)?, directory)));
+        let (pkg_info, directory) = match found {
+            Some(found) => found,
+            None => return Err(Error::archive_error(sdist, problems[0].to_string())),
+        };
+        let pyproject_toml = match contents.files.remove(&format!("{}/pyproject.toml", directory)) {
+            Some(pyproject_toml) => Some(String::from_utf8(pyproject_toml).map_err(|_| {
+                Error::archive_error(sdist, format!("`{}/pyproject.toml` is not UTF-8", directory))
+            })?),
+            None => None,
+        };
+        Ok(Sdist { filename, directory, pkg_info, pyproject_toml })
+    }
+
+    /// Parses the sdist's `pyproject.toml`, if it has one. Requires the
+    /// `pyproject` feature.
+    #[cfg(feature = "pyproject")]
+    pub fn pyproject(&self) -> Result<Option<crate::pyproject::PyProject>, Error> {
+        self.pyproject_toml.as_deref().map(crate::pyproject::PyProject::parse).transpose()
+    }
+}
+
+impl SdistCheck {
+    /// Validates the sdist at `sdist`: the filename and the top-level
+    /// directory must have the version in `PKG-INFO`, compared with
+    /// `Version` equality, and the version strings of the filename and
+    /// `PKG-INFO` must be canonical. Only a filename which isn't a valid
+    /// sdist filename, or an archive which can't be read, is an error.
+    ///
+    /// ```no_run
+    /// # use pep440::sdist::SdistCheck;
+    /// let check = SdistCheck::check("dist/spam-1.0.tar.gz").unwrap();
+    /// for problem in &check.problems {
+    ///     println!("{}: {}", check.filename, problem);
+    /// }
+    /// ```
+    pub fn check<P: AsRef<Path>>(sdist: P) -> Result<SdistCheck, Error> {
+        let sdist = sdist.as_ref();
+        let filename = parse_filename(sdist)?;
+        let contents = read_contents(sdist, filename.extension)?;
+        let mut check = SdistCheck { filename, problems: vec![] };
+        let directory = match contents.directory(&mut check.problems) {
+            Some(directory) => directory,
+            None => return Ok(check),
+        };
+
+        let directory_version = directory.rsplit_once('-').and_then(|(_, version)| Version::parse(version));
+        if directory_version.is_none() {
+            check.problems.push(SdistProblem::InvalidDirectory { directory: directory.clone() });
+        }
+        let raw_filename = sdist.file_name().and_then(|filename| filename.to_str()).unwrap_or_default();
+        let version = raw_filename
+            .strip_suffix(check.filename.extension.as_str())
+            .and_then(|stem| stem.rsplit_once('-'))
+            .map_or("", |(_, version)| version);
+        if !Version::is_canonical(version) {
+            check.problems.push(SdistProblem::NonCanonicalVersion {
+                path: raw_filename.to_string(),
+                version: version.to_string(),
+            });
+        }
+
+        let pkg_info = match contents.pkg_info(&directory, &mut check.problems) {
+            Some(pkg_info) => pkg_info,
+            None => return Ok(check),
+        };
+        if check.filename.version != pkg_info.version {
+            check.problems.push(SdistProblem::VersionMismatch {
+                filename: check.filename.version.clone(),
+                metadata: pkg_info.version.clone(),
+            });
+        }
+        if let Some(directory) = directory_version.filter(|version| *version != pkg_info.version) {
+            check.problems.push(SdistProblem::DirectoryVersionMismatch {
+                directory,
+                metadata: pkg_info.version.clone(),
+            });
+        }
+        let version = pkg_info.get("Version").unwrap_or_default();
+        if !Version::is_canonical(version) {
+            check.problems.push(SdistProblem::NonCanonicalVersion {
+                path: format!("{}/PKG-INFO", directory),
+                version: version.to_string(),
+            });
+        }
+        Ok(check)
+    }
+
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Contents {
+    /// Records an archive path, and returns `true` if its contents are
+    /// wanted.
+    fn add(&mut self, path: &str) -> bool {
+        let mut path = path;
+        while let Some(rest) = path.strip_prefix("./") {
+            path = rest;
+        }
+        let path = path.trim_end_matches('/');
+        if path.is_empty() || path == "." {
+            return false;
+        }
+        let (top, rest) = path.split_once('/').unwrap_or((path, ""));
+        self.top_level.insert(top.to_string());
+        self.nested |= !rest.is_empty();
+        matches!(rest, "PKG-INFO" | "pyproject.toml")
+    }
+
+    /// Returns the single top-level directory, or adds a problem.
+    fn directory(&self, problems: &mut Vec<SdistProblem>) -> Option<String> {
+        match self.top_level.iter().collect::<Vec<_>>().as_slice() {
+            [directory] if self.nested => Some(directory.to_string()),
+            _ => {
+                problems.push(SdistProblem::TopLevel { entries: self.top_level.iter().cloned().collect() });
+                None
+            }
+        }
+    }
+
+    /// Parses the `PKG-INFO` of `directory`, or adds a problem.
+    fn pkg_info(&self, directory: &str, problems: &mut Vec<SdistProblem>) -> Option<Metadata> {
+        let path = format!("{}/PKG-INFO", directory);
+        let contents = match self.files.get(&path) {
+            Some(contents) => contents,
+            None => {
+                problems.push(SdistProblem::MissingFile { path });
+                return None;
+            }
+        };
+        let parsed = match std::str::from_utf8(contents) {
+            Ok(contents) => Metadata::parse(contents).map_err(|err| err.to_string()),
+            Err(_) => Err("not UTF-8".to_string()),
+        };
+        match parsed {
+            Ok(pkg_info) => Some(pkg_info),
+            Err(reason) => {
+                problems.push(SdistProblem::InvalidFile { path, reason });
+                None
+            }
+        }
+    }
+}
+
+fn parse_filename(sdist: &Path) -> Result<SdistFilename, Error> {
+    sdist
+        .file_name()
+        .and_then(|filename| filename.to_str())
+        .and_then(SdistFilename::parse)
+        .ok_or_else(|| Error::archive_error(sdist, "invalid sdist filename".to_string()))
+}
+
+/// The largest `PKG-INFO` or `pyproject.toml` read from an sdist, since they
+/// are read into memory.
+const MAX_FILE_SIZE: u64 = 1 << 24;
+
+/// Reads a file of `size` bytes, as declared in the archive. The declared
+/// size is not trusted: a file larger than [`MAX_FILE_SIZE`], or whose
+/// contents don't have the declared size, is an error.
+fn read_file<R: Read>(reader: R, path: &str, size: u64) -> Result<Vec<u8>, String> {
+    if size > MAX_FILE_SIZE {
+        return Err(format!("`{}` is too large ({} bytes)", path, size));
+    }
+    let mut contents = vec![];
+    reader
+        .take(size + 1)
+        .read_to_end(&mut contents)
+        .map_err(|err| format!("`{}`: {}", path, err))?;
+    if contents.len() as u64 != size {
+        return Err(format!("`{}` doesn't have its declared size of {} bytes", path, size));
+    }
+    Ok(contents)
+}
+
+fn read_contents(sdist: &Path, extension: SdistExtension) -> Result<Contents, Error> {
+    let error = |reason: String| Error::archive_error(sdist, reason);
+    let file = fs::File::open(sdist).map_err(|err| Error::io_error(sdist, err))?;
+    let mut contents = Contents::default();
+    match extension {
+        SdistExtension::TarGz => {
+            let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            for entry in archive.entries().map_err(|err| error(err.to_string()))? {
+                let mut entry = entry.map_err(|err| error(err.to_string()))?;
+                let entry_type = entry.header().entry_type();
+                if matches!(entry_type, tar::EntryType::XGlobalHeader | tar::EntryType::XHeader) {
+                    continue;
+                }
+                let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+                if contents.add(&path) && entry_type.is_file() {
+                    let size = entry.size();
+                    let buffer = read_file(&mut entry, &path, size).map_err(error)?;
+                    contents.files.insert(path.trim_start_matches("./").to_string(), buffer);
+                }
+            }
+        }
+        SdistExtension::Zip => {
+            let mut archive = zip::ZipArchive::new(file).map_err(|err| error(err.to_string()))?;
+            for index in 0..archive.len() {
+                let mut file = archive.by_index(index).map_err(|err| error(err.to_string()))?;
+                let path = file.name().to_string();
+                if contents.add(&path) && !file.is_dir() {
+                    let size = file.size();
+                    let buffer = read_file(&mut file, &path, size).map_err(error)?;
+                    contents.files.insert(path.trim_start_matches("./").to_string(), buffer);
+                }
+            }
+        }
+    }
+    Ok(contents)
+}
+
+impl fmt::Display for SdistProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdistProblem::TopLevel { entries } if entries.is_empty() => write!(f, "the archive is empty"),
+            SdistProblem::TopLevel { entries } => {
+                write!(f, "expected a single top-level directory, found {}", entries.join(", "))
+            }
+            SdistProblem::InvalidDirectory { directory } => {
+                write!(f, "top-level directory `{}` is not `{{name}}-{{version}}`", directory)
+            }
+            SdistProblem::MissingFile { path } => write!(f, "missing `{}`", path),
+            SdistProblem::InvalidFile { path, reason } => write!(f, "`{}`: {}", path, reason),
+            SdistProblem::VersionMismatch { filename, metadata } => {
+                write!(f, "version {} in PKG-INFO doesn't match the filename's {}", metadata, filename)
+            }
+            SdistProblem::DirectoryVersionMismatch { directory, metadata } => {
+                write!(f, "version {} in PKG-INFO doesn't match the top-level directory's {}", metadata, directory)
+            }
+            SdistProblem::NonCanonicalVersion { path, version } => {
+                write!(f, "`{}`: version `{}` is not canonical", path, version)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scratch::ScratchDir;
+    use crate::sdist::*;
+    use std::io::Write;
+
+    /// The paths and contents of the files of an sdist.
+    type SdistFiles<'a> = Vec<(&'a str, &'a [u8])>;
+
+    fn write_sdist(path: &Path, files: &[(&str, &[u8])]) {
+        let file = fs::File::create(path).unwrap();
+        if path.to_string_lossy().ends_with(".zip") {
+            let mut writer = zip::ZipWriter::new(file);
+            for (name, contents) in files {
+                writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+                writer.write_all(contents).unwrap();
+            }
+            writer.finish().unwrap();
+        } else {
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (name, contents) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o644);
+                builder.append_data(&mut header, name, *contents).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+    }
+
+    const PKG_INFO: &[u8] = b"Metadata-Version: 2.1\nName: spam\nVersion: 1.0\n";
+    const PYPROJECT_TOML: &[u8] = b"[project]\nname = \"spam\"\nversion = \"1.0\"\n";
+
+    #[test]
+    fn test_read() {
+        let dir = ScratchDir::new("sdist-read");
+
+        for filename in &["spam-1.0.tar.gz", "spam-1.0.zip"] {
+            let path = dir.join(filename);
+            write_sdist(&path, &[
+                ("./spam-1.0/PKG-INFO", PKG_INFO),
+                ("./spam-1.0/pyproject.toml", PYPROJECT_TOML),
+                ("./spam-1.0/src/spam/PKG-INFO", b"not metadata"),
+            ]);
+            let sdist = Sdist::read(&path).unwrap();
+            assert_eq!(sdist.directory, "spam-1.0");
+            assert_eq!(sdist.pkg_info.name.as_str(), "spam");
+            assert_eq!(sdist.pyproject_toml.as_deref().map(str::as_bytes), Some(PYPROJECT_TOML));
+            #[cfg(feature = "pyproject")]
+            assert!(sdist.pyproject().unwrap().is_some());
+        }
+
+        let path = dir.join("spam-1.0.tar.gz");
+        write_sdist(&path, &[("spam-1.0/setup.py", b"")]);
+        let err = Sdist::read(&path).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid archive {}: missing `spam-1.0/PKG-INFO`", path.display()));
+    }
+
+    #[test]
+    fn test_declared_sizes() {
+        let dir = ScratchDir::new("sdist-sizes");
+
+        // Rewrites the uncompressed size in the central directory, which is
+        // the size the archive declares.
+        let zip = dir.join("spam-1.0.zip");
+        let declare = |size: u32| {
+            write_sdist(&zip, &[("spam-1.0/PKG-INFO", PKG_INFO)]);
+            let mut bytes = fs::read(&zip).unwrap();
+            let header = bytes.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+            bytes[header + 24..header + 28].copy_from_slice(&size.to_le_bytes());
+            fs::write(&zip, bytes).unwrap();
+            Sdist::read(&zip).unwrap_err().to_string()
+        };
+        assert_eq!(
+            declare(u32::MAX),
+            format!("Invalid archive {}: `spam-1.0/PKG-INFO` is too large (4294967295 bytes)", zip.display()));
+        assert_eq!(
+            declare(5),
+            format!("Invalid archive {}: `spam-1.0/PKG-INFO` doesn't have its declared size of 5 bytes", zip.display()));
+
+        // A tar header declaring more than the limit isn't read at all.
+        let tar = dir.join("spam-1.0.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(&tar).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_path("spam-1.0/PKG-INFO").unwrap();
+        header.set_size(1 << 40);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, PKG_INFO).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        assert_eq!(
+            Sdist::read(&tar).unwrap_err().to_string(),
+            format!("Invalid archive {}: `spam-1.0/PKG-INFO` is too large (1099511627776 bytes)", tar.display()));
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = ScratchDir::new("sdist-check");
+        let check = |filename: &str, files: SdistFiles| -> Vec<String> {
+            let path = dir.join(filename);
+            write_sdist(&path, &files);
+            let check = SdistCheck::check(&path).unwrap();
+            assert_eq!(check.is_ok(), check.problems.is_empty());
+            check.problems.iter().map(|problem| problem.to_string()).collect()
+        };
+
+        let problems = check("spam-1.0.tar.gz", vec![("spam-1.0/PKG-INFO", PKG_INFO)]);
+        assert!(problems.is_empty(), "{:?}", problems);
+
+        // Equal versions are fine, but not canonical ones.
+        let problems = check("spam-1.0.0.zip", vec![("spam-1.0.0/PKG-INFO", PKG_INFO)]);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let non_canonical: &[u8] = b"Metadata-Version: 2.1\nName: spam\nVersion: 1.0c1\n";
+        let problems = check("spam-1.0c1.tar.gz", vec![("spam-1.0rc1/PKG-INFO", non_canonical)]);
+        assert_eq!(problems, vec![
+            "`spam-1.0c1.tar.gz`: version `1.0c1` is not canonical",
+            "`spam-1.0rc1/PKG-INFO`: version `1.0c1` is not canonical",
+        ]);
+
+        let problems = check("spam-1.1.tar.gz", vec![("spam-2.0/PKG-INFO", PKG_INFO)]);
+        assert_eq!(problems, vec![
+            "version 1.0 in PKG-INFO doesn't match the filename's 1.1",
+            "version 1.0 in PKG-INFO doesn't match the top-level directory's 2.0",
+        ]);
+
+        let problems = check("spam-1.0.tar.gz", vec![("spam/PKG-INFO", b"Name: spam\n")]);
+        assert_eq!(problems, vec![
+            "top-level directory `spam` is not `{name}-{version}`",
+            "`spam/PKG-INFO`: Invalid metadata: missing `Metadata-Version`",
+        ]);
+
+        let problems = check("spam-1.0.tar.gz", vec![("spam-1.0/PKG-INFO", PKG_INFO), ("setup.py", b"")]);
+        assert_eq!(problems, vec!["expected a single top-level directory, found setup.py, spam-1.0"]);
+        let problems = check("spam-1.0.tar.gz", vec![("PKG-INFO", PKG_INFO)]);
+        assert_eq!(problems, vec!["expected a single top-level directory, found PKG-INFO"]);
+        let problems = check("spam-1.0.zip", vec![]);
+        assert_eq!(problems, vec!["the archive is empty"]);
+
+        let err = SdistCheck::check(dir.join("spam.tar.bz2")).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid archive {}: invalid sdist filename", dir.join("spam.tar.bz2").display()));
+    }
+}
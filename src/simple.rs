@@ -0,0 +1,467 @@
+//! Parsing of project pages from the "simple" repository API, as described in
+//! PEP503, including the yanked status from PEP592, the core metadata
+//! attributes from PEP658 and PEP714, and the repository version from PEP629.
+//!
+//! The HTML parsing is intentionally simple: only `<a>`, `<base>` and `<meta>`
+//! tags are looked at, which is all the API defines.
+
+use crate::error::Error;
+use crate::filename::DistFilename;
+use crate::requires_python::RequiresPython;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+lazy_static! {
+    static ref ANCHOR_RE: Regex = Regex::new(r"(?is)<a\s([^>]*)>(.*?)</a\s*>").unwrap();
+
+    static ref BASE_RE: Regex = Regex::new(r"(?is)<base\s([^>]*)>").unwrap();
+
+    static ref META_RE: Regex = Regex::new(r"(?is)<meta\s([^>]*)>").unwrap();
+
+    static ref ATTRIBUTE_RE: Regex = Regex::new(r#"(?x)
+([a-zA-Z_:][-a-zA-Z0-9_:.]*)
+(?:
+    \s*=\s*
+    (?:"(?P<double>[^"]*)"|'(?P<single>[^']*)'|(?P<bare>[^\s"'=<>`]+))
+)?"#).unwrap();
+
+    static ref ENTITY_RE: Regex = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+
+    static ref SCHEME_RE: Regex = Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap();
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// Availability of the core metadata file of a distribution (PEP658), at the
+/// distribution URL with `.metadata` appended.
+pub struct CoreMetadata {
+    /// Hashes of the metadata file, keyed by algorithm. May be empty if the
+    /// index only says that the file is available.
+    pub hashes: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A file listed on a project page.
+pub struct File {
+    pub filename: String,
+    /// The absolute URL of the file, without any hash fragment.
+    pub url: String,
+    /// Hashes, keyed by algorithm (e.g. `sha256`).
+    pub hashes: BTreeMap<String, String>,
+    pub requires_python: Option<RequiresPython>,
+    pub yanked: bool,
+    /// The reason given for yanking, if any.
+    pub yanked_reason: Option<String>,
+    pub core_metadata: Option<CoreMetadata>,
+    /// The size of the file in bytes. Only the JSON API provides it.
+    pub size: Option<u64>,
+    /// The upload time as an ISO 8601 timestamp (PEP700). Only the JSON API
+    /// provides it.
+    pub upload_time: Option<String>,
+    /// The parsed filename, or `None` for files which are neither wheels nor
+    /// sdists (e.g. legacy `.egg` or `.exe` installers).
+    pub dist: Option<DistFilename>,
+}
+
+impl AsRef<File> for File {
+    fn as_ref(&self) -> &File {
+        self
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A link which was skipped, or an attribute of a file which was ignored,
+/// and why.
+pub struct Diagnostic {
+    /// The filename, or the whole tag if there is no usable link.
+    pub link: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// A parsed project page.
+pub struct ProjectPage {
+    /// The `pypi:repository-version` (PEP629), if the page declares one.
+    pub api_version: Option<String>,
+    pub files: Vec<File>,
+    /// Problems with single links. Like pip, a bad link doesn't fail the
+    /// page: links without an `href` are skipped, and an invalid
+    /// `data-requires-python` is ignored.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ProjectPage {
+    /// Parses the HTML of a project page which was fetched from `page_url`.
+    /// Relative links are resolved against `page_url`, or against the `<base>`
+    /// of the page if there is one.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::simple::ProjectPage;
+    /// let html = r#"<!DOCTYPE html><html><body>
+    /// <a href="../../files/foo-1.0-py3-none-any.whl#sha256=abc"
+    ///    data-requires-python="&gt;=3.8" data-core-metadata="sha256=def">foo-1.0-py3-none-any.whl</a>
+    /// <a href="https://files.example/foo-0.9.tar.gz" data-yanked="broken">foo-0.9.tar.gz</a>
+    /// </body></html>"#;
+    /// let page = ProjectPage::parse(html, "https://index.example/simple/foo/").unwrap();
+    /// let wheel = &page.files[0];
+    /// assert_eq!(wheel.url, "https://index.example/files/foo-1.0-py3-none-any.whl");
+    /// assert_eq!(wheel.hashes["sha256"], "abc");
+    /// assert_eq!(wheel.requires_python.as_ref().unwrap().to_string(), ">=3.8");
+    /// assert_eq!(wheel.core_metadata.as_ref().unwrap().hashes["sha256"], "def");
+    /// assert!(wheel.dist.as_ref().unwrap().is_wheel());
+    ///
+    /// let sdist = &page.files[1];
+    /// assert!(sdist.yanked);
+    /// assert_eq!(sdist.yanked_reason.as_deref(), Some("broken"));
+    /// assert_eq!(sdist.dist.as_ref().unwrap().version(), &Version::parse("0.9").unwrap());
+    /// ```
+    pub fn parse(html: &str, page_url: &str) -> Result<ProjectPage, Error> {
+        let base_url = BASE_RE
+            .captures(html)
+            .and_then(|captures| attributes(&captures[1]).remove("href"))
+            .map(|href| join_url(page_url, &href))
+            .unwrap_or_else(|| page_url.to_string());
+
+        let api_version = META_RE.captures_iter(html).find_map(|captures| {
+            let mut attributes = attributes(&captures[1]);
+            match attributes.get("name").map(String::as_str) {
+                Some("pypi:repository-version") => attributes.remove("content"),
+                _ => None,
+            }
+        });
+        if let Some(version) = &api_version {
+            if !version.starts_with("1.") {
+                return Err(Error::simple_api_error(format!(
+                    "{}: unsupported repository version `{}`", page_url, version)));
+            }
+        }
+
+        let mut diagnostics = vec![];
+        let files = ANCHOR_RE
+            .captures_iter(html)
+            .filter_map(|captures| parse_anchor(&captures[1], &base_url, &mut diagnostics))
+            .collect();
+
+        Ok(ProjectPage { api_version, files, diagnostics })
+    }
+}
+
+/// Parses a link, or returns `None` if it has no `href`.
+fn parse_anchor(raw_attributes: &str, base_url: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<File> {
+    let mut attributes = attributes(raw_attributes);
+    let href = match attributes.remove("href") {
+        Some(href) => href,
+        None => {
+            diagnostics.push(Diagnostic {
+                link: format!("<a {}>", raw_attributes.trim()),
+                reason: "link without href".to_string(),
+            });
+            return None;
+        }
+    };
+    let url = join_url(base_url, &href);
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url.to_string(), Some(fragment.to_string())),
+        None => (url, None),
+    };
+    let filename = percent_decode(url.rsplit('/').next().unwrap_or_default());
+
+    let mut hashes = BTreeMap::new();
+    if let Some((algorithm, digest)) = fragment.as_deref().and_then(|fragment| fragment.split_once('=')) {
+        hashes.insert(algorithm.to_string(), digest.to_string());
+    }
+
+    let requires_python = match attributes.remove("data-requires-python") {
+        Some(specifiers) if !specifiers.trim().is_empty() => {
+            let requires_python = RequiresPython::parse(&specifiers);
+            if requires_python.is_none() {
+                diagnostics.push(Diagnostic {
+                    link: filename.clone(),
+                    reason: format!("invalid data-requires-python `{}`, ignored", specifiers),
+                });
+            }
+            requires_python
+        }
+        _ => None,
+    };
+
+    let yanked_reason = attributes.remove("data-yanked");
+    let yanked = yanked_reason.is_some();
+    let yanked_reason = yanked_reason.filter(|reason| !reason.is_empty());
+
+    // PEP714 renamed `data-dist-info-metadata` to `data-core-metadata`; the new
+    // name wins if both are present.
+    let core_metadata = attributes
+        .remove("data-core-metadata")
+        .or_else(|| attributes.remove("data-dist-info-metadata"))
+        .and_then(|value| parse_core_metadata(&value));
+
+    let dist = DistFilename::parse(&filename);
+
+    Some(File {
+        filename,
+        url,
+        hashes,
+        requires_python,
+        yanked,
+        yanked_reason,
+        core_metadata,
+        size: None,
+        upload_time: None,
+        dist,
+    })
+}
+
+/// `true` means that the metadata is available without a hash, `false` that
+/// it is not available; otherwise the value is `algorithm=digest`.
+fn parse_core_metadata(value: &str) -> Option<CoreMetadata> {
+    match value {
+        "false" => None,
+        "true" | "" => Some(CoreMetadata::default()),
+        _ => {
+            let mut hashes = BTreeMap::new();
+            if let Some((algorithm, digest)) = value.split_once('=') {
+                hashes.insert(algorithm.to_string(), digest.to_string());
+            }
+            Some(CoreMetadata { hashes })
+        }
+    }
+}
+
+/// Parses the attributes of a tag, unescaping their values. Attribute names
+/// are lowercased; attributes without a value get the empty string.
+fn attributes(raw: &str) -> BTreeMap<String, String> {
+    ATTRIBUTE_RE
+        .captures_iter(raw)
+        .map(|captures| {
+            let value = captures
+                .name("double")
+                .or_else(|| captures.name("single"))
+                .or_else(|| captures.name("bare"))
+                .map(|value| unescape(value.as_str()))
+                .unwrap_or_default();
+            (captures[1].to_lowercase(), value)
+        })
+        .collect()
+}
+
+/// Replaces HTML character references; unknown named references are kept.
+fn unescape(value: &str) -> String {
+    ENTITY_RE
+        .replace_all(value, |captures: &Captures| {
+            let entity = &captures[1];
+            let decoded = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(std::char::from_u32)
+            } else if let Some(decimal) = entity.strip_prefix('#') {
+                decimal.parse().ok().and_then(std::char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ => None,
+                }
+            };
+            decoded.map(String::from).unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Returns the `file://` URL of an absolute path, with every byte outside the
+/// unreserved characters of RFC3986 and `/` percent-encoded.
+pub(crate) fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// Decodes `%XX` escapes; invalid escapes are kept as they are.
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let escaped = if bytes[pos] == b'%' {
+            value
+                .get(pos + 1..pos + 3)
+                .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                pos += 3;
+            }
+            None => {
+                decoded.push(bytes[pos]);
+                pos += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolves `href` against `base`, which is enough of RFC3986 for the links
+/// found on index pages.
+pub(crate) fn join_url(base: &str, href: &str) -> String {
+    if SCHEME_RE.is_match(href) {
+        return href.to_string();
+    }
+    let base = base.split('#').next().unwrap_or_default();
+    let (scheme, rest) = match base.split_once("://") {
+        Some(split) => split,
+        None => ("", base),
+    };
+    if let Some(network_path) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, network_path);
+    }
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let prefix = if scheme.is_empty() { authority.to_string() } else { format!("{}://{}", scheme, authority) };
+
+    if href.starts_with('#') || href.starts_with('?') || href.is_empty() {
+        let path = if href.starts_with('#') { path } else { path.split('?').next().unwrap_or_default() };
+        return format!("{}{}{}", prefix, path, href);
+    }
+
+    let path = path.split('?').next().unwrap_or_default();
+    let joined = if href.starts_with('/') {
+        href.to_string()
+    } else {
+        let directory = &path[..path.rfind('/').map(|index| index + 1).unwrap_or(0)];
+        format!("{}{}", if directory.is_empty() { "/" } else { directory }, href)
+    };
+
+    // Remove `.` and `..` segments from the path, leaving query and fragment.
+    let split = joined.find(['?', '#']).unwrap_or(joined.len());
+    let (path, suffix) = joined.split_at(split);
+    let mut segments: Vec<&str> = vec![];
+    let parts: Vec<&str> = path.split('/').collect();
+    for (index, segment) in parts.iter().enumerate() {
+        let last = index == parts.len() - 1;
+        match *segment {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                if segments.len() > 1 {
+                    segments.pop();
+                }
+                if last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(segment),
+        }
+    }
+    format!("{}{}{}", prefix, segments.join("/"), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simple::*;
+
+    #[test]
+    fn test_join_url() {
+        let base = "https://example.com/simple/foo/index.html?x=1#top";
+        let cases: &[(&str, &str)] = &[
+            ("https://other.example/a.whl", "https://other.example/a.whl"),
+            ("//cdn.example/a.whl", "https://cdn.example/a.whl"),
+            ("/files/a.whl", "https://example.com/files/a.whl"),
+            ("a.whl", "https://example.com/simple/foo/a.whl"),
+            ("./a.whl#sha256=00", "https://example.com/simple/foo/a.whl#sha256=00"),
+            ("../../files/a.whl", "https://example.com/files/a.whl"),
+            ("../../../../a.whl", "https://example.com/a.whl"),
+            ("../", "https://example.com/simple/"),
+        ];
+        for (href, expected) in cases {
+            assert_eq!(join_url(base, href), *expected, "href={}", href);
+        }
+        assert_eq!(join_url("file:///srv/mirror/simple/foo/", "../../files/a.whl"), "file:///srv/mirror/files/a.whl");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("foo-1.0%2Blocal-py3-none-any.whl"), "foo-1.0+local-py3-none-any.whl");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode("%+1"), "%+1");
+        assert_eq!(file_url(Path::new("/srv/a b/foo-1.0+local.tar.gz")), "file:///srv/a%20b/foo-1.0%2Blocal.tar.gz");
+    }
+
+    #[test]
+    fn test_parse() {
+        let html = r#"
+<html>
+  <head>
+    <meta name="pypi:repository-version" content="1.1">
+    <base href="https://files.example/packages/">
+  </head>
+  <body>
+    <h1>Links for foo</h1>
+    <A HREF='foo-1.0.tar.gz#md5=123' data-yanked>foo-1.0.tar.gz</A><br/>
+    <a href="foo-2.0-cp312-cp312-win_amd64.whl" data-requires-python='&gt;=3.8,&lt;4' data-dist-info-metadata="true">foo-2.0-cp312-cp312-win_amd64.whl</a>
+    <a href="foo-2.0-py3-none-any.whl" data-dist-info-metadata="sha256=aa" data-core-metadata="sha256=bb">x</a>
+    <a href="foo-0.1.win32.exe">foo-0.1.win32.exe</a>
+  </body>
+</html>"#;
+        let page = ProjectPage::parse(html, "https://index.example/simple/foo/").unwrap();
+        assert_eq!(page.api_version.as_deref(), Some("1.1"));
+        assert_eq!(page.files.len(), 4);
+
+        let sdist = &page.files[0];
+        assert_eq!(sdist.url, "https://files.example/packages/foo-1.0.tar.gz");
+        assert_eq!(sdist.hashes["md5"], "123");
+        assert!(sdist.yanked && sdist.yanked_reason.is_none());
+        assert!(sdist.core_metadata.is_none());
+
+        let wheel = &page.files[1];
+        assert_eq!(wheel.requires_python.as_ref().unwrap().to_string(), ">=3.8, <4");
+        assert_eq!(wheel.core_metadata, Some(CoreMetadata::default()));
+        assert!(!wheel.yanked);
+
+        assert_eq!(page.files[2].core_metadata.as_ref().unwrap().hashes["sha256"], "bb");
+        assert_eq!(page.files[3].filename, "foo-0.1.win32.exe");
+        assert!(page.files[3].dist.is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let html = r#"<meta name="pypi:repository-version" content="2.0">"#;
+        assert!(ProjectPage::parse(html, "https://index.example/simple/foo/").is_err());
+    }
+
+    #[test]
+    fn test_bad_links() {
+        let html = r#"
+<a name="top">top</a>
+<a href="foo-1.0.tar.gz" data-requires-python="&gt;=3.8.*">foo-1.0.tar.gz</a>
+<a href="foo-1.1.tar.gz" data-requires-python="&gt;=3.8">foo-1.1.tar.gz</a>
+<a href="foo-1.2.tar.gz">foo-1.2.tar.gz</a>"#;
+        let page = ProjectPage::parse(html, "https://index.example/simple/foo/").unwrap();
+        let filenames: Vec<&str> = page.files.iter().map(|file| file.filename.as_str()).collect();
+        assert_eq!(filenames, vec!["foo-1.0.tar.gz", "foo-1.1.tar.gz", "foo-1.2.tar.gz"]);
+        assert!(page.files[0].requires_python.is_none());
+        assert!(page.files[1].requires_python.is_some());
+        assert_eq!(page.diagnostics, vec![
+            Diagnostic { link: "<a name=\"top\">".to_string(), reason: "link without href".to_string() },
+            Diagnostic {
+                link: "foo-1.0.tar.gz".to_string(),
+                reason: "invalid data-requires-python `>=3.8.*`, ignored".to_string(),
+            },
+        ]);
+    }
+}
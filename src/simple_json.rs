@@ -0,0 +1,368 @@
+//! Models of the JSON flavour of the "simple" repository API
+//! (`application/vnd.pypi.simple.v1+json`), as described in PEP691, with the
+//! `versions` list, file sizes and upload times added by PEP700.
+//!
+//! The models mirror the wire format; [`ProjectDetail::to_page`] converts
+//! them into the same [`ProjectPage`] the HTML parser produces.
+
+use crate::error::Error;
+use crate::filename::DistFilename;
+use crate::requires_python::RequiresPython;
+use crate::simple::{join_url, CoreMetadata, Diagnostic, File, ProjectPage};
+use crate::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The content type of version 1 of the JSON API.
+pub const CONTENT_TYPE: &str = "application/vnd.pypi.simple.v1+json";
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// The `meta` object of every response.
+pub struct Meta {
+    #[serde(rename = "api-version")]
+    pub api_version: String,
+    /// PyPI's private serial number of the last change, if provided.
+    #[serde(rename = "_last-serial", default, skip_serializing_if = "Option::is_none")]
+    pub last_serial: Option<u64>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// The response of the root URL, listing every project on the index.
+pub struct ProjectList {
+    pub meta: Meta,
+    pub projects: Vec<ProjectListEntry>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProjectListEntry {
+    /// The name of the project, not necessarily normalized.
+    pub name: String,
+    #[serde(rename = "_last-serial", default, skip_serializing_if = "Option::is_none")]
+    pub last_serial: Option<u64>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// The response of a project URL, listing the files of one project.
+pub struct ProjectDetail {
+    pub meta: Meta,
+    pub name: String,
+    pub files: Vec<JsonFile>,
+    /// Every version of the project, including those without files (PEP700).
+    /// Absent from API version 1.0 responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub versions: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A file as listed in a [`ProjectDetail`].
+pub struct JsonFile {
+    pub filename: String,
+    /// The URL of the file, possibly relative to the project URL.
+    pub url: String,
+    pub hashes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_python: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub core_metadata: Option<BoolOrHashes>,
+    /// The name of `core-metadata` before PEP714.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dist_info_metadata: Option<BoolOrHashes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpg_sig: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yanked: Option<Yanked>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// An ISO 8601 timestamp, e.g. `2023-05-01T12:34:56.789012Z`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_time: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Either a flag or a table of hashes keyed by algorithm, as used by the
+/// `core-metadata` key.
+pub enum BoolOrHashes {
+    Bool(bool),
+    Hashes(BTreeMap<String, String>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The `yanked` key, which is either a flag or the reason for yanking.
+pub enum Yanked {
+    Bool(bool),
+    Reason(String),
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The versions of a project, see [`ProjectDetail::versions`].
+pub struct ProjectVersions {
+    /// The valid versions, sorted from lowest to highest.
+    pub versions: Vec<Version>,
+    /// Entries which are not valid versions, in the order they were listed.
+    pub invalid: Vec<String>,
+}
+
+impl ProjectList {
+    /// Parses the JSON response of the root URL of an index.
+    pub fn parse(json: &str) -> Result<ProjectList, Error> {
+        let list: ProjectList = from_json(json)?;
+        check_api_version(&list.meta)?;
+        Ok(list)
+    }
+}
+
+impl ProjectDetail {
+    /// Parses the JSON response of a project URL.
+    ///
+    /// ```
+    /// # use pep440::simple_json::ProjectDetail;
+    /// let json = r#"{
+    ///   "meta": {"api-version": "1.1"},
+    ///   "name": "foo",
+    ///   "files": [],
+    ///   "versions": ["1.0", "0.9", "2.0rc1", "not-a-version"]
+    /// }"#;
+    /// let project = ProjectDetail::parse(json).unwrap();
+    /// let versions = project.versions();
+    /// let versions: Vec<String> = versions.versions.iter().map(|v| v.to_string()).collect();
+    /// assert_eq!(versions, vec!["0.9", "1.0", "2.0rc1"]);
+    /// assert_eq!(project.versions().invalid, vec!["not-a-version"]);
+    /// ```
+    pub fn parse(json: &str) -> Result<ProjectDetail, Error> {
+        let project: ProjectDetail = from_json(json)?;
+        check_api_version(&project.meta)?;
+        Ok(project)
+    }
+
+    /// Returns the versions of the project, sorted in ascending order.
+    ///
+    /// The PEP700 `versions` list is used if present. Older responses don't
+    /// have one, so the versions are then taken from the filenames, and files
+    /// whose name can't be parsed are reported as invalid.
+    pub fn versions(&self) -> ProjectVersions {
+        let mut result = ProjectVersions::default();
+        match &self.versions {
+            Some(versions) => {
+                for version in versions {
+                    match Version::parse(version) {
+                        Some(parsed) => result.versions.push(parsed),
+                        None => result.invalid.push(version.clone()),
+                    }
+                }
+            }
+            None => {
+                for file in &self.files {
+                    match DistFilename::parse(&file.filename) {
+                        Some(dist) => result.versions.push(dist.version().clone()),
+                        None => result.invalid.push(file.filename.clone()),
+                    }
+                }
+            }
+        }
+        result.versions.sort();
+        result.versions.dedup();
+        result
+    }
+
+    /// Converts the project into the representation shared with the HTML
+    /// API, resolving the URLs of the files against `page_url`, the URL the
+    /// response was fetched from. As with the HTML API, an invalid
+    /// `requires-python` is ignored and reported as a diagnostic.
+    pub fn to_page(&self, page_url: &str) -> ProjectPage {
+        let mut diagnostics = vec![];
+        let files = self.files.iter().map(|file| file.to_file(page_url, &mut diagnostics)).collect();
+        ProjectPage { api_version: Some(self.meta.api_version.clone()), files, diagnostics }
+    }
+}
+
+impl JsonFile {
+    /// Converts the file into the representation shared with the HTML API.
+    /// An invalid `requires-python` is left out and reported in `diagnostics`.
+    pub fn to_file(&self, page_url: &str, diagnostics: &mut Vec<Diagnostic>) -> File {
+        let url = join_url(page_url, &self.url);
+        let url = match url.split_once('#') {
+            Some((url, _)) => url.to_string(),
+            None => url,
+        };
+
+        let requires_python = match &self.requires_python {
+            Some(specifiers) if !specifiers.trim().is_empty() => {
+                let requires_python = RequiresPython::parse(specifiers);
+                if requires_python.is_none() {
+                    diagnostics.push(Diagnostic {
+                        link: self.filename.clone(),
+                        reason: format!("invalid requires-python `{}`, ignored", specifiers),
+                    });
+                }
+                requires_python
+            }
+            _ => None,
+        };
+
+        let (yanked, yanked_reason) = match &self.yanked {
+            None | Some(Yanked::Bool(false)) => (false, None),
+            Some(Yanked::Bool(true)) => (true, None),
+            Some(Yanked::Reason(reason)) => (true, Some(reason.clone()).filter(|reason| !reason.is_empty())),
+        };
+
+        // As with the HTML API, the PEP714 name wins if both are present.
+        let core_metadata = match self.core_metadata.as_ref().or(self.dist_info_metadata.as_ref()) {
+            None | Some(BoolOrHashes::Bool(false)) => None,
+            Some(BoolOrHashes::Bool(true)) => Some(CoreMetadata::default()),
+            Some(BoolOrHashes::Hashes(hashes)) => Some(CoreMetadata { hashes: hashes.clone() }),
+        };
+
+        File {
+            filename: self.filename.clone(),
+            url,
+            hashes: self.hashes.clone(),
+            requires_python,
+            yanked,
+            yanked_reason,
+            core_metadata,
+            size: self.size,
+            upload_time: self.upload_time.clone(),
+            dist: DistFilename::parse(&self.filename),
+        }
+    }
+}
+
+fn from_json<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, Error> {
+    serde_json::from_str(json).map_err(|err| Error::simple_api_error(err.to_string()))
+}
+
+/// Only major version 1 of the API is understood; later minor versions are
+/// backwards compatible by definition (PEP629).
+fn check_api_version(meta: &Meta) -> Result<(), Error> {
+    if meta.api_version.starts_with("1.") {
+        Ok(())
+    } else {
+        Err(Error::simple_api_error(format!("unsupported api-version `{}`", meta.api_version)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simple_json::*;
+
+    const PROJECT: &str = r#"{
+      "meta": {"api-version": "1.1", "_last-serial": 42},
+      "name": "holygrail",
+      "files": [
+        {
+          "filename": "holygrail-1.0.tar.gz",
+          "url": "../../files/holygrail-1.0.tar.gz",
+          "hashes": {"sha256": "aaa"},
+          "requires-python": ">=3.7",
+          "yanked": "Broken build",
+          "size": 1234,
+          "upload-time": "2023-05-01T12:34:56.789012Z"
+        },
+        {
+          "filename": "holygrail-1.0-py3-none-any.whl",
+          "url": "https://files.example/holygrail-1.0-py3-none-any.whl",
+          "hashes": {"sha256": "bbb"},
+          "dist-info-metadata": {"sha256": "ccc"},
+          "yanked": false
+        },
+        {
+          "filename": "holygrail-0.9.win32.exe",
+          "url": "https://files.example/holygrail-0.9.win32.exe",
+          "hashes": {},
+          "core-metadata": true,
+          "dist-info-metadata": false
+        }
+      ],
+      "versions": ["1.0", "0.9", "1.0.0", "1.1.dev0", "bogus"]
+    }"#;
+
+    #[test]
+    fn test_project_detail() {
+        let project = ProjectDetail::parse(PROJECT).unwrap();
+        assert_eq!(project.meta.last_serial, Some(42));
+        assert_eq!(project.files.len(), 3);
+
+        let versions = project.versions();
+        let sorted: Vec<String> = versions.versions.iter().map(|v| v.to_string()).collect();
+        assert_eq!(sorted, vec!["0.9", "1.0", "1.1.dev0"]);
+        assert_eq!(versions.invalid, vec!["bogus"]);
+
+        let page = project.to_page("https://index.example/simple/holygrail/");
+        assert_eq!(page.api_version.as_deref(), Some("1.1"));
+        assert_eq!(page.diagnostics, vec![]);
+        let files = page.files;
+        assert_eq!(files[0].url, "https://index.example/files/holygrail-1.0.tar.gz");
+        assert_eq!(files[0].requires_python.as_ref().unwrap().to_string(), ">=3.7");
+        assert!(files[0].yanked);
+        assert_eq!(files[0].yanked_reason.as_deref(), Some("Broken build"));
+        assert_eq!(files[0].size, Some(1234));
+        assert_eq!(files[0].upload_time.as_deref(), Some("2023-05-01T12:34:56.789012Z"));
+        assert!(!files[1].yanked);
+        assert_eq!(files[1].core_metadata.as_ref().unwrap().hashes["sha256"], "ccc");
+        assert_eq!(files[2].core_metadata, Some(CoreMetadata::default()));
+        assert!(files[2].dist.is_none());
+    }
+
+    #[test]
+    fn test_versions_from_files() {
+        let mut project = ProjectDetail::parse(PROJECT).unwrap();
+        project.versions = None;
+        let versions = project.versions();
+        let sorted: Vec<String> = versions.versions.iter().map(|v| v.to_string()).collect();
+        assert_eq!(sorted, vec!["1.0"]);
+        assert_eq!(versions.invalid, vec!["holygrail-0.9.win32.exe"]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let project = ProjectDetail::parse(PROJECT).unwrap();
+        let json = serde_json::to_string(&project).unwrap();
+        assert_eq!(ProjectDetail::parse(&json).unwrap(), project);
+    }
+
+    #[test]
+    fn test_project_list() {
+        let list = ProjectList::parse(r#"{
+          "meta": {"api-version": "1.0"},
+          "projects": [{"name": "Frob"}, {"name": "spamspamspam", "_last-serial": 7}]
+        }"#).unwrap();
+        assert_eq!(list.projects[0].name, "Frob");
+        assert_eq!(list.projects[1].last_serial, Some(7));
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = &[
+            r#"{"meta": {"api-version": "2.0"}, "projects": []}"#,
+            r#"{"meta": {}, "projects": []}"#,
+            r#"{"projects": []"#,
+        ];
+        for json in cases {
+            assert!(ProjectList::parse(json).is_err(), "json={}", json);
+        }
+    }
+
+    #[test]
+    fn test_invalid_requires_python() {
+        let project = ProjectDetail::parse(r#"{
+          "meta": {"api-version": "1.0"}, "name": "foo",
+          "files": [
+            {"filename": "foo-1.0.tar.gz", "url": "foo-1.0.tar.gz", "hashes": {},
+             "requires-python": ">=3.x"},
+            {"filename": "foo-1.1.tar.gz", "url": "foo-1.1.tar.gz", "hashes": {},
+             "requires-python": ">=3.8"}
+          ]
+        }"#).unwrap();
+        let page = project.to_page("https://index.example/simple/foo/");
+        assert_eq!(page.files.len(), 2);
+        assert_eq!(page.files[0].requires_python, None);
+        assert_eq!(page.files[1].requires_python.as_ref().unwrap().to_string(), ">=3.8");
+        assert_eq!(page.diagnostics, vec![Diagnostic {
+            link: "foo-1.0.tar.gz".to_string(),
+            reason: "invalid requires-python `>=3.x`, ignored".to_string(),
+        }]);
+    }
+}
@@ -0,0 +1,389 @@
+//! Version specifiers, as described in the "Version specifiers" section of
+//! PEP440, e.g. `>=3.8`, `!=3.9.*` or `~=1.4.2`.
+//!
+//! The semantics, including the handling of pre-releases, are modelled after
+//! `packaging.specifiers` so that results agree with pip.
+
+use crate::error::Error;
+use crate::Version;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    /// Splits a single specifier into its operator and version. The version
+    /// is validated separately, since which forms are allowed depends on the
+    /// operator.
+    static ref SPECIFIER_RE: Regex = Regex::new(r#"(?x)
+^\s*
+(?P<operator>~=|===|==|!=|<=|>=|<|>)
+\s*
+(?P<version>[^\s;,]+)
+\s*$"#).unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// The comparison operator of a `Specifier`.
+pub enum Operator {
+    /// `~=`
+    Compatible,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<=`
+    LessThanEqual,
+    /// `>=`
+    GreaterThanEqual,
+    /// `<`
+    LessThan,
+    /// `>`
+    GreaterThan,
+    /// `===`
+    ArbitraryEqual,
+}
+
+impl Operator {
+    fn from_str(operator: &str) -> Option<Operator> {
+        match operator {
+            "~=" => Some(Operator::Compatible),
+            "==" => Some(Operator::Equal),
+            "!=" => Some(Operator::NotEqual),
+            "<=" => Some(Operator::LessThanEqual),
+            ">=" => Some(Operator::GreaterThanEqual),
+            "<" => Some(Operator::LessThan),
+            ">" => Some(Operator::GreaterThan),
+            "===" => Some(Operator::ArbitraryEqual),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Operator::Compatible => "~=",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::LessThanEqual => "<=",
+            Operator::GreaterThanEqual => ">=",
+            Operator::LessThan => "<",
+            Operator::GreaterThan => ">",
+            Operator::ArbitraryEqual => "===",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A single version specifier clause, such as `>=3.8` or `==3.9.*`.
+///
+/// `wildcard` is only ever `true` for the `==` and `!=` operators, in which
+/// case `version` is a release-only version (the part before the `.*`).
+///
+/// The `===` operator is only supported for versions which are themselves
+/// valid PEP440 versions, and matches if the normalized forms are identical.
+pub struct Specifier {
+    pub operator: Operator,
+    pub version: Version,
+    pub wildcard: bool,
+}
+
+impl Specifier {
+    /// Attempt to parse a single specifier clause.
+    ///
+    /// ```
+    /// # use pep440::specifier::{Operator, Specifier};
+    /// let spec = Specifier::parse("!= 3.9.*").unwrap();
+    /// assert_eq!(spec.operator, Operator::NotEqual);
+    /// assert!(spec.wildcard);
+    /// assert!(Specifier::parse(">=3.9.*").is_none());
+    /// ```
+    pub fn parse(input: &str) -> Option<Specifier> {
+        let captures = SPECIFIER_RE.captures(input)?;
+        let operator = Operator::from_str(captures.name("operator")?.as_str())?;
+        let raw = captures.name("version")?.as_str();
+
+        let (raw, wildcard) = match raw.strip_suffix(".*") {
+            Some(prefix) => (prefix, true),
+            None => (raw, false),
+        };
+        let version = Version::parse(raw)?;
+
+        let allowed = match operator {
+            // Wildcards are only allowed on a plain release segment.
+            Operator::Equal | Operator::NotEqual => {
+                !wildcard || (version.base() == version && version.local.is_empty())
+            }
+            // `~=1` is meaningless, there has to be something to bump.
+            Operator::Compatible => {
+                !wildcard && version.local.is_empty() && version.release.len() >= 2
+            }
+            Operator::LessThanEqual
+            | Operator::GreaterThanEqual
+            | Operator::LessThan
+            | Operator::GreaterThan => !wildcard && version.local.is_empty(),
+            Operator::ArbitraryEqual => !wildcard,
+        };
+        if !allowed {
+            return None;
+        }
+
+        Some(Specifier { operator, version, wildcard })
+    }
+
+    /// Returns `true` if this specifier explicitly asks for a pre-release,
+    /// which means that pre-releases should be considered when matching
+    /// against the set the specifier is part of.
+    pub fn prereleases(&self) -> bool {
+        match self.operator {
+            Operator::NotEqual | Operator::LessThan | Operator::GreaterThan => false,
+            _ => self.version.is_prerelease(),
+        }
+    }
+
+    /// Returns `true` if the given version satisfies this specifier.
+    ///
+    /// Pre-releases are not treated specially here; that is the job of
+    /// `SpecifierSet::contains()`.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::specifier::Specifier;
+    /// let spec = Specifier::parse("~=2.2.1").unwrap();
+    /// assert!(spec.contains(&Version::parse("2.2.5").unwrap()));
+    /// assert!(!spec.contains(&Version::parse("2.3").unwrap()));
+    /// ```
+    pub fn contains(&self, version: &Version) -> bool {
+        let spec = &self.version;
+        match self.operator {
+            Operator::Equal => self.equal(version),
+            Operator::NotEqual => !self.equal(version),
+            Operator::LessThanEqual => version.public() <= *spec,
+            Operator::GreaterThanEqual => version.public() >= *spec,
+            Operator::LessThan => {
+                // `<3.8` must not match `3.8rc1`, unless the specifier is
+                // itself a pre-release.
+                version.public() < *spec
+                    && (spec.is_prerelease()
+                        || !version.is_prerelease()
+                        || version.base() != spec.base())
+            }
+            Operator::GreaterThan => {
+                // `>3.8` must neither match `3.8.post1` (unless the specifier
+                // is itself a post-release) nor `3.8+local`.
+                version.public() > *spec
+                    && (spec.is_postrelease()
+                        || !version.is_postrelease()
+                        || version.base() != spec.base())
+                    && (version.local.is_empty() || version.base() != spec.base())
+            }
+            Operator::Compatible => {
+                let prefix = spec.truncate_release(spec.release.len() - 1);
+                version.public() >= *spec && prefix_match(version, &prefix)
+            }
+            Operator::ArbitraryEqual => version.normalize() == spec.normalize(),
+        }
+    }
+
+    fn equal(&self, version: &Version) -> bool {
+        if self.wildcard {
+            prefix_match(version, &self.version)
+        } else if self.version.local.is_empty() {
+            version.public() == self.version
+        } else {
+            *version == self.version
+        }
+    }
+}
+
+/// Returns `true` if the release of `version`, padded with zeros, starts with
+/// the release of `prefix` (and the epochs are the same).
+fn prefix_match(version: &Version, prefix: &Version) -> bool {
+    version.epoch == prefix.epoch
+        && version.truncate_release(prefix.release.len()).release == prefix.release
+}
+
+impl fmt::Display for Specifier {
+    /// ```
+    /// # use pep440::specifier::Specifier;
+    /// let spec = Specifier::parse("== 3.09.*").unwrap();
+    /// assert_eq!(spec.to_string(), "==3.9.*");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.operator, self.version)?;
+        if self.wildcard {
+            write!(f, ".*")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Specifier {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Specifier::parse(s).ok_or_else(|| Error::specifier_parse_error(s.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+/// A comma-separated set of specifiers, all of which have to match, such as
+/// `>=3.8, !=3.9.*`. The empty set matches every version.
+pub struct SpecifierSet {
+    pub specifiers: Vec<Specifier>,
+}
+
+impl SpecifierSet {
+    /// Attempt to parse a comma-separated list of specifiers.
+    ///
+    /// ```
+    /// # use pep440::specifier::SpecifierSet;
+    /// let specs = SpecifierSet::parse(">=3.8, !=3.9.*").unwrap();
+    /// assert_eq!(specs.specifiers.len(), 2);
+    /// assert!(SpecifierSet::parse("").unwrap().specifiers.is_empty());
+    /// assert!(SpecifierSet::parse(">=3.8, foo").is_none());
+    /// ```
+    pub fn parse(input: &str) -> Option<SpecifierSet> {
+        let specifiers = input
+            .split(',')
+            .filter(|clause| !clause.trim().is_empty())
+            .map(Specifier::parse)
+            .collect::<Option<Vec<Specifier>>>()?;
+        Some(SpecifierSet { specifiers })
+    }
+
+    /// Returns `true` if any of the specifiers explicitly mentions a
+    /// pre-release, in which case pre-releases are allowed to match.
+    pub fn prereleases(&self) -> bool {
+        self.specifiers.iter().any(Specifier::prereleases)
+    }
+
+    /// Returns `true` if the given version satisfies every specifier in the
+    /// set. Following PEP440, pre-releases only match if the set itself
+    /// mentions a pre-release.
+    ///
+    /// ```
+    /// # use pep440::Version;
+    /// # use pep440::specifier::SpecifierSet;
+    /// let specs = SpecifierSet::parse(">=1.0").unwrap();
+    /// assert!(!specs.contains(&Version::parse("2.0b1").unwrap()));
+    /// let specs = SpecifierSet::parse(">=1.0b1").unwrap();
+    /// assert!(specs.contains(&Version::parse("2.0b1").unwrap()));
+    /// ```
+    pub fn contains(&self, version: &Version) -> bool {
+        self.contains_with(version, self.prereleases())
+    }
+
+    /// Like `contains()`, but with explicit control over whether or not
+    /// pre-releases are allowed to match.
+    pub fn contains_with(&self, version: &Version, prereleases: bool) -> bool {
+        if !prereleases && version.is_prerelease() {
+            return false;
+        }
+        self.specifiers.iter().all(|spec| spec.contains(version))
+    }
+
+    /// Returns `true` if the set contains no specifiers at all.
+    pub fn is_empty(&self) -> bool {
+        self.specifiers.is_empty()
+    }
+}
+
+impl fmt::Display for SpecifierSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let joined = self.specifiers
+            .iter()
+            .map(|spec| spec.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}", joined)
+    }
+}
+
+impl FromStr for SpecifierSet {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SpecifierSet::parse(s).ok_or_else(|| Error::specifier_parse_error(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+/// Much of the test data here is pulled from the python-packaging tests,
+/// https://github.com/pypa/packaging/blob/main/tests/test_specifiers.py
+mod tests {
+    use crate::specifier::*;
+
+    const INVALID_SPECIFIERS: &[&str] = &[
+        "2.0", "=>2.0", "=2.0", "~=1", "~=1.0.*", "~=1.0+abc", ">=1.0.*",
+        "<1.0+abc", "==1.0a1.*", "==1.0.*+abc", "!=1.0.post1.*", "==", "",
+        ">=foo",
+    ];
+
+    // (version, specifier, expected) with pre-releases enabled.
+    const MATCHES: &[(&str, &str, bool)] = &[
+        ("2.0", "==2", true), ("2.0", "==2.0", true), ("2.0", "==2.0.0", true),
+        ("2.0+deadbeef", "==2", true), ("2.0+deadbeef", "==2.0+deadbeef", true),
+        ("2.0", "==2.0+deadbeef", false), ("2.0", "==2.*", true),
+        ("2.0.0", "==2.*", true), ("2.1+local", "==2.1.*", true),
+        ("2.0", "==2.0.*", true), ("2.0.0", "==2.0.0.*", true),
+        ("2.0rc1", "==2.0.0.*", true), ("2.1", "==2.0.*", false),
+        ("3", "==2.0.*", false), ("3.0", "!=3.0.*", false),
+        ("3.0.1", "!=3.0.*", false), ("3.1", "!=3.0.*", true),
+        ("2.0.dev1", "!=2.0", true), ("2.0", "~=2.0", true),
+        ("2.2.0", "~=2.0", true), ("2.0.5", "~=2.0.0", true),
+        ("2.1", "~=2.0.0", false), ("2.0", "~=2.0.1", false),
+        ("3.0", "~=2.0", false), ("1!2.0", "~=2.0", false),
+        ("2.0.post1", "~=2.0", true), ("2.0a1", "<2.0", false),
+        ("2.0a1", "<2.0a2", true), ("1.9", "<2.0", true),
+        ("2.0", "<2.0", false), ("2.0.post1", ">2.0", false),
+        ("2.0.post2", ">2.0.post1", true), ("2.0+local", ">2.0", false),
+        ("2.1", ">2.0", true), ("2.0", ">=2.0", true),
+        ("2.0+local", ">=2.0", true), ("2.0", "<=2.0", true),
+        ("2.0+local", "<=2.0", true), ("2.0.1", "<=2.0", false),
+        ("1.0", "===1.0", true), ("1.0", "===1.0.0", false),
+    ];
+
+    #[test]
+    fn test_invalid() {
+        for spec in INVALID_SPECIFIERS {
+            assert!(
+                Specifier::parse(spec).is_none(),
+                "Parsed specifier but should not have: '{}'", spec);
+            assert!(Specifier::from_str(spec).is_err());
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        for (version, spec, expected) in MATCHES {
+            let parsed = Specifier::parse(spec).expect(spec);
+            let version = Version::parse(version).expect(version);
+            assert_eq!(
+                parsed.contains(&version),
+                *expected,
+                "version={}, spec={}", version, spec);
+        }
+    }
+
+    #[test]
+    fn test_set_prereleases() {
+        let rc = Version::parse("3.13.0rc1").unwrap();
+        let set = SpecifierSet::parse(">=3.8").unwrap();
+        assert!(!set.prereleases());
+        assert!(!set.contains(&rc));
+        assert!(set.contains_with(&rc, true));
+
+        let set = SpecifierSet::parse(">=3.13.0rc1").unwrap();
+        assert!(set.contains(&rc));
+
+        // `!=` never enables pre-releases.
+        let set = SpecifierSet::parse("!=3.13.0rc1").unwrap();
+        assert!(!set.prereleases());
+    }
+
+    #[test]
+    fn test_set_display() {
+        let set: SpecifierSet = " >= 3.8 ,!=3.9.*,".parse().unwrap();
+        assert_eq!(set.to_string(), ">=3.8, !=3.9.*");
+    }
+}
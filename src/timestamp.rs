@@ -0,0 +1,175 @@
+//! UTC timestamps, as used for the upload times of the JSON simple API
+//! (PEP700), which are RFC3339 strings such as `2023-05-01T12:34:56.789012Z`.
+
+use crate::error::Error;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    static ref TIMESTAMP_RE: Regex = Regex::new(r"(?x)
+^\s*
+(?P<year>[0-9]{4})-(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+(?:
+    [Tt\x20]
+    (?P<hour>[0-9]{2}):(?P<minute>[0-9]{2}):(?P<second>[0-9]{2})
+    (?:\.(?P<fraction>[0-9]{1,9})[0-9]*)?
+    (?:(?P<utc>[Zz])|(?P<offset>[+-][0-9]{2}:[0-9]{2}))
+)?
+\s*$").unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// A point in time, with nanosecond precision.
+pub struct Timestamp {
+    /// Seconds since the Unix epoch.
+    pub seconds: i64,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    /// Attempt to parse an RFC3339 timestamp. A bare date such as
+    /// `2023-05-01` is also accepted and means midnight UTC.
+    ///
+    /// ```
+    /// # use pep440::timestamp::Timestamp;
+    /// let upload = Timestamp::parse("2023-05-01T14:34:56.5+02:00").unwrap();
+    /// assert_eq!(upload.to_string(), "2023-05-01T12:34:56.500000000Z");
+    /// assert!(upload > Timestamp::parse("2023-05-01").unwrap());
+    /// assert!(Timestamp::parse("2023-02-29").is_none());
+    /// ```
+    pub fn parse(input: &str) -> Option<Timestamp> {
+        let captures = TIMESTAMP_RE.captures(input)?;
+        let field = |name: &str| -> i64 {
+            captures.name(name).map(|m| m.as_str().parse().unwrap()).unwrap_or(0)
+        };
+
+        let (year, month, day) = (field("year"), field("month"), field("day"));
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        let (hour, minute, second) = (field("hour"), field("minute"), field("second"));
+        // A leap second is folded into the following second.
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+        let offset = match captures.name("offset") {
+            Some(offset) => {
+                let sign = if offset.as_str().starts_with('-') { -1 } else { 1 };
+                let hours: i64 = offset.as_str()[1..3].parse().unwrap();
+                let minutes: i64 = offset.as_str()[4..6].parse().unwrap();
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                sign * (hours * 3600 + minutes * 60)
+            }
+            None => 0,
+        };
+        let nanos = captures.name("fraction").map_or(0, |fraction| {
+            let digits = fraction.as_str();
+            digits.parse::<u32>().unwrap() * 10u32.pow(9 - digits.len() as u32)
+        });
+
+        let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+        Some(Timestamp { seconds, nanos })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// Formats the timestamp in UTC, with nanoseconds only if there are any.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.seconds.div_euclid(86400);
+        let time = self.seconds.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, time / 3600, time / 60 % 60, time % 60)?;
+        if self.nanos != 0 {
+            write!(f, ".{:09}", self.nanos)?;
+        }
+        write!(f, "Z")
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Timestamp::parse(s).ok_or_else(|| Error::timestamp_parse_error(s.to_string()))
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days between 1970-01-01 and the given date, after Howard
+/// Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timestamp::*;
+
+    #[test]
+    fn test_parse() {
+        let cases: &[(&str, Option<i64>)] = &[
+            ("1970-01-01T00:00:00Z", Some(0)),
+            ("1970-01-01", Some(0)),
+            ("2000-03-01T00:00:00Z", Some(951868800)),
+            ("2023-05-01T12:34:56.789012Z", Some(1682944496)),
+            ("2023-05-01 14:34:56+02:00", Some(1682944496)),
+            ("2023-05-01T07:34:56-05:00", Some(1682944496)),
+            ("1969-12-31T23:59:59Z", Some(-1)),
+            ("2024-02-29", Some(1709164800)),
+            ("2023-02-29", None),
+            ("2023-13-01", None),
+            ("2023-05-01T24:00:00Z", None),
+            ("2023-05-01T12:34:56", None),
+            ("2023-05-01T12:34Z", None),
+            ("May 1st", None),
+        ];
+        for (input, expected) in cases {
+            let actual = Timestamp::parse(input).map(|timestamp| timestamp.seconds);
+            assert_eq!(actual, *expected, "input={}", input);
+        }
+        assert_eq!(Timestamp::parse("2023-05-01T12:34:56.789012Z").unwrap().nanos, 789012000);
+    }
+
+    #[test]
+    fn test_display() {
+        for input in &["1970-01-01T00:00:00Z", "1969-12-31T23:59:59Z", "2024-02-29T23:00:00.000000001Z"] {
+            assert_eq!(&Timestamp::parse(input).unwrap().to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Uninstallation of a distribution from a `site-packages` directory, by
+//! removing the files listed in its `RECORD`, like `pip uninstall`.
+
+use crate::error::Error;
+use crate::installed::{DistributionKind, SitePackages};
+use crate::name::PackageName;
+use crate::record::Record;
+use crate::Version;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// Uninstalls distributions.
+pub struct Uninstaller {
+    /// Only list what would be removed.
+    pub dry_run: bool,
+    /// Refuse to uninstall any other version.
+    pub expected_version: Option<Version>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// What was, or in a dry run would be, removed.
+pub struct Uninstallation {
+    pub name: PackageName,
+    pub version: Version,
+    /// The files, sorted. Recorded files which don't exist are left out.
+    pub files: Vec<PathBuf>,
+    /// The directories inside `site-packages` which were left empty and
+    /// removed, deepest first. Always empty in a dry run.
+    pub directories: Vec<PathBuf>,
+    /// The `RECORD` paths which were not removed because they are absolute
+    /// or outside the environment prefix.
+    pub skipped: Vec<String>,
+}
+
+impl Uninstaller {
+    /// Uninstalls the distribution `name` from `site_packages`, which must
+    /// be inside the environment `prefix`.
+    ///
+    /// Like pip, only files inside `prefix` are removed: absolute `RECORD`
+    /// paths, and paths which lead out of `prefix` through `..` components
+    /// or symbolic links, are skipped. Besides the recorded files, the whole
+    /// `.dist-info` directory, including unrecorded files in subdirectories
+    /// such as `licenses`, and the compiled `__pycache__` files of recorded
+    /// `.py` files are removed. Afterwards, the directories inside `site_packages` which are left
+    /// empty are removed; directories outside it, such as the scripts
+    /// directory, are kept.
+    ///
+    /// ```no_run
+    /// # use pep440::name::PackageName;
+    /// # use pep440::uninstall::Uninstaller;
+    /// # use pep440::Version;
+    /// let uninstaller = Uninstaller { dry_run: true, expected_version: Version::parse("1.16.0") };
+    /// let six = PackageName::parse("six").unwrap();
+    /// let plan = uninstaller.uninstall("/opt/venv", "/opt/venv/lib/python3.12/site-packages", &six).unwrap();
+    /// for file in &plan.files {
+    ///     println!("Would remove {}", file.display());
+    /// }
+    /// ```
+    pub fn uninstall<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        prefix: P,
+        site_packages: Q,
+        name: &PackageName,
+    ) -> Result<Uninstallation, Error> {
+        let prefix = Prefix::new(prefix.as_ref())?;
+        let root = site_packages.as_ref();
+        if !prefix.contains(root) {
+            return Err(error(name, format!("{} is not inside {}", root.display(), prefix.lexical.display())));
+        }
+        let installed = SitePackages::scan(root)?;
+        let distribution = match installed.get(name) {
+            [distribution] => distribution,
+            [] => return Err(error(name, "not installed".to_string())),
+            distributions => {
+                let versions: Vec<String> = distributions.iter().map(|dist| dist.version.to_string()).collect();
+                return Err(error(name, format!("installed more than once ({})", versions.join(", "))));
+            }
+        };
+        if let Some(expected) = &self.expected_version {
+            if distribution.version != *expected {
+                return Err(error(
+                    name,
+                    format!("version {} is installed, not {}", distribution.version, expected)));
+            }
+        }
+        if distribution.kind != DistributionKind::DistInfo {
+            return Err(error(name, "only `.dist-info` installs have a RECORD".to_string()));
+        }
+        let record = Record::read(distribution)?;
+
+        let mut files = BTreeSet::new();
+        let mut skipped = vec![];
+        for entry in &record.entries {
+            let path = normalize(&root.join(&entry.path));
+            if Path::new(&entry.path).is_absolute() || !prefix.contains(&path) {
+                skipped.push(entry.path.clone());
+                continue;
+            }
+            if path.is_file() {
+                files.insert(path.clone());
+            }
+            if path.extension().is_some_and(|extension| extension == "py") {
+                files.extend(compiled_files(&path));
+            }
+        }
+        walk(&distribution.path, &mut files)?;
+
+        let mut uninstallation = Uninstallation {
+            name: distribution.name.clone(),
+            version: distribution.version.clone(),
+            files: files.into_iter().collect(),
+            directories: vec![],
+            skipped,
+        };
+        if self.dry_run {
+            return Ok(uninstallation);
+        }
+
+        for file in &uninstallation.files {
+            fs::remove_file(file).map_err(|err| Error::io_error(file, err))?;
+        }
+        let root = normalize(root);
+        let mut directories: BTreeSet<PathBuf> = BTreeSet::new();
+        for file in &uninstallation.files {
+            let inside = file
+                .ancestors()
+                .skip(1)
+                .take_while(|directory| directory.starts_with(&root) && *directory != root);
+            directories.extend(inside.map(Path::to_path_buf));
+        }
+        // Deepest first, so that parents are empty by the time they're tried.
+        let mut directories: Vec<PathBuf> = directories.into_iter().collect();
+        directories.sort_by_key(|directory| std::cmp::Reverse(directory.components().count()));
+        for directory in directories {
+            let empty = fs::read_dir(&directory).is_ok_and(|mut entries| entries.next().is_none());
+            if empty {
+                fs::remove_dir(&directory).map_err(|err| Error::io_error(&directory, err))?;
+                uninstallation.directories.push(directory);
+            }
+        }
+        Ok(uninstallation)
+    }
+}
+
+fn error(name: &PackageName, reason: String) -> Error {
+    Error::uninstall_error(format!("{}: {}", name, reason))
+}
+
+/// The environment prefix, which nothing outside of is removed.
+struct Prefix {
+    lexical: PathBuf,
+    canonical: PathBuf,
+}
+
+impl Prefix {
+    fn new(prefix: &Path) -> Result<Prefix, Error> {
+        let canonical = fs::canonicalize(prefix).map_err(|err| Error::io_error(prefix, err))?;
+        Ok(Prefix { lexical: normalize(prefix), canonical })
+    }
+
+    /// Returns `true` if `path` is inside the prefix, both lexically and
+    /// once the symbolic links of its existing ancestors are resolved.
+    fn contains(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        if !path.starts_with(&self.lexical) {
+            return false;
+        }
+        match path.ancestors().skip(1).find(|ancestor| ancestor.exists()) {
+            Some(ancestor) => fs::canonicalize(ancestor).is_ok_and(|ancestor| ancestor.starts_with(&self.canonical)),
+            None => false,
+        }
+    }
+}
+
+/// Resolves `.` and `..` components lexically, since `RECORD` paths of
+/// scripts go up out of `site-packages`.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Collects the files under `directory`, recursively. Symbolic links are
+/// collected rather than followed.
+fn walk(directory: &Path, files: &mut BTreeSet<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(directory).map_err(|err| Error::io_error(directory, err))? {
+        let entry = entry.map_err(|err| Error::io_error(directory, err))?;
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => walk(&path, files)?,
+            Ok(_) => {
+                files.insert(path);
+            }
+            Err(err) => return Err(Error::io_error(&path, err)),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the `__pycache__/{stem}.*.pyc` files of a module.
+fn compiled_files(module: &Path) -> Vec<PathBuf> {
+    let (directory, stem) = match (module.parent(), module.file_stem().and_then(|stem| stem.to_str())) {
+        (Some(directory), Some(stem)) => (directory.join("__pycache__"), format!("{}.", stem)),
+        _ => return vec![],
+    };
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&stem) && name.ends_with(".pyc"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scratch::ScratchDir;
+    use crate::uninstall::*;
+
+    #[test]
+    fn test_uninstall() {
+        let dir = ScratchDir::new("uninstall");
+        let prefix = dir.join("env");
+        let root = prefix.join("lib/site-packages");
+        let write = |path: &Path| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        };
+        for path in &[
+            "spam/__init__.py",
+            "spam/sub/mod.py",
+            "spam/__pycache__/__init__.cpython-312.pyc",
+            "spam/__pycache__/other.cpython-312.pyc",
+            "spam-1.0.dist-info/REQUESTED",
+            "spam-1.0.dist-info/licenses/LICENSE",
+            "eggs/__init__.py",
+        ] {
+            write(&root.join(path));
+        }
+        write(&prefix.join("bin/spam"));
+        write(&dir.join("secret"));
+        write(&dir.join("other-secret"));
+        fs::write(
+            root.join("spam-1.0.dist-info/METADATA"),
+            "Metadata-Version: 2.1\nName: spam\nVersion: 1.0\n").unwrap();
+        fs::write(
+            root.join("spam-1.0.dist-info/RECORD"),
+            format!(
+                "spam/__init__.py,,\n\
+                 spam/sub/mod.py,,\n\
+                 spam/gone.py,,\n\
+                 ../../bin/spam,,\n\
+                 ../../../other-secret,,\n\
+                 {},,\n\
+                 spam-1.0.dist-info/METADATA,,\n\
+                 spam-1.0.dist-info/RECORD,,\n",
+                dir.join("secret").display())).unwrap();
+
+        let spam = PackageName::parse("spam").unwrap();
+        let dry_run = Uninstaller { dry_run: true, expected_version: None }.uninstall(&prefix, &root, &spam).unwrap();
+        let files: Vec<String> = dry_run
+            .files
+            .iter()
+            .map(|file| file.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        assert_eq!(files, vec![
+            "env/bin/spam",
+            "env/lib/site-packages/spam/__init__.py",
+            "env/lib/site-packages/spam/__pycache__/__init__.cpython-312.pyc",
+            "env/lib/site-packages/spam/sub/mod.py",
+            "env/lib/site-packages/spam-1.0.dist-info/METADATA",
+            "env/lib/site-packages/spam-1.0.dist-info/RECORD",
+            "env/lib/site-packages/spam-1.0.dist-info/REQUESTED",
+            "env/lib/site-packages/spam-1.0.dist-info/licenses/LICENSE",
+        ]);
+        assert_eq!(dry_run.skipped, vec!["../../../other-secret".to_string(), dir.join("secret").display().to_string()]);
+        assert!(root.join("spam/__init__.py").exists());
+
+        let wrong_version = Uninstaller { dry_run: false, expected_version: Version::parse("1.0.post1") };
+        let err = wrong_version.uninstall(&prefix, &root, &spam).unwrap_err();
+        assert_eq!(err.to_string(), "Uninstallation failed: spam: version 1.0 is installed, not 1.0.post1");
+        let err = Uninstaller::default().uninstall(&prefix, &root, &PackageName::parse("eggs").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Uninstallation failed: eggs: not installed");
+
+        let uninstaller = Uninstaller { dry_run: false, expected_version: Version::parse("1.0") };
+        let done = uninstaller.uninstall(&prefix, &root, &spam).unwrap();
+        assert_eq!(done.files, dry_run.files);
+        let directories: Vec<String> = done
+            .directories
+            .iter()
+            .map(|directory| directory.strip_prefix(&root).unwrap().display().to_string())
+            .collect();
+        assert_eq!(directories, vec!["spam/sub", "spam-1.0.dist-info/licenses", "spam-1.0.dist-info"]);
+        assert!(!root.join("spam-1.0.dist-info").exists());
+        // The unrecorded compiled file keeps `spam` around, and `bin` is
+        // outside `site-packages`.
+        assert!(root.join("spam/__pycache__/other.cpython-312.pyc").exists());
+        assert!(prefix.join("bin").is_dir());
+        assert!(!prefix.join("bin/spam").exists());
+        assert!(dir.join("secret").exists());
+        assert!(dir.join("other-secret").exists());
+        assert!(root.join("eggs/__init__.py").exists());
+    }
+}
@@ -0,0 +1,361 @@
+//! Validation of a wheel archive before it is published, like
+//! `check-wheel-contents`: the filename, the `.dist-info` directory,
+//! `METADATA` and `WHEEL` must agree, and `RECORD` must list every file with
+//! its correct hash.
+//!
+//! This module requires the `installer` feature.
+
+use crate::error::Error;
+use crate::filename::WheelFilename;
+use crate::install::{is_record, is_safe_path, read_archive, WheelEntry};
+use crate::metadata::{Metadata, WheelMetadata};
+use crate::name::PackageName;
+use crate::record::{FileProblem, FileProblemKind, Record, RecordHash};
+use crate::Version;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Something wrong with a wheel.
+pub enum WheelProblem {
+    /// A path which is absolute or goes up with `..`.
+    UnsafePath { path: String },
+    /// There is no `.dist-info` directory, so nothing else was checked.
+    MissingDistInfo,
+    /// There are several `.dist-info` directories, so nothing else was
+    /// checked.
+    MultipleDistInfo { directories: Vec<String> },
+    /// The `.dist-info` directory isn't named with the escaped name and
+    /// normalized version of the filename.
+    DistInfoName { expected: String, actual: String },
+    /// `METADATA`, `WHEEL` or `RECORD` is missing from the `.dist-info`
+    /// directory.
+    MissingFile { path: String },
+    /// `METADATA`, `WHEEL` or `RECORD` can't be parsed.
+    InvalidFile { path: String, reason: String },
+    /// The `Name` in `METADATA` isn't the filename's, after normalization.
+    NameMismatch { filename: PackageName, metadata: String },
+    /// The `Version` in `METADATA` doesn't normalize to the filename's.
+    VersionMismatch { filename: Version, metadata: Version },
+    /// The major `Wheel-Version` isn't supported.
+    UnsupportedWheelVersion { version: Version },
+    /// The `Tag`s in `WHEEL` aren't the tags of the filename. Both lists
+    /// are sorted.
+    TagMismatch { filename: Vec<String>, wheel: Vec<String> },
+    /// A file without a hash in `RECORD`.
+    Unhashed { path: String },
+    /// A file whose `RECORD` hash uses an algorithm that can't be checked.
+    UnsupportedHash { path: String, algorithm: String },
+    /// A file which isn't recorded, is recorded but missing, or doesn't
+    /// match its recorded hash or size.
+    File(FileProblem),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The result of validating a wheel.
+pub struct WheelCheck {
+    pub filename: WheelFilename,
+    pub problems: Vec<WheelProblem>,
+}
+
+impl WheelCheck {
+    /// Validates the wheel at `wheel`. Only a filename which isn't a valid
+    /// wheel filename, or an archive which can't be read, is an error;
+    /// everything else is reported as a problem.
+    ///
+    /// ```no_run
+    /// # use pep440::wheel_check::WheelCheck;
+    /// let check = WheelCheck::check("dist/spam-1.0-py3-none-any.whl").unwrap();
+    /// for problem in &check.problems {
+    ///     println!("{}: {}", check.filename, problem);
+    /// }
+    /// ```
+    pub fn check<P: AsRef<Path>>(wheel: P) -> Result<WheelCheck, Error> {
+        let wheel = wheel.as_ref();
+        let filename = wheel
+            .file_name()
+            .and_then(|filename| filename.to_str())
+            .and_then(WheelFilename::parse)
+            .ok_or_else(|| Error::archive_error(wheel, "invalid wheel filename".to_string()))?;
+        let entries = read_archive(wheel)?;
+        let problems = check_entries(&filename, &entries);
+        Ok(WheelCheck { filename, problems })
+    }
+
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+fn check_entries(filename: &WheelFilename, entries: &[WheelEntry]) -> Vec<WheelProblem> {
+    let mut problems: Vec<WheelProblem> = entries
+        .iter()
+        .filter(|entry| !is_safe_path(&entry.path))
+        .map(|entry| WheelProblem::UnsafePath { path: entry.path.clone() })
+        .collect();
+
+    let directories: BTreeSet<&str> = entries
+        .iter()
+        .filter_map(|entry| entry.path.split_once('/'))
+        .map(|(top, _)| top)
+        .filter(|top| top.ends_with(".dist-info"))
+        .collect();
+    let dist_info = match directories.iter().collect::<Vec<_>>().as_slice() {
+        [directory] => directory.to_string(),
+        [] => {
+            problems.push(WheelProblem::MissingDistInfo);
+            return problems;
+        }
+        _ => {
+            let directories = directories.iter().map(|directory| directory.to_string()).collect();
+            problems.push(WheelProblem::MultipleDistInfo { directories });
+            return problems;
+        }
+    };
+    let expected = format!("{}-{}.dist-info", filename.name.as_dist_info_name(), filename.version.normalize());
+    if dist_info != expected {
+        problems.push(WheelProblem::DistInfoName { expected, actual: dist_info.clone() });
+    }
+
+    let mut read = |name: &str| -> Option<(String, String)> {
+        let path = format!("{}/{}", dist_info, name);
+        let contents = match entries.iter().find(|entry| entry.path == path) {
+            Some(entry) => String::from_utf8(entry.contents.clone()),
+            None => {
+                problems.push(WheelProblem::MissingFile { path });
+                return None;
+            }
+        };
+        match contents {
+            Ok(contents) => Some((path, contents)),
+            Err(_) => {
+                problems.push(WheelProblem::InvalidFile { path, reason: "not UTF-8".to_string() });
+                None
+            }
+        }
+    };
+    let metadata = read("METADATA");
+    let wheel_metadata = read("WHEEL");
+    let record = read("RECORD");
+
+    if let Some((path, contents)) = metadata {
+        match Metadata::parse(&contents) {
+            Ok(metadata) => {
+                if metadata.name != filename.name {
+                    problems.push(WheelProblem::NameMismatch {
+                        filename: filename.name.clone(),
+                        metadata: metadata.raw_name,
+                    });
+                }
+                if metadata.version.normalize() != filename.version.normalize() {
+                    problems.push(WheelProblem::VersionMismatch {
+                        filename: filename.version.clone(),
+                        metadata: metadata.version,
+                    });
+                }
+            }
+            Err(err) => problems.push(WheelProblem::InvalidFile { path, reason: err.to_string() }),
+        }
+    }
+
+    if let Some((path, contents)) = wheel_metadata {
+        match WheelMetadata::parse(&contents) {
+            Ok(wheel_metadata) => {
+                if !wheel_metadata.is_supported() {
+                    problems.push(WheelProblem::UnsupportedWheelVersion { version: wheel_metadata.wheel_version });
+                }
+                let expected: BTreeSet<String> = filename
+                    .tags()
+                    .into_iter()
+                    .map(|(python, abi, platform)| format!("{}-{}-{}", python, abi, platform))
+                    .collect();
+                let actual: BTreeSet<String> = wheel_metadata.tags.into_iter().collect();
+                if actual != expected {
+                    problems.push(WheelProblem::TagMismatch {
+                        filename: expected.into_iter().collect(),
+                        wheel: actual.into_iter().collect(),
+                    });
+                }
+            }
+            Err(err) => problems.push(WheelProblem::InvalidFile { path, reason: err.to_string() }),
+        }
+    }
+
+    if let Some((path, contents)) = record {
+        match Record::parse(&contents) {
+            Ok(record) => check_record(entries, &record, &dist_info, &mut problems),
+            Err(err) => problems.push(WheelProblem::InvalidFile { path, reason: err.to_string() }),
+        }
+    }
+    problems
+}
+
+/// Checks every file against `RECORD`, and that every recorded file exists.
+fn check_record(entries: &[WheelEntry], record: &Record, dist_info: &str, problems: &mut Vec<WheelProblem>) {
+    let file_problem = |path: &str, kind| WheelProblem::File(FileProblem { path: path.to_string(), kind });
+    for entry in entries {
+        if is_record(&entry.path, dist_info) {
+            continue;
+        }
+        let recorded = match record.get(&entry.path) {
+            Some(recorded) => recorded,
+            None => {
+                problems.push(file_problem(&entry.path, FileProblemKind::Unlisted));
+                continue;
+            }
+        };
+        let expected = match &recorded.hash {
+            Some(expected) => expected,
+            None => {
+                problems.push(WheelProblem::Unhashed { path: entry.path.clone() });
+                continue;
+            }
+        };
+        match RecordHash::compute(&expected.algorithm, &entry.contents) {
+            Some(actual) if actual != *expected => {
+                let expected = expected.clone();
+                problems.push(file_problem(&entry.path, FileProblemKind::Modified { expected, actual }));
+            }
+            Some(_) => {}
+            None => problems.push(WheelProblem::UnsupportedHash {
+                path: entry.path.clone(),
+                algorithm: expected.algorithm.clone(),
+            }),
+        }
+        let actual = entry.contents.len() as u64;
+        if let Some(expected) = recorded.size.filter(|size| *size != actual) {
+            problems.push(file_problem(&entry.path, FileProblemKind::SizeMismatch { expected, actual }));
+        }
+    }
+    for recorded in &record.entries {
+        if !entries.iter().any(|entry| entry.path == recorded.path) {
+            problems.push(file_problem(&recorded.path, FileProblemKind::Missing));
+        }
+    }
+}
+
+impl fmt::Display for WheelProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WheelProblem::UnsafePath { path } => write!(f, "unsafe path `{}`", path),
+            WheelProblem::MissingDistInfo => write!(f, "no `.dist-info` directory"),
+            WheelProblem::MultipleDistInfo { directories } => {
+                write!(f, "more than one `.dist-info` directory: {}", directories.join(", "))
+            }
+            WheelProblem::DistInfoName { expected, actual } => {
+                write!(f, "`{}` should be named `{}`", actual, expected)
+            }
+            WheelProblem::MissingFile { path } => write!(f, "missing `{}`", path),
+            WheelProblem::InvalidFile { path, reason } => write!(f, "`{}`: {}", path, reason),
+            WheelProblem::NameMismatch { filename, metadata } => {
+                write!(f, "name `{}` in METADATA doesn't match the filename's {}", metadata, filename)
+            }
+            WheelProblem::VersionMismatch { filename, metadata } => {
+                write!(f, "version {} in METADATA doesn't match the filename's {}", metadata, filename)
+            }
+            WheelProblem::UnsupportedWheelVersion { version } => {
+                write!(f, "unsupported `Wheel-Version` {}", version)
+            }
+            WheelProblem::TagMismatch { filename, wheel } => write!(
+                f,
+                "tags in WHEEL ({}) don't match the filename's ({})",
+                wheel.join(", "),
+                filename.join(", ")),
+            WheelProblem::Unhashed { path } => write!(f, "{}: no hash in RECORD", path),
+            WheelProblem::UnsupportedHash { path, algorithm } => {
+                write!(f, "{}: unsupported hash algorithm `{}`", path, algorithm)
+            }
+            WheelProblem::File(problem) => write!(f, "{}", problem),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::install::tests::{write_wheel, WheelFiles, METADATA, WHEEL};
+    use crate::scratch::ScratchDir;
+    use crate::wheel_check::*;
+
+    #[test]
+    fn test_check() {
+        let dir = ScratchDir::new("wheel-check");
+        let check = |filename: &str, files: WheelFiles| -> Vec<String> {
+            let wheel = dir.join(filename);
+            write_wheel(&wheel, &files);
+            let check = WheelCheck::check(&wheel).unwrap();
+            assert_eq!(check.is_ok(), check.problems.is_empty());
+            check.problems.iter().map(|problem| problem.to_string()).collect()
+        };
+
+        let valid = check("spam_eggs-1.0-py3-none-any.whl", vec![
+            ("spam_eggs/__init__.py", b""),
+            ("spam_eggs-1.0.dist-info/METADATA", METADATA),
+            ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+        ]);
+        assert!(valid.is_empty(), "{:?}", valid);
+
+        // Equal, but not the same normalized version.
+        let padded: &[u8] = b"Metadata-Version: 2.1\nName: spam-eggs\nVersion: 1.0.0\n";
+        let universal: &[u8] = b"Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py2-none-any\nTag: py3-none-any\n";
+        let problems = check("Spam.Eggs-1.0-py2.py3-none-any.whl", vec![
+            ("Spam.Eggs-1.0.dist-info/METADATA", padded),
+            ("Spam.Eggs-1.0.dist-info/WHEEL", universal),
+        ]);
+        assert_eq!(problems, vec![
+            "`Spam.Eggs-1.0.dist-info` should be named `spam_eggs-1.0.dist-info`",
+            "version 1.0.0 in METADATA doesn't match the filename's 1.0",
+        ]);
+
+        let other_name: &[u8] = b"Metadata-Version: 2.1\nName: ham\nVersion: 1.0\n";
+        let problems = check("spam_eggs-1.0-cp312-cp312-manylinux_2_17_x86_64.whl", vec![
+            ("../evil.py", b""),
+            ("spam_eggs-1.0.dist-info/METADATA", other_name),
+            ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+        ]);
+        assert_eq!(problems, vec![
+            "unsafe path `../evil.py`",
+            "name `ham` in METADATA doesn't match the filename's spam-eggs",
+            "tags in WHEEL (py3-none-any) don't match the filename's (cp312-cp312-manylinux_2_17_x86_64)",
+        ]);
+
+        let problems = check("spam_eggs-1.0-py3-none-any.whl", vec![
+            ("spam_eggs-1.0.dist-info/METADATA", METADATA),
+            ("spam_eggs-1.0.dist-info/WHEEL", WHEEL),
+            ("spam_eggs-1.0.dist-info/RECORD", b"\
+spam_eggs-1.0.dist-info/METADATA,sha256=AAAA,1
+spam_eggs-1.0.dist-info/WHEEL,,
+spam_eggs/gone.py,md5=AAAA,0
+spam_eggs-1.0.dist-info/RECORD,,
+"),
+            ("spam_eggs/__init__.py", b""),
+        ]);
+        assert_eq!(problems, vec![
+            format!(
+                "spam_eggs-1.0.dist-info/METADATA: hash is {}, but sha256=AAAA is recorded",
+                RecordHash::sha256(METADATA)),
+            format!("spam_eggs-1.0.dist-info/METADATA: size is {}, but 1 is recorded", METADATA.len()),
+            "spam_eggs-1.0.dist-info/WHEEL: no hash in RECORD".to_string(),
+            "spam_eggs/__init__.py: not recorded".to_string(),
+            "spam_eggs/gone.py: missing".to_string(),
+        ]);
+
+        let problems = check("spam_eggs-1.0-py3-none-any.whl", vec![
+            ("spam_eggs-1.0.dist-info/WHEEL", b"Wheel-Version: 2.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n"),
+            ("ham-1.0.dist-info/WHEEL", WHEEL),
+        ]);
+        assert_eq!(problems, vec!["more than one `.dist-info` directory: ham-1.0.dist-info, spam_eggs-1.0.dist-info"]);
+
+        let problems = check("spam_eggs-1.0-py3-none-any.whl", vec![
+            ("spam_eggs-1.0.dist-info/WHEEL", b"Wheel-Version: 2.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n"),
+        ]);
+        assert_eq!(problems, vec![
+            "missing `spam_eggs-1.0.dist-info/METADATA`",
+            "unsupported `Wheel-Version` 2.0",
+        ]);
+
+        let err = WheelCheck::check(dir.join("spam.whl")).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid archive {}: invalid wheel filename", dir.join("spam.whl").display()));
+    }
+}
@@ -1,3 +1,6 @@
+// The negated comparisons below are deliberate: they exercise each operator.
+#![allow(clippy::nonminimal_bool)]
+
 use pep440::Version;
 use std::fs::File;
 use std::hash::{Hash, Hasher};
@@ -12,7 +15,7 @@ struct TestHasher {
 
 impl Hasher for TestHasher {
     fn finish(&self) -> u64 {
-        return 0;
+        0
     }
 
     fn write(&mut self, bytes: &[u8]) {
@@ -36,26 +39,26 @@ fn test_comparison() {
 
         // Excuse the ugly parsing
         let split: Vec<&str> = text.split_whitespace().collect();
-        let x = split.get(0).expect("Malformed input");
+        let x = split.first().expect("Malformed input");
         let op = split.get(1).expect("Malformed input");
         let y = split.get(2).expect("Malformed input");
 
-        let x_ver = Version::parse(x).expect(&format!("Could not parse LHS version: {}", x));
-        let y_ver = Version::parse(y).expect(&format!("Could not parse RHS version: {}", y));
+        let x_ver = Version::parse(x).unwrap_or_else(|| panic!("Could not parse LHS version: {}", x));
+        let y_ver = Version::parse(y).unwrap_or_else(|| panic!("Could not parse RHS version: {}", y));
 
-        match op {
-            &">" => assert!(x_ver > y_ver, "Failed: {} > {}", x_ver, y_ver),
-            &">=" => assert!(x_ver >= y_ver, "Failed: {} >= {}", x_ver, y_ver),
-            &"<" => assert!(x_ver < y_ver, "Failed: {} < {}", x_ver, y_ver),
-            &"<=" => assert!(x_ver <= y_ver, "Failed: {} <= {}", x_ver, y_ver),
-            &"==" => {
-                assert!(&x_ver == &y_ver, "Failed: {} == {}", x_ver, y_ver);
+        match *op {
+            ">" => assert!(x_ver > y_ver, "Failed: {} > {}", x_ver, y_ver),
+            ">=" => assert!(x_ver >= y_ver, "Failed: {} >= {}", x_ver, y_ver),
+            "<" => assert!(x_ver < y_ver, "Failed: {} < {}", x_ver, y_ver),
+            "<=" => assert!(x_ver <= y_ver, "Failed: {} <= {}", x_ver, y_ver),
+            "==" => {
+                assert!(x_ver == y_ver, "Failed: {} == {}", x_ver, y_ver);
                 assert!(
                     hash_state(&x_ver) == hash_state(&y_ver),
                     "Failed: hash(x) == hash(y)"
                 );
             },
-            &"!=" => {
+            "!=" => {
                 assert!(x_ver != y_ver, "Failed: {} != {}", x_ver, y_ver);
                 assert!(
                     hash_state(&x_ver) != hash_state(&y_ver),